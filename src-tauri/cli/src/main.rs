@@ -0,0 +1,920 @@
+use std::{path::PathBuf, process::ExitCode};
+
+use chembank_core::{
+    ArchiveReport, Chembank, ChembankError, ExportOptions, ExportSubset, ImportMode, ImportOptions,
+    ImportReport, Job, MatchBy, PropertyRange, Resolution, Result, SchemaReport, SdfField,
+    SdfOptions, SearchFilter, SearchHit, Settings, SortKey, StructureDetail, XlsxOptions,
+};
+use clap::{Parser, Subcommand, ValueEnum};
+use comfy_table::Table;
+use serde::Serialize;
+use serde_json::Value;
+
+use entities::*;
+
+#[cfg(feature = "server")]
+mod server;
+
+/// 与`tauri.conf.json`中的`identifier`一致，使命令行与图形界面共用配置和数据目录
+const IDENTIFIER: &str = "com.chembank.app";
+
+/// 化合物库命令行工具，可在没有图形界面的服务器上导入、导出、检索和检查数据库
+#[derive(Parser, Debug)]
+#[command(name = "chembank-cli", version)]
+struct Cli {
+    /// 数据库文件，未指定时与图形界面使用同一个默认数据库
+    #[arg(short, long, global = true)]
+    database: Option<PathBuf>,
+    /// 输出格式
+    #[arg(short, long, value_enum, default_value_t = Format::Table, global = true)]
+    format: Format,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Table,
+    Json,
+}
+
+/// 与`ImportMode`对应
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// 原样使用导入的ID，与已有结构冲突时撤销整个导入
+    Fail,
+    /// 保留已有结构
+    Skip,
+    /// 用导入的结构替换已有结构
+    Overwrite,
+    /// 只补充已有结构中空白的信息
+    Merge,
+    /// 全部作为新结构导入
+    Renumber,
+}
+
+/// 与`MatchBy`对应
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum Match {
+    Id,
+    Name,
+    Smiles,
+}
+
+impl From<Mode> for ImportMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Fail => Self::Fail,
+            Mode::Skip => Self::Skip,
+            Mode::Overwrite => Self::Overwrite,
+            Mode::Merge => Self::Merge,
+            Mode::Renumber => Self::Renumber,
+        }
+    }
+}
+
+impl From<Match> for MatchBy {
+    fn from(match_by: Match) -> Self {
+        match match_by {
+            Match::Id => Self::Id,
+            Match::Name => Self::Name,
+            Match::Smiles => Self::Smiles,
+        }
+    }
+}
+
+/// 解析`标签=字段`，字段名与CSV表格的列名相同
+fn parse_sdf_field(text: &str) -> std::result::Result<(String, SdfField), String> {
+    let (tag, field) = text
+        .rsplit_once('=')
+        .ok_or_else(|| format!("“{}”应为`标签=字段`的形式", text))?;
+    let field =
+        SdfField::from_tag(field.trim()).ok_or_else(|| format!("未知的字段“{}”", field.trim()))?;
+    Ok((tag.to_string(), field))
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// 从导出目录导入数据，数据库不存在时创建
+    Import {
+        folder: PathBuf,
+        /// 按选择的处理方式检查能否导入并输出检查结果，不写入数据库；会导致导入失败时以状态码2退出
+        #[arg(long)]
+        dry_run: bool,
+        /// 导入的结构与已有结构相同时的处理方式
+        #[arg(short, long, value_enum, default_value_t = Mode::Fail)]
+        mode: Mode,
+        /// 判断导入的结构与已有结构相同的依据
+        #[arg(long, value_enum, default_value_t = Match::Id)]
+        match_by: Match,
+        /// 导入前清空数据库，导入失败时已有数据保持不变
+        #[arg(long)]
+        replace: bool,
+    },
+    /// 将全部数据导出到目录。给出结构ID或检索条件时只导出这些结构，
+    /// 以及它们直接或间接含有的组成部分
+    Export {
+        folder: PathBuf,
+        /// 将结构表、属性表和组成关系合并为一个表格`merged.csv`
+        #[arg(long)]
+        merged: bool,
+        /// 只导出指定ID的结构，可以重复给出或以逗号分隔
+        #[arg(
+            long = "id",
+            value_delimiter = ',',
+            conflicts_with_all = ["keyword", "min_charge", "max_charge", "ranges", "query"]
+        )]
+        ids: Vec<u32>,
+        /// 只导出与关键词匹配的结构，其余检索条件的写法与`search`相同
+        #[arg(short, long)]
+        keyword: Option<String>,
+        #[arg(long, allow_negative_numbers = true)]
+        min_charge: Option<i8>,
+        #[arg(long, allow_negative_numbers = true)]
+        max_charge: Option<i8>,
+        #[arg(short, long = "range")]
+        ranges: Vec<PropertyRange>,
+        #[arg(short, long)]
+        query: Option<String>,
+    },
+    /// 从SD文件导入结构和属性，数据库不存在时创建
+    ImportSdf {
+        file: PathBuf,
+        /// SD标签对应的字段，例如`"Density (g/cm3)=density"`，可以重复给出；
+        /// 未给出的标签按与字段名相同的标签识别
+        #[arg(long = "field", value_parser = parse_sdf_field)]
+        fields: Vec<(String, SdfField)>,
+        /// 导入的结构与已有结构相同时的处理方式
+        #[arg(short, long, value_enum, default_value_t = Mode::Fail)]
+        mode: Mode,
+        /// 判断导入的结构与已有结构相同的依据
+        #[arg(long, value_enum, default_value_t = Match::Id)]
+        match_by: Match,
+        /// 导入前清空数据库，导入失败时已有数据保持不变
+        #[arg(long)]
+        replace: bool,
+    },
+    /// 将全部结构和属性导出为SD文件
+    ExportSdf {
+        file: PathBuf,
+        /// 字段写入的SD标签，格式与`import-sdf`相同
+        #[arg(long = "field", value_parser = parse_sdf_field)]
+        fields: Vec<(String, SdfField)>,
+    },
+    /// 从Excel工作簿导入数据，工作簿的格式与`export-xlsx`导出的相同，数据库不存在时创建
+    ImportXlsx {
+        file: PathBuf,
+        /// 导入的结构与已有结构相同时的处理方式
+        #[arg(short, long, value_enum, default_value_t = Mode::Fail)]
+        mode: Mode,
+        /// 判断导入的结构与已有结构相同的依据
+        #[arg(long, value_enum, default_value_t = Match::Id)]
+        match_by: Match,
+        /// 导入前清空数据库，导入失败时已有数据保持不变
+        #[arg(long)]
+        replace: bool,
+    },
+    /// 将全部数据导出为Excel工作簿，每个表一个工作表
+    ExportXlsx {
+        file: PathBuf,
+        /// 在结构表中插入结构图片
+        #[arg(long)]
+        images: bool,
+    },
+    /// 从`.chembank`归档导入数据，先核对归档的版本和校验和，数据库不存在时创建
+    ImportArchive {
+        file: PathBuf,
+        /// 按选择的处理方式检查能否导入并输出检查结果，不写入数据库；会导致导入失败时以状态码2退出
+        #[arg(long)]
+        dry_run: bool,
+        /// 导入的结构与已有结构相同时的处理方式
+        #[arg(short, long, value_enum, default_value_t = Mode::Fail)]
+        mode: Mode,
+        /// 判断导入的结构与已有结构相同的依据
+        #[arg(long, value_enum, default_value_t = Match::Id)]
+        match_by: Match,
+        /// 导入前清空数据库，导入失败时已有数据保持不变
+        #[arg(long)]
+        replace: bool,
+    },
+    /// 将全部数据导出为一个`.chembank`归档
+    ExportArchive { file: PathBuf },
+    /// 按关键词、电荷范围、属性数值范围和检索表达式检索结构，每次输出一页
+    Search {
+        #[arg(short, long)]
+        keyword: Option<String>,
+        #[arg(long, default_value_t = i8::MIN, allow_negative_numbers = true)]
+        min_charge: i8,
+        #[arg(long, default_value_t = i8::MAX, allow_negative_numbers = true)]
+        max_charge: i8,
+        /// 每页的结构数量
+        #[arg(long, default_value_t = 100)]
+        limit: u32,
+        /// 上一页输出的分页位置，省略时从第一页开始
+        #[arg(long)]
+        cursor: Option<String>,
+        /// 排序键，例如`charge`、`density:desc`，可以重复给出，依次比较
+        #[arg(short, long)]
+        sort: Vec<SortKey>,
+        /// 属性数值范围，例如`density:1.80..1.95`、`decomp_temp:200..`，可以重复给出
+        #[arg(short, long = "range")]
+        ranges: Vec<PropertyRange>,
+        /// 检索表达式，例如`name:硝酸 AND charge=0 AND NOT remarks:待核实`
+        #[arg(short, long)]
+        query: Option<String>,
+    },
+    /// 统计结构数量
+    Count,
+    /// 查看结构的详细信息
+    Show { id: u32 },
+    /// 检查数据库结构与程序定义是否一致，存在差异或尚未执行的迁移时以状态码2退出，不会升级数据库
+    Check,
+    /// 将数据库升级到最新的结构版本。只读的子命令不会升级数据库，结构版本较旧时报错
+    Migrate,
+    /// 以HTTP/JSON接口提供数据库操作，数据库不存在时创建
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        listen: std::net::SocketAddr,
+        /// 导入导出时允许读写的目录，请求中的相对路径相对于该目录；省略时不能通过接口读写服务器上的文件
+        #[arg(long)]
+        root: Option<PathBuf>,
+    },
+}
+
+/// 组成部分或以该结构为组成部分的结构
+#[derive(Serialize, Debug)]
+struct Member {
+    id: u32,
+    name: Option<String>,
+    formula: Option<String>,
+    count: u32,
+}
+
+/// `show`命令的输出，图片只给出文件名
+#[derive(Serialize, Debug)]
+struct Detail {
+    structure: structure::Model,
+    property: Option<property::Model>,
+    image: Option<String>,
+    components: Vec<Member>,
+    component_of: Vec<Member>,
+}
+
+impl From<StructureDetail> for Detail {
+    fn from((structure, property, image, components, component_of): StructureDetail) -> Self {
+        let members = |items: Vec<(component::Model, Option<structure::Model>)>, outer: bool| {
+            items
+                .into_iter()
+                .map(|(component, model)| Member {
+                    id: if outer {
+                        component.structure_id
+                    } else {
+                        component.component_id
+                    },
+                    name: model.as_ref().and_then(|model| model.name.clone()),
+                    formula: model.map(|model| model.formula),
+                    count: component.count,
+                })
+                .collect()
+        };
+        Self {
+            structure,
+            property,
+            image: image.map(|image| image.filename),
+            components: members(components, false),
+            component_of: members(component_of, true),
+        }
+    }
+}
+
+/// 按照图形界面的规则确定默认数据库的位置
+fn default_database() -> Result<PathBuf> {
+    let no_directory = |message: &str| ChembankError::Io {
+        path: None,
+        message: message.to_string(),
+    };
+    let config_dir = dirs_next::config_dir()
+        .ok_or_else(|| no_directory("无法确定配置文件目录"))?
+        .join(IDENTIFIER);
+    let data_dir = dirs_next::data_dir()
+        .ok_or_else(|| no_directory("无法确定数据目录"))?
+        .join(IDENTIFIER);
+    Settings::load(&config_dir.join("settings.json"))?.default_database(&data_dir)
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// 检索结果表格，使用全文索引检索时增加一列摘要，匹配部分用【】标出
+fn hit_table(hits: &[SearchHit]) -> Table {
+    let with_snippet = hits.iter().any(|hit| !hit.snippet.is_empty());
+    let mut table = Table::new();
+    let mut header = vec!["ID", "名称", "化学式", "SMILES", "电荷"];
+    if with_snippet {
+        header.push("匹配内容");
+    }
+    table.set_header(header);
+    for SearchHit { structure, snippet } in hits {
+        let mut row = vec![
+            structure.id.to_string(),
+            structure.name.clone().unwrap_or_default(),
+            structure.formula.clone(),
+            structure.smiles.clone().unwrap_or_default(),
+            structure.charge.to_string(),
+        ];
+        if with_snippet {
+            row.push(
+                snippet
+                    .iter()
+                    .map(|part| match part.matched {
+                        true => format!("【{}】", part.text),
+                        false => part.text.clone(),
+                    })
+                    .collect(),
+            );
+        }
+        table.add_row(row);
+    }
+    table
+}
+
+fn member_table(members: &[Member]) -> Table {
+    let mut table = Table::new();
+    table.set_header(["ID", "名称", "化学式", "数量"]);
+    for member in members {
+        table.add_row([
+            member.id.to_string(),
+            member.name.clone().unwrap_or_default(),
+            member.formula.clone().unwrap_or_default(),
+            member.count.to_string(),
+        ]);
+    }
+    table
+}
+
+fn detail_table(detail: &Detail) -> String {
+    let mut fields = Table::new();
+    fields.set_header(["字段", "值"]);
+    let values = [
+        serde_json::to_value(&detail.structure),
+        serde_json::to_value(&detail.property),
+    ];
+    for value in values.into_iter().flatten() {
+        if let Value::Object(map) = value {
+            for (key, value) in map.iter().filter(|(key, _)| *key != "structure_id") {
+                fields.add_row([key.clone(), cell(value)]);
+            }
+        }
+    }
+    fields.add_row([
+        "image".to_string(),
+        detail.image.clone().unwrap_or_default(),
+    ]);
+    let mut output = fields.to_string();
+    if !detail.components.is_empty() {
+        output += &format!("\n组成部分\n{}", member_table(&detail.components));
+    }
+    if !detail.component_of.is_empty() {
+        output += &format!(
+            "\n作为以下结构的组成部分\n{}",
+            member_table(&detail.component_of)
+        );
+    }
+    output
+}
+
+fn report_table(report: &SchemaReport) -> String {
+    if report.drifts.is_empty()
+        && report.unexpected_tables.is_empty()
+        && report.pending_migrations.is_empty()
+    {
+        return "数据库结构与程序定义一致".to_string();
+    }
+    let mut table = Table::new();
+    table.set_header(["差异", "详细信息"]);
+    for drift in &report.drifts {
+        if let Ok(Value::Object(mut map)) = serde_json::to_value(drift) {
+            let kind = map.remove("kind").as_ref().map(cell).unwrap_or_default();
+            let details = map
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, cell(value)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            table.add_row([kind, details]);
+        }
+    }
+    for name in &report.unexpected_tables {
+        table.add_row(["UnexpectedTable".to_string(), format!("table={}", name)]);
+    }
+    for name in &report.pending_migrations {
+        table.add_row([
+            "PendingMigration".to_string(),
+            format!("migration={}", name),
+        ]);
+    }
+    table.to_string()
+}
+
+/// 冲突在导入时的处理结果
+fn resolution_text(resolution: Resolution) -> &'static str {
+    match resolution {
+        Resolution::Error => "导入失败",
+        Resolution::Skipped => "跳过导入的记录",
+        Resolution::Overwritten => "覆盖已有记录",
+        Resolution::Merged => "补充已有记录",
+        Resolution::Renumbered => "使用新的ID",
+    }
+}
+
+fn import_report_table(report: &ImportReport) -> String {
+    let mut text = format!(
+        "将导入{}个结构、{}条属性、{}条组成关系和{}张图片",
+        report.structures, report.properties, report.components, report.images
+    );
+    if report.errors.is_empty() && report.conflicts.is_empty() {
+        return text + "，未发现问题";
+    }
+    let mut table = Table::new();
+    table.set_header(["问题", "文件", "行", "列", "详细信息"]);
+    let line = |line: Option<u32>| line.map(|line| line.to_string()).unwrap_or_default();
+    for issue in &report.errors {
+        table.add_row([
+            "错误".to_string(),
+            issue.file.clone(),
+            line(issue.line),
+            issue.column.clone().unwrap_or_default(),
+            issue.message.clone(),
+        ]);
+    }
+    for conflict in &report.conflicts {
+        table.add_row([
+            "冲突".to_string(),
+            conflict.file.clone(),
+            line(conflict.line),
+            conflict.field.clone(),
+            format!(
+                "“{}”与已有结构{}冲突，{}",
+                conflict.value,
+                conflict.existing_id,
+                resolution_text(conflict.resolution)
+            ),
+        ]);
+    }
+    text += &format!(
+        "，发现{}个错误和{}处冲突\n{}",
+        report.errors.len(),
+        report.conflicts.len(),
+        table
+    );
+    text
+}
+
+/// 按照选择的格式输出结果，`table`只在表格格式下调用
+fn print<T: Serialize>(format: Format, value: &T, table: impl FnOnce() -> String) {
+    match format {
+        Format::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(value).expect("输出结果应当总能序列化为JSON")
+        ),
+        Format::Table => println!("{}", table()),
+    }
+}
+
+/// 子命令打开数据库的方式
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Access {
+    /// 只读，结构版本需要升级时报错
+    Read,
+    /// 只读，不检查结构版本
+    Inspect,
+    /// 读写，打开时升级结构版本
+    Write,
+    /// 读写，数据库不存在时创建
+    Create,
+}
+
+async fn open(database: &Option<PathBuf>, access: Access) -> Result<Chembank> {
+    let path = match database {
+        Some(path) => path.clone(),
+        None => default_database()?,
+    };
+    match access {
+        Access::Read => Chembank::open_read_only(&path).await,
+        Access::Inspect => Chembank::inspect(&path).await,
+        Access::Write => Chembank::open(&path).await,
+        Access::Create => Chembank::open_or_create(&path).await,
+    }
+}
+
+async fn run(cli: Cli) -> Result<ExitCode> {
+    let format = cli.format;
+    #[cfg(feature = "server")]
+    let create = matches!(
+        cli.command,
+        Command::Import { dry_run: false, .. }
+            | Command::ImportSdf { .. }
+            | Command::ImportXlsx { .. }
+            | Command::ImportArchive { dry_run: false, .. }
+            | Command::Serve { .. }
+    );
+    #[cfg(not(feature = "server"))]
+    let create = matches!(
+        cli.command,
+        Command::Import { dry_run: false, .. }
+            | Command::ImportSdf { .. }
+            | Command::ImportXlsx { .. }
+            | Command::ImportArchive { dry_run: false, .. }
+    );
+    let access = match cli.command {
+        Command::Check => Access::Inspect,
+        Command::Migrate => Access::Write,
+        // 清空后导入的检查在不提交的事务中删除已有数据，需要写权限
+        Command::Import {
+            dry_run: true,
+            replace: true,
+            ..
+        }
+        | Command::ImportArchive {
+            dry_run: true,
+            replace: true,
+            ..
+        } => Access::Write,
+        _ if create => Access::Create,
+        _ => Access::Read,
+    };
+    let db = open(&cli.database, access).await?;
+    let mut code = ExitCode::SUCCESS;
+    match cli.command {
+        Command::Import {
+            folder,
+            dry_run: true,
+            mode,
+            match_by,
+            replace,
+        } => {
+            let options = ImportOptions {
+                mode: mode.into(),
+                match_by: match_by.into(),
+                replace,
+            };
+            let report = db.check_import(&folder, options).await?;
+            if !report.importable() {
+                code = ExitCode::from(2);
+            }
+            print(format, &report, || import_report_table(&report));
+        }
+        Command::Import {
+            folder,
+            dry_run: false,
+            mode,
+            match_by,
+            replace,
+        } => {
+            let options = ImportOptions {
+                mode: mode.into(),
+                match_by: match_by.into(),
+                replace,
+            };
+            db.import_from_folder(&folder, options, &Job::default())
+                .await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("导入完成，数据库中共有{}个结构", count)
+            });
+        }
+        Command::Export {
+            folder,
+            merged,
+            ids,
+            keyword,
+            min_charge,
+            max_charge,
+            ranges,
+            query,
+        } => {
+            let filtered = keyword.is_some()
+                || min_charge.is_some()
+                || max_charge.is_some()
+                || !ranges.is_empty()
+                || query.is_some();
+            let subset = match (ids.is_empty(), filtered) {
+                (false, _) => Some(ExportSubset::Ids(ids)),
+                (true, true) => Some(ExportSubset::Filter(SearchFilter {
+                    keyword,
+                    max_charge: max_charge.unwrap_or(i8::MAX),
+                    min_charge: min_charge.unwrap_or(i8::MIN),
+                    ranges,
+                    query,
+                })),
+                (true, false) => None,
+            };
+            let options = ExportOptions { merged, subset };
+            let count = db
+                .export_to_folder(&folder, options, &Job::default())
+                .await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("已将{}个结构导出到{}", count, folder.display())
+            });
+        }
+        Command::ImportSdf {
+            file,
+            fields,
+            mode,
+            match_by,
+            replace,
+        } => {
+            let sdf = SdfOptions {
+                fields: fields.into_iter().collect(),
+            };
+            let options = ImportOptions {
+                mode: mode.into(),
+                match_by: match_by.into(),
+                replace,
+            };
+            db.import_sdf(&file, &sdf, options, &Job::default()).await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("导入完成，数据库中共有{}个结构", count)
+            });
+        }
+        Command::ExportSdf { file, fields } => {
+            let sdf = SdfOptions {
+                fields: fields.into_iter().collect(),
+            };
+            db.export_sdf(&file, &sdf, &Job::default()).await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("已将{}个结构导出到{}", count, file.display())
+            });
+        }
+        Command::ImportXlsx {
+            file,
+            mode,
+            match_by,
+            replace,
+        } => {
+            let options = ImportOptions {
+                mode: mode.into(),
+                match_by: match_by.into(),
+                replace,
+            };
+            db.import_xlsx(&file, options, &Job::default()).await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("导入完成，数据库中共有{}个结构", count)
+            });
+        }
+        Command::ExportXlsx { file, images } => {
+            db.export_xlsx(&file, XlsxOptions { images }, &Job::default())
+                .await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("已将{}个结构导出到{}", count, file.display())
+            });
+        }
+        Command::ImportArchive {
+            file,
+            dry_run: true,
+            mode,
+            match_by,
+            replace,
+        } => {
+            let options = ImportOptions {
+                mode: mode.into(),
+                match_by: match_by.into(),
+                replace,
+            };
+            let report = db.check_archive(&file, options).await?;
+            let ArchiveReport {
+                manifest,
+                report: check,
+            } = &report;
+            if !check.importable() {
+                code = ExitCode::from(2);
+            }
+            print(format, &report, || {
+                format!(
+                    "归档导出于{}，{}",
+                    manifest.exported_at,
+                    import_report_table(check)
+                )
+            });
+        }
+        Command::ImportArchive {
+            file,
+            dry_run: false,
+            mode,
+            match_by,
+            replace,
+        } => {
+            let options = ImportOptions {
+                mode: mode.into(),
+                match_by: match_by.into(),
+                replace,
+            };
+            db.import_archive(&file, options, &Job::default()).await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("导入完成，数据库中共有{}个结构", count)
+            });
+        }
+        Command::ExportArchive { file } => {
+            db.export_archive(&file, &Job::default()).await?;
+            let count = db.structure_count().await?;
+            print(format, &serde_json::json!({ "structures": count }), || {
+                format!("已将{}个结构导出到{}", count, file.display())
+            });
+        }
+        Command::Search {
+            keyword,
+            min_charge,
+            max_charge,
+            limit,
+            cursor,
+            sort,
+            ranges,
+            query,
+        } => {
+            let page = db
+                .search_structure(
+                    keyword, max_charge, min_charge, ranges, query, sort, cursor, limit,
+                )
+                .await?;
+            print(format, &page, || {
+                let mut text = format!("{}\n共{}个结构", hit_table(&page.hits), page.total);
+                if let Some(cursor) = &page.next_cursor {
+                    text += &format!("，下一页：--cursor {}", cursor);
+                }
+                text
+            });
+        }
+        Command::Count => {
+            let count = db.structure_count().await?;
+            print(format, &count, || count.to_string());
+        }
+        Command::Show { id } => {
+            let detail = Detail::from(db.get_structure_detail(id).await?);
+            print(format, &detail, || detail_table(&detail));
+        }
+        Command::Check => {
+            let report = db.schema_report().await?;
+            if !report.drifts.is_empty() || report.foreign || !report.pending_migrations.is_empty()
+            {
+                code = ExitCode::from(2);
+            }
+            print(format, &report, || report_table(&report));
+        }
+        Command::Migrate => {
+            let report = db.schema_report().await?;
+            print(format, &report, || report_table(&report));
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { listen, root } => {
+            server::serve(db, listen, root).await?;
+            return Ok(code);
+        }
+    }
+    db.close().await?;
+    Ok(code)
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let format = cli.format;
+    match run(cli).await {
+        Ok(code) => code,
+        Err(e) => {
+            match format {
+                Format::Json => eprintln!(
+                    "{}",
+                    serde_json::to_string(&e).expect("错误应当总能序列化为JSON")
+                ),
+                Format::Table => eprintln!("{}", e),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[test]
+fn test_cli_arguments() {
+    use clap::CommandFactory;
+
+    Cli::command().debug_assert();
+    let cli = Cli::parse_from([
+        "chembank-cli",
+        "search",
+        "--min-charge",
+        "-2",
+        "--format",
+        "json",
+        "-d",
+        "salts.db",
+        "-r",
+        "density:1.8..",
+        "-q",
+        "name:硝酸 AND NOT remarks:待核实",
+        "-s",
+        "charge",
+        "--sort",
+        "density:desc",
+    ]);
+    assert_eq!(cli.format, Format::Json);
+    assert_eq!(cli.database, Some(PathBuf::from("salts.db")));
+    assert!(matches!(
+        cli.command,
+        Command::Search {
+            min_charge: -2,
+            max_charge: i8::MAX,
+            limit: 100,
+            ref ranges,
+            ref sort,
+            query: Some(_),
+            ..
+        } if ranges.len() == 1 && sort.len() == 2
+    ));
+    let cli = Cli::parse_from([
+        "chembank-cli",
+        "import",
+        "../example/export",
+        "--mode",
+        "merge",
+        "--match-by",
+        "smiles",
+    ]);
+    assert!(matches!(
+        cli.command,
+        Command::Import {
+            dry_run: false,
+            mode: Mode::Merge,
+            match_by: Match::Smiles,
+            ..
+        }
+    ));
+    let cli = Cli::parse_from([
+        "chembank-cli",
+        "import-sdf",
+        "partners.sdf",
+        "--field",
+        "Density (g/cm3)=density",
+        "--field",
+        "IUPAC=name",
+    ]);
+    assert!(matches!(
+        cli.command,
+        Command::ImportSdf { ref fields, .. } if fields[0].1 == SdfField::Property(quantity::Quantity::Density)
+            && fields[1] == ("IUPAC".to_string(), SdfField::Name)
+    ));
+    assert!(Cli::try_parse_from([
+        "chembank-cli",
+        "export-sdf",
+        "a.sdf",
+        "--field",
+        "X=unknown"
+    ])
+    .is_err());
+    let cli = Cli::parse_from(["chembank-cli", "export-xlsx", "--images", "a.xlsx"]);
+    assert!(matches!(
+        cli.command,
+        Command::ExportXlsx { images: true, .. }
+    ));
+    let cli = Cli::parse_from(["chembank-cli", "import-archive", "--dry-run", "a.chembank"]);
+    assert!(matches!(
+        cli.command,
+        Command::ImportArchive { dry_run: true, .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_show_detail() {
+    let folder = std::env::temp_dir().join(format!("chembank-cli-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("salts.db");
+    let db = Chembank::create(&path).await.unwrap();
+    let sodium = db
+        .create_structure(Some("钠离子".to_string()), "Na".to_string(), None, 1)
+        .await
+        .unwrap();
+    let salt = db
+        .create_structure(Some("氯化钠".to_string()), "NaCl".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.set_component(salt, sodium, 1).await.unwrap();
+    let detail = Detail::from(db.get_structure_detail(salt).await.unwrap());
+    assert_eq!(detail.components.len(), 1);
+    assert_eq!(detail.components[0].id, sodium);
+    assert_eq!(detail.components[0].name.as_deref(), Some("钠离子"));
+    let detail = Detail::from(db.get_structure_detail(sodium).await.unwrap());
+    assert_eq!(detail.component_of[0].id, salt);
+    assert!(detail_table(&detail).contains("氯化钠"));
+    db.close().await.unwrap();
+    std::fs::remove_dir_all(&folder).unwrap();
+}
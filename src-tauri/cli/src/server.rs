@@ -0,0 +1,675 @@
+use std::{net::SocketAddr, path::PathBuf, str::FromStr, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::{FromRef, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use chembank_core::{
+    ArchiveReport, Chembank, ChembankError, ExportOptions, ImportOptions, ImportReport, Job,
+    SchemaReport, SdfOptions, SearchPage, XlsxOptions,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use entities::*;
+
+use crate::Detail;
+
+type Db = State<Arc<Chembank>>;
+
+/// 导入导出时允许读写的服务器目录，未指定时不能访问服务器上的文件
+#[derive(Clone, Debug)]
+struct Root(Option<Arc<PathBuf>>);
+
+impl Root {
+    /// 将请求中的路径解析为根目录内的绝对路径，相对路径相对于根目录。
+    /// 解析`..`和符号链接后不在根目录内时拒绝；目标不存在时解析其所在的目录
+    fn resolve(&self, path: &std::path::Path) -> ApiResult<PathBuf> {
+        let not_allowed = |message: &str| ChembankError::PathNotAllowed {
+            path: path.display().to_string(),
+            message: message.to_string(),
+        };
+        let root = self
+            .0
+            .as_deref()
+            .ok_or_else(|| not_allowed("服务器启动时没有通过--root指定允许读写的目录"))?;
+        let joined = root.join(path);
+        let resolved = match joined.canonicalize() {
+            Ok(resolved) => resolved,
+            Err(_) => match (joined.parent(), joined.file_name()) {
+                (Some(parent), Some(name)) => parent
+                    .canonicalize()
+                    .map_err(|_| not_allowed("所在的目录不存在"))?
+                    .join(name),
+                _ => Err(not_allowed("路径无效"))?,
+            },
+        };
+        if !resolved.starts_with(root) {
+            Err(not_allowed("路径不在允许读写的目录内"))?;
+        }
+        Ok(resolved)
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    db: Arc<Chembank>,
+    root: Root,
+}
+
+impl FromRef<AppState> for Arc<Chembank> {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Root {
+    fn from_ref(state: &AppState) -> Self {
+        state.root.clone()
+    }
+}
+
+/// 将错误转换为HTTP响应，响应体为与Tauri命令相同的错误JSON
+struct ApiError(ChembankError);
+
+impl From<ChembankError> for ApiError {
+    fn from(e: ChembankError) -> Self {
+        Self(e)
+    }
+}
+
+fn status_code(e: &ChembankError) -> StatusCode {
+    match e {
+        ChembankError::ImportRecord { cause, .. } => status_code(cause),
+        ChembankError::NotFound { .. } => StatusCode::NOT_FOUND,
+        ChembankError::DuplicateName { .. }
+        | ChembankError::DuplicateSmiles { .. }
+        | ChembankError::StillReferencedAsComponent { .. }
+        | ChembankError::SchemaTooNew { .. }
+        | ChembankError::SchemaOutdated { .. } => StatusCode::CONFLICT,
+        ChembankError::NotChembankDatabase => StatusCode::FORBIDDEN,
+        ChembankError::CsvFormat { .. }
+        | ChembankError::SdfFormat { .. }
+        | ChembankError::XlsxFormat { .. }
+        | ChembankError::InvalidArchive { .. }
+        | ChembankError::InvalidImageFolder { .. }
+        | ChembankError::InvalidRecord { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        ChembankError::PathNotAllowed { .. } => StatusCode::FORBIDDEN,
+        ChembankError::InvalidSearch { .. } | ChembankError::QuerySyntax { .. } => {
+            StatusCode::BAD_REQUEST
+        }
+        ChembankError::DbUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        ChembankError::Database { .. } | ChembankError::Io { .. } | ChembankError::Cancelled => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (status_code(&self.0), Json(self.0)).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// 创建或修改结构时的请求体
+#[derive(Deserialize, Debug)]
+struct StructureBody {
+    name: Option<String>,
+    formula: String,
+    smiles: Option<String>,
+    #[serde(default)]
+    charge: i8,
+}
+
+#[derive(Deserialize, Debug)]
+struct SearchQuery {
+    keyword: Option<String>,
+    min_charge: Option<i8>,
+    max_charge: Option<i8>,
+    limit: Option<u32>,
+    cursor: Option<String>,
+    /// 以逗号分隔的排序键，例如`charge,density:desc`
+    sort: Option<String>,
+    /// 以逗号分隔的属性数值范围，例如`density:1.80..1.95,decomp_temp:200..`
+    ranges: Option<String>,
+    /// 检索表达式
+    query: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ComponentBody {
+    count: u32,
+}
+
+#[derive(Deserialize, Debug)]
+struct ImageQuery {
+    filename: String,
+}
+
+/// 导出的请求体，`merged`可以省略
+#[derive(Deserialize, Debug)]
+struct ExportBody {
+    folder: PathBuf,
+    #[serde(flatten)]
+    options: ExportOptions,
+}
+
+/// 导入的请求体，`mode`、`match_by`和`replace`可以省略
+#[derive(Deserialize, Debug)]
+struct ImportBody {
+    folder: PathBuf,
+    #[serde(flatten)]
+    options: ImportOptions,
+}
+
+/// SD文件导入导出的请求体，`fields`、`mode`、`match_by`和`replace`可以省略
+#[derive(Deserialize, Debug)]
+struct SdfBody {
+    file: PathBuf,
+    #[serde(flatten)]
+    sdf: SdfOptions,
+    #[serde(flatten)]
+    options: ImportOptions,
+}
+
+/// Excel工作簿导入导出的请求体，`images`、`mode`、`match_by`和`replace`可以省略
+#[derive(Deserialize, Debug)]
+struct XlsxBody {
+    file: PathBuf,
+    #[serde(flatten)]
+    xlsx: XlsxOptions,
+    #[serde(flatten)]
+    options: ImportOptions,
+}
+
+/// 归档导入导出的请求体，`mode`、`match_by`和`replace`可以省略
+#[derive(Deserialize, Debug)]
+struct ArchiveBody {
+    file: PathBuf,
+    #[serde(flatten)]
+    options: ImportOptions,
+}
+
+async fn count(State(db): Db) -> ApiResult<Json<Value>> {
+    Ok(Json(json!({ "count": db.structure_count().await? })))
+}
+
+/// 解析以逗号分隔的列表
+fn parse_list<T: FromStr<Err = ChembankError>>(list: &Option<String>) -> ApiResult<Vec<T>> {
+    Ok(list
+        .iter()
+        .flat_map(|list| list.split(','))
+        .filter(|item| !item.trim().is_empty())
+        .map(str::parse)
+        .collect::<chembank_core::Result<Vec<T>>>()?)
+}
+
+async fn search(State(db): Db, Query(query): Query<SearchQuery>) -> ApiResult<Json<SearchPage>> {
+    let page = db
+        .search_structure(
+            query.keyword.filter(|keyword| !keyword.is_empty()),
+            query.max_charge.unwrap_or(i8::MAX),
+            query.min_charge.unwrap_or(i8::MIN),
+            parse_list(&query.ranges)?,
+            query.query,
+            parse_list(&query.sort)?,
+            query.cursor.filter(|cursor| !cursor.is_empty()),
+            query.limit.unwrap_or(100),
+        )
+        .await?;
+    Ok(Json(page))
+}
+
+async fn create_structure(
+    State(db): Db,
+    Json(body): Json<StructureBody>,
+) -> ApiResult<(StatusCode, Json<Value>)> {
+    let id = db
+        .create_structure(body.name, body.formula, body.smiles, body.charge)
+        .await?;
+    Ok((StatusCode::CREATED, Json(json!({ "id": id }))))
+}
+
+async fn get_structure(State(db): Db, Path(id): Path<u32>) -> ApiResult<Json<Detail>> {
+    Ok(Json(Detail::from(db.get_structure_detail(id).await?)))
+}
+
+async fn update_structure(
+    State(db): Db,
+    Path(id): Path<u32>,
+    Json(body): Json<StructureBody>,
+) -> ApiResult<StatusCode> {
+    db.update_structure(id, body.name, body.formula, body.smiles, body.charge)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn remove_structure(State(db): Db, Path(id): Path<u32>) -> ApiResult<StatusCode> {
+    db.remove_structure(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 属性的`structure_id`以路径为准，请求体中可以省略
+async fn set_property(
+    State(db): Db,
+    Path(id): Path<u32>,
+    Json(mut body): Json<Value>,
+) -> ApiResult<StatusCode> {
+    if let Value::Object(map) = &mut body {
+        map.insert("structure_id".to_string(), json!(id));
+    }
+    let model: property::Model =
+        serde_json::from_value(body).map_err(|e| ChembankError::InvalidRecord {
+            table: chembank_core::Table::Property,
+            message: e.to_string(),
+        })?;
+    db.set_property(model).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn set_component(
+    State(db): Db,
+    Path((id, component_id)): Path<(u32, u32)>,
+    Json(body): Json<ComponentBody>,
+) -> ApiResult<StatusCode> {
+    db.set_component(id, component_id, body.count).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_component(
+    State(db): Db,
+    Path((id, component_id)): Path<(u32, u32)>,
+) -> ApiResult<StatusCode> {
+    db.delete_component(id, component_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn content_type(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn get_image(State(db): Db, Path(id): Path<u32>) -> ApiResult<Response> {
+    let (_, _, image, _, _) = db.get_structure_detail(id).await?;
+    let image = image.ok_or(ChembankError::not_found(chembank_core::Table::Image, [id]))?;
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                content_type(&image.filename).to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", image.filename.replace('"', "")),
+            ),
+        ],
+        image.image,
+    )
+        .into_response())
+}
+
+/// 请求体为图片文件的原始内容，文件名通过`filename`参数给出
+async fn set_image(
+    State(db): Db,
+    Path(id): Path<u32>,
+    Query(query): Query<ImageQuery>,
+    body: Bytes,
+) -> ApiResult<StatusCode> {
+    db.set_image(id, body.to_vec(), query.filename).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 导入导出的目录和文件是服务器上根目录内的路径
+async fn import(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<ImportBody>,
+) -> ApiResult<Json<Value>> {
+    db.import_from_folder(&root.resolve(&body.folder)?, body.options, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn check_import(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<ImportBody>,
+) -> ApiResult<Json<ImportReport>> {
+    let folder = root.resolve(&body.folder)?;
+    Ok(Json(db.check_import(&folder, body.options).await?))
+}
+
+async fn export(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<ExportBody>,
+) -> ApiResult<Json<Value>> {
+    let count = db
+        .export_to_folder(&root.resolve(&body.folder)?, body.options, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": count })))
+}
+
+async fn import_sdf(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<SdfBody>,
+) -> ApiResult<Json<Value>> {
+    db.import_sdf(
+        &root.resolve(&body.file)?,
+        &body.sdf,
+        body.options,
+        &Job::default(),
+    )
+    .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn export_sdf(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<SdfBody>,
+) -> ApiResult<Json<Value>> {
+    db.export_sdf(&root.resolve(&body.file)?, &body.sdf, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn import_xlsx(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<XlsxBody>,
+) -> ApiResult<Json<Value>> {
+    db.import_xlsx(&root.resolve(&body.file)?, body.options, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn export_xlsx(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<XlsxBody>,
+) -> ApiResult<Json<Value>> {
+    db.export_xlsx(&root.resolve(&body.file)?, body.xlsx, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn import_archive(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<ArchiveBody>,
+) -> ApiResult<Json<Value>> {
+    db.import_archive(&root.resolve(&body.file)?, body.options, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn check_archive(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<ArchiveBody>,
+) -> ApiResult<Json<ArchiveReport>> {
+    let file = root.resolve(&body.file)?;
+    Ok(Json(db.check_archive(&file, body.options).await?))
+}
+
+async fn export_archive(
+    State(db): Db,
+    State(root): State<Root>,
+    Json(body): Json<ArchiveBody>,
+) -> ApiResult<Json<Value>> {
+    db.export_archive(&root.resolve(&body.file)?, &Job::default())
+        .await?;
+    Ok(Json(json!({ "structures": db.structure_count().await? })))
+}
+
+async fn schema(State(db): Db) -> ApiResult<Json<SchemaReport>> {
+    Ok(Json(db.schema_report().await?))
+}
+
+fn router(db: Arc<Chembank>, root: Option<PathBuf>) -> Router {
+    Router::new()
+        .route("/structures", get(search).post(create_structure))
+        .route("/structures/count", get(count))
+        .route(
+            "/structures/{id}",
+            get(get_structure)
+                .put(update_structure)
+                .delete(remove_structure),
+        )
+        .route("/structures/{id}/property", put(set_property))
+        .route(
+            "/structures/{id}/components/{component_id}",
+            put(set_component).delete(delete_component),
+        )
+        .route("/structures/{id}/image", get(get_image).put(set_image))
+        .route("/import", post(import))
+        .route("/import/check", post(check_import))
+        .route("/export", post(export))
+        .route("/import/sdf", post(import_sdf))
+        .route("/export/sdf", post(export_sdf))
+        .route("/import/xlsx", post(import_xlsx))
+        .route("/export/xlsx", post(export_xlsx))
+        .route("/import/archive", post(import_archive))
+        .route("/import/archive/check", post(check_archive))
+        .route("/export/archive", post(export_archive))
+        .route("/schema", get(schema))
+        .with_state(AppState {
+            db,
+            root: Root(root.map(Arc::new)),
+        })
+}
+
+/// 在指定地址上提供HTTP接口，直到收到Ctrl+C后关闭数据库。
+/// 导入导出只能读写`root`目录内的文件，未指定时不能访问服务器上的文件
+pub async fn serve(
+    db: Chembank,
+    listen: SocketAddr,
+    root: Option<PathBuf>,
+) -> chembank_core::Result<()> {
+    let root = root
+        .map(|root| root.canonicalize().map_err(|e| ChembankError::io(&root, e)))
+        .transpose()?;
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .map_err(|e| ChembankError::Io {
+            path: None,
+            message: format!("无法监听{}：{}", listen, e),
+        })?;
+    eprintln!("正在监听http://{}", listen);
+    let db = Arc::new(db);
+    axum::serve(listener, router(db.clone(), root))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| ChembankError::Io {
+            path: None,
+            message: e.to_string(),
+        })?;
+    if let Ok(db) = Arc::try_unwrap(db) {
+        db.close().await?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_http_api() {
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let root = std::env::temp_dir().join(format!("chembank-serve-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    let root = root.canonicalize().unwrap();
+    let app = router(Arc::new(db), Some(root.clone()));
+    let request = |method: &str, uri: &str, body: Value| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/structures",
+            json!({ "name": "钠离子", "formula": "Na", "charge": 1 }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/structures",
+            json!({ "name": "钠离子", "formula": "Na" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let error: ChembankError = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        error,
+        ChembankError::DuplicateName {
+            name: "钠离子".to_string()
+        }
+    );
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/structures/1/property",
+            json!({ "density": "2.16" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = app
+        .clone()
+        .oneshot(request(
+            "PUT",
+            "/structures/1/property",
+            json!({ "density": 2.16 }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let error: ChembankError = serde_json::from_slice(&body).unwrap();
+    assert!(matches!(error, ChembankError::InvalidRecord { .. }));
+    let response = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/structures?min_charge=1&sort=charge:desc,density&limit=1",
+            Value::Null,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let page: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(page["total"], 1);
+    assert_eq!(page["next_cursor"], Value::Null);
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/structures?sort=weight", Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/structures?ranges=density:2..",
+            Value::Null,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(request("GET", "/structures?ranges=density", Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = app
+        .clone()
+        .oneshot(request(
+            "GET",
+            "/structures?query=charge%3E%3D",
+            Value::Null,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // 根目录以外的路径一律拒绝
+    for folder in ["/nonexistent/chembank", "../chembank", "/"] {
+        let response = app
+            .clone()
+            .oneshot(request("POST", "/export", json!({ "folder": folder })))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+    let response = app
+        .clone()
+        .oneshot(request("POST", "/export", json!({ "folder": "backup" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(root.join("backup").join("structures.csv").is_file());
+    let response = app
+        .clone()
+        .oneshot(request(
+            "POST",
+            "/import/check",
+            json!({ "folder": "nonexistent" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let report: ImportReport = serde_json::from_slice(&body).unwrap();
+    assert_eq!(report.errors.len(), 4);
+    let response = app
+        .oneshot(request("GET", "/structures/42", Value::Null))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,527 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use migration::{Migrator, MigratorTrait};
+use sea_orm::{EntityTrait, PaginatorTrait};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use zip::{result::ZipError, write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use entities::*;
+
+use crate::{
+    error::Result,
+    progress::Job,
+    transfer::{read_import_rows, ExportOptions, ImportOptions},
+    validate::ImportReport,
+    Chembank, ChembankError,
+};
+
+/// 归档格式的版本，归档内容不兼容地改变时递增
+const ARCHIVE_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 归档中各表的记录数和图片数
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+pub struct ArchiveCounts {
+    pub structures: u32,
+    pub properties: u32,
+    pub components: u32,
+    pub images: u32,
+}
+
+/// 归档中的清单文件`manifest.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Type)]
+pub struct Manifest {
+    /// 归档格式的版本
+    pub version: u32,
+    /// 导出时数据库结构的版本，即最后一个迁移的名称
+    pub schema: String,
+    /// 导出时间，RFC 3339格式
+    pub exported_at: String,
+    pub counts: ArchiveCounts,
+    /// 归档中除清单以外每个文件的SHA-256校验和，键为文件在归档中的路径
+    pub checksums: BTreeMap<String, String>,
+}
+
+/// 归档的检查结果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+pub struct ArchiveReport {
+    pub manifest: Manifest,
+    pub report: ImportReport,
+}
+
+fn invalid(path: &Path, message: impl Into<String>) -> ChembankError {
+    ChembankError::InvalidArchive {
+        path: path.to_string_lossy().to_string(),
+        message: message.into(),
+    }
+}
+
+fn zip_error(path: &Path, e: ZipError) -> ChembankError {
+    match e {
+        ZipError::Io(e) => ChembankError::io(path, e),
+        e => invalid(path, e.to_string()),
+    }
+}
+
+fn sha256(content: &[u8]) -> String {
+    Sha256::digest(content)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// 打包或解压归档用的临时目录，离开作用域时删除
+struct Scratch {
+    folder: PathBuf,
+}
+
+impl Scratch {
+    fn new() -> Result<Self> {
+        static NEXT: AtomicU32 = AtomicU32::new(0);
+        let folder = std::env::temp_dir().join(format!(
+            "chembank-archive-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(&folder).map_err(|e| ChembankError::io(&folder, e))?;
+        Ok(Self { folder })
+    }
+
+    /// 目录中全部文件相对于目录的路径，以`/`分隔并按名称排序
+    fn files(&self) -> Result<Vec<String>> {
+        let mut files = vec![];
+        let mut pending = vec![self.folder.clone()];
+        while let Some(folder) = pending.pop() {
+            for item in fs::read_dir(&folder).map_err(|e| ChembankError::io(&folder, e))? {
+                let path = item.map_err(|e| ChembankError::io(&folder, e))?.path();
+                if path.is_dir() {
+                    pending.push(path);
+                } else if let Some(name) = self.entry_name(&path) {
+                    files.push(name);
+                }
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// 临时目录中的文件在归档中的路径，以`/`分隔
+    fn entry_name(&self, path: &Path) -> Option<String> {
+        let parts = path
+            .strip_prefix(&self.folder)
+            .ok()?
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>();
+        Some(parts.join("/"))
+    }
+
+    /// 将临时目录中的文件路径改写为归档中的路径，例如`chembank.chembank#structures.csv`
+    fn relocate(&self, archive: &Path, file: &str) -> String {
+        match self.entry_name(Path::new(file)) {
+            Some(name) => format!("{}#{}", archive.to_string_lossy(), name),
+            None => file.to_string(),
+        }
+    }
+
+    fn relocate_error(&self, archive: &Path, e: ChembankError) -> ChembankError {
+        match e {
+            ChembankError::Io {
+                path: Some(path),
+                message,
+            } => ChembankError::Io {
+                path: Some(self.relocate(archive, &path)),
+                message,
+            },
+            ChembankError::CsvFormat {
+                file,
+                line,
+                column,
+                message,
+            } => ChembankError::CsvFormat {
+                file: self.relocate(archive, &file),
+                line,
+                column,
+                message,
+            },
+            ChembankError::InvalidImageFolder { path, message } => {
+                ChembankError::InvalidImageFolder {
+                    path: self.relocate(archive, &path),
+                    message,
+                }
+            }
+            ChembankError::ImportRecord { file, line, cause } => ChembankError::ImportRecord {
+                file: self.relocate(archive, &file),
+                line,
+                cause: Box::new(self.relocate_error(archive, *cause)),
+            },
+            e => e,
+        }
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.folder);
+    }
+}
+
+/// 解压归档，解压前检查清单中的格式版本和数据库结构版本，解压时逐个核对文件的校验和
+fn unpack_archive(path: &Path) -> Result<(Scratch, Manifest)> {
+    let file = File::open(path).map_err(|e| ChembankError::io(path, e))?;
+    let mut zip = ZipArchive::new(file).map_err(|e| zip_error(path, e))?;
+    let manifest: Manifest = match zip.by_name(MANIFEST_FILE) {
+        Ok(entry) => serde_json::from_reader(entry)
+            .map_err(|e| invalid(path, format!("清单文件无法解析：{}", e)))?,
+        Err(ZipError::FileNotFound) => {
+            return Err(invalid(path, format!("缺少清单文件{}", MANIFEST_FILE)))
+        }
+        Err(e) => return Err(zip_error(path, e)),
+    };
+    if manifest.version > ARCHIVE_VERSION {
+        let message = format!(
+            "归档格式版本{}来自更新版本的程序，请升级程序后再导入",
+            manifest.version
+        );
+        return Err(invalid(path, message));
+    }
+    if !Migrator::migrations()
+        .iter()
+        .any(|migration| migration.name() == manifest.schema)
+    {
+        let message = format!(
+            "数据库结构版本{}来自更新版本的程序，请升级程序后再导入",
+            manifest.schema
+        );
+        return Err(invalid(path, message));
+    }
+
+    let scratch = Scratch::new()?;
+    let mut missing = manifest.checksums.keys().collect::<BTreeSet<_>>();
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index).map_err(|e| zip_error(path, e))?;
+        let name = entry.name().to_string();
+        if entry.is_dir() || name == MANIFEST_FILE {
+            continue;
+        }
+        let relative = entry
+            .enclosed_name()
+            .ok_or_else(|| invalid(path, format!("文件名{}不安全", name)))?;
+        let checksum = manifest
+            .checksums
+            .get(&name)
+            .ok_or_else(|| invalid(path, format!("文件{}不在清单中", name)))?;
+        let mut content = vec![];
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| ChembankError::io(path, e))?;
+        if sha256(&content) != *checksum {
+            return Err(invalid(
+                path,
+                format!("文件{}的校验和不符，归档可能已经损坏", name),
+            ));
+        }
+        let target = scratch.folder.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| ChembankError::io(parent, e))?;
+        }
+        fs::write(&target, content).map_err(|e| ChembankError::io(&target, e))?;
+        missing.remove(&name);
+    }
+    if let Some(name) = missing.first() {
+        return Err(invalid(path, format!("缺少清单中的文件{}", name)));
+    }
+    // 没有图片时归档中没有图片目录，导入时需要这个目录
+    let image_folder = scratch.folder.join("images");
+    fs::create_dir_all(&image_folder).map_err(|e| ChembankError::io(&image_folder, e))?;
+    Ok((scratch, manifest))
+}
+
+/// 表格中的记录数，整个文件无法读取或有无法解析的行时为空
+fn parsed_count<T>(rows: &Result<Vec<Result<T>>>) -> Option<u32> {
+    match rows {
+        Ok(rows) if rows.iter().all(|row| row.is_ok()) => Some(rows.len() as u32),
+        _ => None,
+    }
+}
+
+impl Chembank {
+    /// 核对解压出的各表记录数和图片数与清单中的数量一致。
+    /// 表格无法完整解析时不核对该表，由导入或检查给出具体的错误
+    async fn verify_counts(
+        &self,
+        path: &Path,
+        scratch: &Scratch,
+        manifest: &Manifest,
+    ) -> Result<()> {
+        let rows = read_import_rows(&self.db, &scratch.folder).await?;
+        let image_folder = scratch.folder.join("images");
+        let images = fs::read_dir(&image_folder)
+            .map_err(|e| ChembankError::io(&image_folder, e))?
+            .count() as u32;
+        let expected = manifest.counts;
+        let counts = [
+            ("结构", parsed_count(&rows.structures), expected.structures),
+            ("属性", parsed_count(&rows.properties), expected.properties),
+            (
+                "组成关系",
+                parsed_count(&rows.components),
+                expected.components,
+            ),
+            ("图片", Some(images), expected.images),
+        ];
+        for (table, actual, expected) in counts {
+            match actual {
+                Some(actual) if actual != expected => {
+                    let message = format!(
+                        "{}的数量为{}，与清单中的{}不符，归档可能已经损坏",
+                        table, actual, expected
+                    );
+                    return Err(invalid(path, message));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// 将全部数据导出为一个`.chembank`归档。归档是包含导出目录中全部文件和清单文件`manifest.json`的zip文件，
+    /// 清单中记录了数据库结构版本、导出时间、记录数和每个文件的校验和
+    pub async fn export_archive(&self, path: &Path, job: &Job) -> Result<()> {
+        let db = &self.db;
+        let scratch = Scratch::new()?;
+        self.export_to_folder(&scratch.folder, ExportOptions::default(), job)
+            .await?;
+        let counts = ArchiveCounts {
+            structures: structure::Entity::find().count(db).await? as u32,
+            properties: property::Entity::find().count(db).await? as u32,
+            components: component::Entity::find().count(db).await? as u32,
+            images: image::Entity::find().count(db).await? as u32,
+        };
+        let files = scratch.files()?;
+        let mut checksums = BTreeMap::new();
+        for name in &files {
+            let file = scratch.folder.join(name);
+            let content = fs::read(&file).map_err(|e| ChembankError::io(&file, e))?;
+            checksums.insert(name.clone(), sha256(&content));
+        }
+        let manifest = Manifest {
+            version: ARCHIVE_VERSION,
+            schema: Migrator::migrations()
+                .last()
+                .map(|migration| migration.name().to_string())
+                .unwrap_or_default(),
+            exported_at: chrono::Local::now().to_rfc3339(),
+            counts,
+            checksums,
+        };
+
+        let file = File::create(path).map_err(|e| ChembankError::io(path, e))?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+        zip.start_file(MANIFEST_FILE, options)
+            .map_err(|e| zip_error(path, e))?;
+        serde_json::to_writer_pretty(&mut zip, &manifest)
+            .map_err(|e| ChembankError::io(path, e.into()))?;
+        for name in files {
+            let file = scratch.folder.join(&name);
+            let content = fs::read(&file).map_err(|e| ChembankError::io(&file, e))?;
+            zip.start_file(name, options)
+                .map_err(|e| zip_error(path, e))?;
+            zip.write_all(&content)
+                .map_err(|e| ChembankError::io(path, e))?;
+        }
+        zip.finish().map_err(|e| zip_error(path, e))?;
+        Ok(())
+    }
+
+    /// 从`.chembank`归档导入，先核对清单中的版本、校验和与记录数，再按导出目录导入。
+    /// 错误中的文件路径为归档路径加`#`和文件在归档中的路径
+    pub async fn import_archive(
+        &self,
+        path: &Path,
+        options: ImportOptions,
+        job: &Job,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let (scratch, manifest) = unpack_archive(path)?;
+        self.verify_counts(path, &scratch, &manifest)
+            .await
+            .map_err(|e| scratch.relocate_error(path, e))?;
+        self.import_from_folder(&scratch.folder, options, job)
+            .await
+            .map_err(|e| scratch.relocate_error(path, e))
+    }
+
+    /// 按`options`检查`.chembank`归档能否导入当前数据库，不写入任何内容，版本、校验和或记录数不符时返回错误
+    pub async fn check_archive(
+        &self,
+        path: &Path,
+        options: ImportOptions,
+    ) -> Result<ArchiveReport> {
+        let (scratch, manifest) = unpack_archive(path)?;
+        self.verify_counts(path, &scratch, &manifest)
+            .await
+            .map_err(|e| scratch.relocate_error(path, e))?;
+        let mut report = self
+            .check_import(&scratch.folder, options)
+            .await
+            .map_err(|e| scratch.relocate_error(path, e))?;
+        for issue in &mut report.errors {
+            issue.file = scratch.relocate(path, &issue.file);
+        }
+        for conflict in &mut report.conflicts {
+            conflict.file = scratch.relocate(path, &conflict.file);
+        }
+        Ok(ArchiveReport { manifest, report })
+    }
+}
+
+#[tokio::test]
+async fn test_archive_round_trip() {
+    let folder = std::env::temp_dir().join(format!("chembank-archive-test-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example/export");
+    let mut source = Chembank::connect("sqlite::memory:").await.unwrap();
+    source.migrate().await.unwrap();
+    source
+        .import_from_folder(&example, ImportOptions::default(), &Job::default())
+        .await
+        .unwrap();
+    let archive = folder.join("export.chembank");
+    source
+        .export_archive(&archive, &Job::default())
+        .await
+        .unwrap();
+
+    let mut target = Chembank::connect("sqlite::memory:").await.unwrap();
+    target.migrate().await.unwrap();
+    let checked = target
+        .check_archive(&archive, ImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(checked.manifest.version, ARCHIVE_VERSION);
+    assert_eq!(
+        checked.manifest.counts.structures,
+        checked.report.structures
+    );
+    assert_eq!(checked.manifest.counts.images, checked.report.images);
+    assert!(checked.manifest.checksums.contains_key("structures.csv"));
+    target
+        .import_archive(&archive, ImportOptions::default(), &Job::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        target.structure_count().await.unwrap(),
+        source.structure_count().await.unwrap()
+    );
+    assert_eq!(
+        image::Entity::find().count(&target.db).await.unwrap(),
+        checked.manifest.counts.images as u64
+    );
+    // 再次检查时与已有结构冲突，冲突的位置为归档中的文件
+    let conflict = &target
+        .check_archive(&archive, ImportOptions::default())
+        .await
+        .unwrap()
+        .report
+        .conflicts[0];
+    assert_eq!(
+        conflict.file,
+        format!("{}#structures.csv", archive.to_string_lossy())
+    );
+    let error = target
+        .import_archive(&archive, ImportOptions::default(), &Job::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ChembankError::ImportRecord { ref file, line: Some(2), .. }
+            if *file == format!("{}#structures.csv", archive.to_string_lossy())
+    ));
+
+    // 修改归档中的文件后校验和不符
+    let tampered = folder.join("tampered.chembank");
+    let mut original = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+    let mut zip = ZipWriter::new(File::create(&tampered).unwrap());
+    for index in 0..original.len() {
+        let entry = original.by_index(index).unwrap();
+        if entry.name() == "structures.csv" {
+            zip.start_file("structures.csv", SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(b"id,name,formula,smiles,charge\n").unwrap();
+        } else {
+            zip.raw_copy_file(entry).unwrap();
+        }
+    }
+    zip.finish().unwrap();
+    assert_eq!(
+        target
+            .check_archive(&tampered, ImportOptions::default())
+            .await
+            .unwrap_err(),
+        invalid(
+            &tampered,
+            "文件structures.csv的校验和不符，归档可能已经损坏"
+        )
+    );
+    assert!(matches!(
+        target
+            .check_archive(&example.join("structures.csv"), ImportOptions::default())
+            .await,
+        Err(ChembankError::InvalidArchive { .. })
+    ));
+
+    // 清单中的记录数与归档中的文件不符
+    let miscounted = folder.join("miscounted.chembank");
+    let mut manifest = checked.manifest.clone();
+    manifest.counts.properties += 1;
+    let mut original = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+    let mut zip = ZipWriter::new(File::create(&miscounted).unwrap());
+    for index in 0..original.len() {
+        let entry = original.by_index(index).unwrap();
+        if entry.name() == MANIFEST_FILE {
+            zip.start_file(MANIFEST_FILE, SimpleFileOptions::default())
+                .unwrap();
+            serde_json::to_writer(&mut zip, &manifest).unwrap();
+        } else {
+            zip.raw_copy_file(entry).unwrap();
+        }
+    }
+    zip.finish().unwrap();
+    let message = format!(
+        "属性的数量为{}，与清单中的{}不符，归档可能已经损坏",
+        checked.manifest.counts.properties, manifest.counts.properties
+    );
+    assert_eq!(
+        target
+            .check_archive(&miscounted, ImportOptions::default())
+            .await
+            .unwrap_err(),
+        invalid(&miscounted, &message)
+    );
+    let count = target.structure_count().await.unwrap();
+    let options = ImportOptions {
+        mode: crate::ImportMode::Skip,
+        ..Default::default()
+    };
+    assert_eq!(
+        target
+            .import_archive(&miscounted, options, &Job::default())
+            .await
+            .unwrap_err(),
+        invalid(&miscounted, message)
+    );
+    assert_eq!(target.structure_count().await.unwrap(), count);
+    let _ = fs::remove_dir_all(&folder);
+}
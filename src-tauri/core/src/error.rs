@@ -0,0 +1,274 @@
+use std::{fmt, path::Path};
+
+use sea_orm::{DbErr, SqlErr};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// 错误所涉及的数据表
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub enum Table {
+    Structure,
+    Property,
+    Image,
+    Component,
+}
+
+/// 全部数据库操作的错误类型，前端可根据`kind`字段区分错误种类并自行生成提示信息
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[serde(tag = "kind")]
+pub enum ChembankError {
+    /// 没有打开的数据库，或数据库连接已经断开
+    DbUnavailable { message: String },
+    /// 按主键没有找到对应记录
+    NotFound { table: Table, key: Vec<u32> },
+    /// 结构名称与已有结构重复
+    DuplicateName { name: String },
+    /// 结构SMILES与已有结构重复
+    DuplicateSmiles { smiles: String },
+    /// 该结构仍作为其他结构的组成部分存在，`referenced_by`为引用它的结构ID
+    StillReferencedAsComponent { id: u32, referenced_by: Vec<u32> },
+    /// 数据库来自更新版本的程序，`migrations`为本程序不认识的迁移
+    SchemaTooNew { migrations: Vec<String> },
+    /// 以只读方式打开的数据库需要先升级结构版本，`migrations`为尚未执行的迁移
+    SchemaOutdated { migrations: Vec<String> },
+    /// 数据库文件显然不是化合物库，禁止写入
+    NotChembankDatabase,
+    /// 其他数据库错误
+    Database { message: String },
+    /// 文件读写错误
+    Io {
+        path: Option<String>,
+        message: String,
+    },
+    /// CSV表格或Excel工作表格式错误，行号从1开始并包含表头行。
+    /// Excel工作表的`file`为工作簿路径加`#`和工作表名，例如`chembank.xlsx#structures`
+    CsvFormat {
+        file: String,
+        line: Option<u32>,
+        column: Option<String>,
+        message: String,
+    },
+    /// SD文件格式错误，`line`为出错记录标题行的行号，从1开始
+    SdfFormat {
+        file: String,
+        line: Option<u32>,
+        message: String,
+    },
+    /// Excel工作簿无法读取或写入，例如不是xlsx文件或缺少工作表
+    XlsxFormat { file: String, message: String },
+    /// `.chembank`归档无法导入，例如缺少清单、校验和不符或来自更新版本的程序
+    InvalidArchive { path: String, message: String },
+    /// 图片目录结构不正确，例如目录名不是结构ID或目录为空
+    InvalidImageFolder { path: String, message: String },
+    /// 检索条件无法识别
+    InvalidSearch { input: String, message: String },
+    /// 通过HTTP接口提交的记录无法识别
+    InvalidRecord { table: Table, message: String },
+    /// 通过HTTP接口访问的服务器路径不在允许读写的目录内
+    PathNotAllowed { path: String, message: String },
+    /// 导入时写入某条记录出错，`line`为CSV表格中的行号，从1开始并包含表头行；图片没有行号
+    ImportRecord {
+        file: String,
+        line: Option<u32>,
+        cause: Box<ChembankError>,
+    },
+    /// 导入导出被用户取消，导入时已写入的内容全部撤销
+    Cancelled,
+    /// 检索表达式语法错误，`position`为出错位置，从0开始按字符计数
+    QuerySyntax {
+        query: String,
+        position: u32,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, ChembankError>;
+
+impl ChembankError {
+    pub fn not_found(table: Table, key: impl Into<Vec<u32>>) -> Self {
+        Self::NotFound {
+            table,
+            key: key.into(),
+        }
+    }
+
+    pub fn io(path: impl AsRef<Path>, e: std::io::Error) -> Self {
+        Self::Io {
+            path: Some(path.as_ref().to_string_lossy().to_string()),
+            message: e.to_string(),
+        }
+    }
+
+    /// 将写入结构表时的唯一性冲突转换为`DuplicateName`或`DuplicateSmiles`
+    pub(crate) fn from_structure_write(
+        e: DbErr,
+        name: &Option<String>,
+        smiles: &Option<String>,
+    ) -> Self {
+        if let Some(SqlErr::UniqueConstraintViolation(message)) = e.sql_err() {
+            if message.contains("structures.name") {
+                return Self::DuplicateName {
+                    name: name.clone().unwrap_or_default(),
+                };
+            }
+            if message.contains("structures.smiles") {
+                return Self::DuplicateSmiles {
+                    smiles: smiles.clone().unwrap_or_default(),
+                };
+            }
+        }
+        e.into()
+    }
+
+    pub(crate) fn csv(
+        file: impl AsRef<Path>,
+        headers: Option<&csv::StringRecord>,
+        e: csv::Error,
+    ) -> Self {
+        let file = file.as_ref().to_string_lossy().to_string();
+        let line = e.position().map(|pos| pos.line() as u32);
+        let column = match e.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => err.field().map(|field| {
+                headers
+                    .and_then(|headers| headers.get(field as usize))
+                    .map(str::to_string)
+                    .unwrap_or_else(|| (field + 1).to_string())
+            }),
+            _ => None,
+        };
+        let message = e.to_string();
+        match e.into_kind() {
+            csv::ErrorKind::Io(e) => Self::io(file, e),
+            _ => Self::CsvFormat {
+                file,
+                line,
+                column,
+                message,
+            },
+        }
+    }
+}
+
+impl From<DbErr> for ChembankError {
+    fn from(e: DbErr) -> Self {
+        match e {
+            DbErr::ConnectionAcquire(_) | DbErr::Conn(_) => Self::DbUnavailable {
+                message: e.to_string(),
+            },
+            e => Self::Database {
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ChembankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DbUnavailable { message } => {
+                write!(
+                    f,
+                    "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员：{}",
+                    message
+                )
+            }
+            Self::NotFound { table, key } => {
+                write!(
+                    f,
+                    "没有找到{:?}表中主键为{:?}的记录，可能已经删除或未添加",
+                    table, key
+                )
+            }
+            Self::DuplicateName { name } => write!(f, "名称“{}”与已有结构重复", name),
+            Self::DuplicateSmiles { smiles } => write!(f, "SMILES“{}”与已有结构重复", smiles),
+            Self::StillReferencedAsComponent { id, referenced_by } => write!(
+                f,
+                "结构{}仍被作为结构{:?}的组成部分存在，请检查删除相应结构后再删除此结构",
+                id, referenced_by
+            ),
+            Self::SchemaTooNew { migrations } => write!(
+                f,
+                "该数据库由更新版本的程序创建，请升级程序后再打开，未知的迁移：{}",
+                migrations.join(", ")
+            ),
+            Self::SchemaOutdated { migrations } => write!(
+                f,
+                "该数据库的结构版本较旧，需要先升级才能读取，尚未执行的迁移：{}",
+                migrations.join(", ")
+            ),
+            Self::NotChembankDatabase => {
+                write!(f, "该文件不是化合物数据库，为避免损坏其中的数据，禁止写入")
+            }
+            Self::Database { message } => write!(f, "数据库故障，详细信息：{}", message),
+            Self::Io { path, message } => match path {
+                Some(path) => write!(f, "无法读写文件{}：{}", path, message),
+                None => write!(f, "无法读写文件：{}", message),
+            },
+            Self::CsvFormat {
+                file,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "表格{}格式不正确", file)?;
+                if let Some(line) = line {
+                    write!(f, "，第{}行", line)?;
+                }
+                if let Some(column) = column {
+                    write!(f, "，{}列", column)?;
+                }
+                write!(f, "：{}", message)
+            }
+            Self::SdfFormat {
+                file,
+                line,
+                message,
+            } => {
+                write!(f, "SD文件{}格式不正确", file)?;
+                if let Some(line) = line {
+                    write!(f, "，第{}行", line)?;
+                }
+                write!(f, "：{}", message)
+            }
+            Self::XlsxFormat { file, message } => {
+                write!(f, "Excel工作簿{}格式不正确：{}", file, message)
+            }
+            Self::InvalidArchive { path, message } => {
+                write!(f, "归档{}无法导入：{}", path, message)
+            }
+            Self::InvalidImageFolder { path, message } => {
+                write!(f, "图片目录{}不正确：{}", path, message)
+            }
+            Self::InvalidSearch { input, message } => {
+                write!(f, "检索条件“{}”不正确：{}", input, message)
+            }
+            Self::InvalidRecord { table, message } => {
+                write!(f, "{:?}表的记录格式不正确：{}", table, message)
+            }
+            Self::PathNotAllowed { path, message } => {
+                write!(f, "不能访问服务器上的路径{}：{}", path, message)
+            }
+            Self::ImportRecord { file, line, cause } => {
+                write!(f, "导入{}", file)?;
+                if let Some(line) = line {
+                    write!(f, "第{}行", line)?;
+                }
+                write!(f, "时出错，已撤销本次导入：{}", cause)
+            }
+            Self::Cancelled => write!(f, "操作已取消"),
+            Self::QuerySyntax {
+                query,
+                position,
+                message,
+            } => write!(
+                f,
+                "检索表达式“{}”第{}个字符处有误：{}",
+                query,
+                position + 1,
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ChembankError {}
@@ -0,0 +1,271 @@
+use std::path::PathBuf;
+
+use migration::{seaql_migrations, Migrator, MigratorTrait, SchemaManager};
+use sea_orm::{Database, DatabaseConnection, EntityTrait};
+
+mod archive;
+mod error;
+mod library;
+mod merged;
+mod molecule;
+mod progress;
+mod query;
+mod records;
+mod schema;
+mod sdf;
+mod search;
+mod transfer;
+mod validate;
+mod workbook;
+
+pub use archive::{ArchiveCounts, ArchiveReport, Manifest};
+pub use error::{ChembankError, Result, Table};
+pub use library::{
+    DatabaseStatus, RecentDatabases, Recovery, Settings, DATABASE_ENV, DEFAULT_DATABASE,
+};
+pub use progress::{Job, Phase, Progress};
+pub use query::Query;
+pub use records::StructureDetail;
+pub use schema::{SchemaDrift, SchemaReport, TypeAffinity};
+pub use sdf::{SdfField, SdfOptions};
+pub use search::{
+    PropertyRange, SearchFilter, SearchHit, SearchPage, SnippetPart, SortField, SortKey,
+};
+pub use transfer::{ExportOptions, ExportSubset, ImportMode, ImportOptions, MatchBy};
+pub use validate::{ImportConflict, ImportIssue, ImportReport, Resolution};
+pub use workbook::XlsxOptions;
+
+/// 化合物库句柄，封装了对数据库的全部操作，可以在Tauri命令、脚本和测试中复用
+pub struct Chembank {
+    db: DatabaseConnection,
+    path: Option<PathBuf>,
+    foreign: bool,
+}
+
+impl Chembank {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self {
+            db,
+            path: None,
+            foreign: false,
+        }
+    }
+
+    /// 连接到指定的数据库，例如`sqlite:chembank.db?mode=rwc`
+    pub async fn connect(url: &str) -> Result<Self> {
+        let db = Database::connect(url)
+            .await
+            .map_err(|e| ChembankError::DbUnavailable {
+                message: e.to_string(),
+            })?;
+        Ok(Self::new(db))
+    }
+
+    pub fn connection(&self) -> &DatabaseConnection {
+        &self.db
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.db.close().await?;
+        Ok(())
+    }
+
+    /// 将数据库升级到最新的结构版本。由早期版本创建、尚未记录迁移的数据库会被直接纳入迁移管理；
+    /// 如果数据库中记录了本程序不认识的迁移，说明它来自更新的版本，此时拒绝打开；
+    /// 如果数据库显然不是化合物库，则拒绝迁移，并禁止之后的一切写入
+    pub async fn migrate(&mut self) -> Result<()> {
+        self.foreign = self.schema_report().await?.foreign;
+        self.ensure_writable()?;
+        let (unknown, _) = self.migration_state().await?;
+        if !unknown.is_empty() {
+            return Err(ChembankError::SchemaTooNew {
+                migrations: unknown,
+            });
+        }
+        Migrator::up(&self.db, None).await?;
+        Ok(())
+    }
+
+    /// 数据库中记录的本程序不认识的迁移，以及本程序中尚未执行的迁移
+    pub(crate) async fn migration_state(&self) -> Result<(Vec<String>, Vec<String>)> {
+        let known = Migrator::migrations()
+            .iter()
+            .map(|migration| migration.name().to_string())
+            .collect::<Vec<_>>();
+        let manager = SchemaManager::new(&self.db);
+        let applied = match manager.has_table("seaql_migrations").await? {
+            true => seaql_migrations::Entity::find()
+                .all(&self.db)
+                .await?
+                .into_iter()
+                .map(|migration| migration.version)
+                .collect::<Vec<_>>(),
+            false => vec![],
+        };
+        let unknown = applied
+            .iter()
+            .filter(|version| !known.contains(version))
+            .cloned()
+            .collect();
+        let pending = known
+            .into_iter()
+            .filter(|name| !applied.contains(name))
+            .collect();
+        Ok((unknown, pending))
+    }
+}
+
+#[tokio::test]
+async fn test_structure_lifecycle() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let report = db.schema_report().await.unwrap();
+    assert_eq!(report.drifts, vec![]);
+    assert_eq!(report.unexpected_tables, Vec::<String>::new());
+    let sodium = db
+        .create_structure(
+            Some("钠离子".to_string()),
+            "Na".to_string(),
+            Some("[Na+]".to_string()),
+            1,
+        )
+        .await
+        .unwrap();
+    let salt = db
+        .create_structure(Some("氯化钠".to_string()), "NaCl".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.set_component(salt, sodium, 1).await.unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 2);
+    assert_eq!(
+        db.remove_structure(sodium).await,
+        Err(ChembankError::StillReferencedAsComponent {
+            id: sodium,
+            referenced_by: vec![salt]
+        })
+    );
+    assert_eq!(
+        db.create_structure(Some("钠离子".to_string()), "Na".to_string(), None, 1)
+            .await,
+        Err(ChembankError::DuplicateName {
+            name: "钠离子".to_string()
+        })
+    );
+    let (_, _, _, components, _) = db.get_structure_detail(salt).await.unwrap();
+    assert_eq!(components.len(), 1);
+    db.remove_structure(salt).await.unwrap();
+    db.remove_structure(sodium).await.unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_refuse_newer_schema() {
+    use sea_orm::{ActiveModelTrait, ActiveValue};
+
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    db.migrate().await.unwrap();
+    seaql_migrations::ActiveModel {
+        version: ActiveValue::set("m29991231_000001_from_the_future".to_string()),
+        applied_at: ActiveValue::set(0),
+    }
+    .insert(db.connection())
+    .await
+    .unwrap();
+    assert_eq!(
+        db.migrate().await,
+        Err(ChembankError::SchemaTooNew {
+            migrations: vec!["m29991231_000001_from_the_future".to_string()]
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_adopt_legacy_database() {
+    use entities::*;
+    use sea_orm::ConnectionTrait;
+
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    // 早期版本按当时的模型建表并写入的数据，此时还没有迁移记录、数值列和元素组成表
+    db.connection()
+        .execute_unprepared(
+            r#"CREATE TABLE "structures" ( "id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "name" varchar UNIQUE, "formula" varchar NOT NULL, "smiles" varchar UNIQUE, "charge" tinyint NOT NULL );
+            CREATE TABLE "components" ( "structure_id" integer NOT NULL, "component_id" integer NOT NULL, "count" integer NOT NULL, CONSTRAINT "pk-components" PRIMARY KEY ("structure_id", "component_id"), FOREIGN KEY ("structure_id") REFERENCES "structures" ("id"), FOREIGN KEY ("component_id") REFERENCES "structures" ("id") );
+            CREATE TABLE "property" ( "structure_id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "decomp_temp" varchar, "density" varchar, "diss_temp" varchar, "formation_enthalpy" varchar, "impact_sensitive" varchar, "friction_sensitivity" varchar, "det_velocity" varchar, "det_pressure" varchar, "n_content" varchar, "o_content" varchar, "no_content" varchar, "references" varchar, "remarks" varchar, FOREIGN KEY ("structure_id") REFERENCES "structures" ("id") );
+            CREATE TABLE "images" ( "structure_id" integer NOT NULL PRIMARY KEY AUTOINCREMENT, "filename" varchar NOT NULL, "image" varbinary_blob NOT NULL, FOREIGN KEY ("structure_id") REFERENCES "structures" ("id") );
+            INSERT INTO structures (formula, smiles, charge) VALUES ('H2O', 'O', 0);
+            INSERT INTO property (structure_id, density) VALUES (1, '1.00 g/cm3');"#,
+        )
+        .await
+        .unwrap();
+    db.migrate().await.unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 1);
+    assert_eq!(db.schema_report().await.unwrap().drifts, vec![]);
+    assert_eq!(
+        composition::Entity::find()
+            .all(db.connection())
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    let (_, property, _, _, _) = db.get_structure_detail(1).await.unwrap();
+    assert_eq!(property.unwrap().density_value, Some(1.0));
+}
+
+#[tokio::test]
+async fn test_migrate_property_values() {
+    use sea_orm::ConnectionTrait;
+
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    Migrator::up(db.connection(), Some(1)).await.unwrap();
+    db.connection()
+        .execute_unprepared(
+            "INSERT INTO structures (formula, charge) VALUES ('CH3NO2', 0);
+            INSERT INTO property (structure_id, density, det_velocity, decomp_temp)
+            VALUES (1, '1.14 g/cm3', '6,300 m/s', '约300');",
+        )
+        .await
+        .unwrap();
+    db.migrate().await.unwrap();
+    assert_eq!(db.schema_report().await.unwrap().drifts, vec![]);
+    let (_, property, _, _, _) = db.get_structure_detail(1).await.unwrap();
+    let mut property = property.unwrap();
+    assert_eq!(property.density_value, Some(1.14));
+    assert_eq!(property.det_velocity_value, Some(6300.0));
+    assert_eq!(property.decomp_temp_value, None);
+    assert_eq!(property.decomp_temp.as_deref(), Some("约300"));
+    assert_eq!(property.value_note.as_deref(), Some("分解温度：约300"));
+    assert_eq!(property.density_unit.as_deref(), Some("g·cm-3"));
+    assert_eq!(property.det_velocity_unit.as_deref(), Some("m·s-1"));
+    assert_eq!(property.decomp_temp_unit, None);
+
+    property.decomp_temp = Some("300".to_string());
+    property.density_value = None;
+    db.set_property(property).await.unwrap();
+    let (_, property, _, _, _) = db.get_structure_detail(1).await.unwrap();
+    let property = property.unwrap();
+    assert_eq!(property.decomp_temp_value, Some(300.0));
+    assert_eq!(property.decomp_temp_unit.as_deref(), Some("℃"));
+    assert_eq!(property.density_value, Some(1.14));
+    assert_eq!(property.value_note, None);
+}
+
+#[tokio::test]
+async fn test_refuse_foreign_database() {
+    use sea_orm::ConnectionTrait;
+
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.connection()
+        .execute_unprepared("CREATE TABLE invoices (id integer PRIMARY KEY, total real)")
+        .await
+        .unwrap();
+    assert_eq!(db.migrate().await, Err(ChembankError::NotChembankDatabase));
+    let report = db.schema_report().await.unwrap();
+    assert!(report.foreign);
+    assert_eq!(report.unexpected_tables, vec!["invoices".to_string()]);
+    assert_eq!(
+        db.create_structure(None, "H2O".to_string(), None, 0).await,
+        Err(ChembankError::NotChembankDatabase)
+    );
+}
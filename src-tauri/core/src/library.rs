@@ -0,0 +1,504 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use sea_orm::{
+    sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlxSqliteConnector,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{error::Result, Chembank, ChembankError};
+
+/// 最近打开的数据库列表最多保留的条目数
+const RECENT_LIMIT: usize = 10;
+
+/// 默认数据库的文件名，早期版本将其保存在工作目录中
+pub const DEFAULT_DATABASE: &str = "chembank.db";
+
+/// 指定默认数据库位置的环境变量，优先于配置文件
+pub const DATABASE_ENV: &str = "CHEMBANK_DB";
+
+impl Chembank {
+    /// 连接到数据库文件，只读连接不会升级结构版本
+    async fn connect_file(path: &Path, create: bool, read_only: bool) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(create)
+            .read_only(read_only);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .map_err(|e| ChembankError::DbUnavailable {
+                message: e.to_string(),
+            })?;
+        let mut db = Self::new(SqlxSqliteConnector::from_sqlx_sqlite_pool(pool));
+        db.path = Some(path.to_path_buf());
+        // 显然不是化合物库的文件不做迁移，仍可读取和查看结构差异，但禁止写入
+        db.foreign = db.schema_report().await?.foreign;
+        if !db.foreign && !read_only {
+            db.migrate().await?;
+        }
+        Ok(db)
+    }
+
+    /// 以只读方式打开已有的数据库文件，不升级结构版本，用于检查数据库结构
+    pub async fn inspect(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            Err(ChembankError::io(
+                path,
+                std::io::Error::from(ErrorKind::NotFound),
+            ))?;
+        }
+        Self::connect_file(path, false, true).await
+    }
+
+    /// 以只读方式打开已有的数据库文件用于查询和导出，不会修改文件。
+    /// 结构版本需要升级或来自更新版本的程序时报错
+    pub async fn open_read_only(path: &Path) -> Result<Self> {
+        let db = Self::inspect(path).await?;
+        if !db.foreign {
+            let (unknown, pending) = db.migration_state().await?;
+            if !unknown.is_empty() {
+                Err(ChembankError::SchemaTooNew {
+                    migrations: unknown,
+                })?;
+            }
+            if !pending.is_empty() {
+                Err(ChembankError::SchemaOutdated {
+                    migrations: pending,
+                })?;
+            }
+        }
+        Ok(db)
+    }
+
+    /// 打开已有的数据库文件，并将其升级到最新的结构版本。不是化合物库的文件以只读方式打开
+    pub async fn open(path: &Path) -> Result<Self> {
+        if !path.is_file() {
+            Err(ChembankError::io(
+                path,
+                std::io::Error::from(ErrorKind::NotFound),
+            ))?;
+        }
+        Self::connect_file(path, false, false).await
+    }
+
+    /// 创建新的数据库文件，目标文件已经存在时报错
+    pub async fn create(path: &Path) -> Result<Self> {
+        if path.exists() {
+            Err(ChembankError::io(
+                path,
+                std::io::Error::from(ErrorKind::AlreadyExists),
+            ))?;
+        }
+        Self::connect_file(path, true, false).await
+    }
+
+    /// 打开数据库文件，文件不存在时创建
+    pub async fn open_or_create(path: &Path) -> Result<Self> {
+        Self::connect_file(path, true, false).await
+    }
+
+    /// 用备份文件替换无法打开的数据库文件后打开，原文件被重命名保留
+    pub async fn restore(path: &Path, backup: &Path) -> Result<Self> {
+        if !backup.is_file() {
+            Err(ChembankError::io(
+                backup,
+                std::io::Error::from(ErrorKind::NotFound),
+            ))?;
+        }
+        set_aside(path)?;
+        fs::copy(backup, path).map_err(|e| ChembankError::io(path, e))?;
+        Self::open(path).await
+    }
+
+    /// 在无法打开的数据库文件的位置重新创建空数据库，原文件被重命名保留
+    pub async fn recreate(path: &Path) -> Result<Self> {
+        set_aside(path)?;
+        Self::create(path).await
+    }
+
+    /// 删除数据库文件并在原位置重新创建空数据库。
+    /// 显然不是化合物库的文件和通过URL连接的数据库不能清空
+    pub async fn reset(&mut self) -> Result<()> {
+        self.ensure_writable()?;
+        let path = self.path.clone().ok_or(ChembankError::DbUnavailable {
+            message: "当前数据库不是本地文件，无法重置".to_string(),
+        })?;
+        std::mem::take(&mut self.db).close().await?;
+        fs::remove_file(&path).map_err(|e| ChembankError::io(&path, e))?;
+        *self = Self::create(&path).await?;
+        Ok(())
+    }
+
+    /// 当前数据库文件的路径，通过URL连接的数据库没有路径
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+/// 当前数据库的状态，启动时无法打开默认数据库不会导致程序退出，而是记录失败原因，
+/// 由前端引导用户打开其他文件、从备份恢复或新建数据库
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[serde(tag = "state")]
+pub enum DatabaseStatus {
+    Open {
+        path: Option<PathBuf>,
+    },
+    Closed,
+    Failed {
+        path: Option<PathBuf>,
+        error: ChembankError,
+    },
+}
+
+/// 从无法打开的数据库中恢复的方式
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[serde(tag = "action")]
+pub enum Recovery {
+    /// 用备份文件替换原数据库
+    RestoreBackup { backup: PathBuf },
+    /// 在原位置新建空数据库
+    CreateNew,
+}
+
+/// 程序配置，以JSON格式保存在配置目录中
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    /// 默认打开的数据库文件，未设置时使用数据目录中的`chembank.db`
+    pub database: Option<PathBuf>,
+}
+
+impl Settings {
+    /// 读取配置，文件不存在时返回默认配置
+    pub fn load(file: &Path) -> Result<Self> {
+        match fs::read(file) {
+            Ok(content) => serde_json::from_slice(&content).map_err(|e| ChembankError::Io {
+                path: Some(file.to_string_lossy().to_string()),
+                message: e.to_string(),
+            }),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ChembankError::io(file, e)),
+        }
+    }
+
+    /// 确定默认数据库的位置：环境变量`CHEMBANK_DB`优先，其次是配置文件中的`database`，
+    /// 最后是数据目录中的`chembank.db`。使用数据目录时，如果其中还没有数据库而工作目录中
+    /// 有早期版本留下的`chembank.db`，则将其移动到数据目录
+    pub fn default_database(&self, data_dir: &Path) -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os(DATABASE_ENV).filter(|path| !path.is_empty()) {
+            return Ok(PathBuf::from(path));
+        }
+        self.configured_database(data_dir, Path::new(DEFAULT_DATABASE))
+    }
+
+    /// 不考虑环境变量时的默认数据库，`legacy`为早期版本留下的数据库文件
+    fn configured_database(&self, data_dir: &Path, legacy: &Path) -> Result<PathBuf> {
+        if let Some(path) = &self.database {
+            return Ok(path.clone());
+        }
+        fs::create_dir_all(data_dir).map_err(|e| ChembankError::io(data_dir, e))?;
+        let path = data_dir.join(DEFAULT_DATABASE);
+        if !path.exists() && legacy.is_file() {
+            move_file(legacy, &path)?;
+        }
+        Ok(path)
+    }
+}
+
+/// 将无法打开的数据库文件重命名为`<文件名>.broken`，已有同名文件时追加序号
+fn set_aside(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut target = PathBuf::from(format!("{}.broken", path.display()));
+    let mut index = 1;
+    while target.exists() {
+        target = PathBuf::from(format!("{}.broken.{}", path.display(), index));
+        index += 1;
+    }
+    move_file(path, &target)
+}
+
+/// 移动文件，跨文件系统时退化为复制后删除
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map_err(|e| ChembankError::io(to, e))?;
+    fs::remove_file(from).map_err(|e| ChembankError::io(from, e))
+}
+
+/// 最近打开的数据库文件列表，以JSON格式保存，最近打开的排在最前
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RecentDatabases {
+    pub paths: Vec<PathBuf>,
+}
+
+impl RecentDatabases {
+    /// 读取列表，文件不存在或内容无法识别时返回空列表
+    pub fn load(file: &Path) -> Self {
+        fs::read(file)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, file: &Path) -> Result<()> {
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent).map_err(|e| ChembankError::io(parent, e))?;
+        }
+        let content = serde_json::to_vec_pretty(self).map_err(|e| ChembankError::Io {
+            path: Some(file.to_string_lossy().to_string()),
+            message: e.to_string(),
+        })?;
+        fs::write(file, content).map_err(|e| ChembankError::io(file, e))
+    }
+
+    /// 将路径移动到列表最前
+    pub fn push(&mut self, path: &Path) {
+        self.paths.retain(|item| item != path);
+        self.paths.insert(0, path.to_path_buf());
+        self.paths.truncate(RECENT_LIMIT);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.paths.retain(|item| item != path);
+    }
+}
+
+#[tokio::test]
+async fn test_create_and_open_database() {
+    let folder = std::env::temp_dir().join(format!("chembank-library-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("salts.db");
+    assert!(Chembank::open(&path).await.is_err());
+    let db = Chembank::create(&path).await.unwrap();
+    db.create_structure(None, "NaCl".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.close().await.unwrap();
+    assert!(Chembank::create(&path).await.is_err());
+    let db = Chembank::open(&path).await.unwrap();
+    assert_eq!(db.path(), Some(path.as_path()));
+    assert_eq!(db.structure_count().await.unwrap(), 1);
+    db.close().await.unwrap();
+
+    let recent_file = folder.join("recent.json");
+    let mut recent = RecentDatabases::load(&recent_file);
+    recent.push(&path);
+    recent.push(&folder.join("neutral.db"));
+    recent.push(&path);
+    recent.save(&recent_file).unwrap();
+    assert_eq!(
+        RecentDatabases::load(&recent_file).paths,
+        vec![path.clone(), folder.join("neutral.db")]
+    );
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_open_foreign_database() {
+    use sea_orm::ConnectionTrait;
+
+    let folder = std::env::temp_dir().join(format!("chembank-foreign-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("invoices.db");
+    let db = Chembank::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    db.connection()
+        .execute_unprepared("CREATE TABLE invoices (id integer PRIMARY KEY, total real)")
+        .await
+        .unwrap();
+    db.close().await.unwrap();
+
+    let db = Chembank::open(&path).await.unwrap();
+    let report = db.schema_report().await.unwrap();
+    assert!(report.foreign);
+    assert_eq!(report.unexpected_tables, vec!["invoices".to_string()]);
+    assert_eq!(
+        db.create_structure(None, "H2O".to_string(), None, 0).await,
+        Err(ChembankError::NotChembankDatabase)
+    );
+    let tables = db
+        .connection()
+        .query_all(sea_orm::Statement::from_string(
+            db.connection().get_database_backend(),
+            "SELECT name FROM sqlite_master WHERE type = 'table'",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(tables.len(), 1);
+    db.close().await.unwrap();
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_reset_database() {
+    use sea_orm::ConnectionTrait;
+
+    let folder = std::env::temp_dir().join(format!("chembank-reset-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("invoices.db");
+    let db = Chembank::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    db.connection()
+        .execute_unprepared("CREATE TABLE invoices (id integer PRIMARY KEY, total real)")
+        .await
+        .unwrap();
+    db.close().await.unwrap();
+
+    // 不是化合物库的文件不能清空，文件保持原样
+    let mut db = Chembank::open(&path).await.unwrap();
+    assert_eq!(db.reset().await, Err(ChembankError::NotChembankDatabase));
+    assert!(db.schema_report().await.unwrap().foreign);
+    db.close().await.unwrap();
+    assert!(path.is_file());
+
+    let path = folder.join(DEFAULT_DATABASE);
+    let mut db = Chembank::create(&path).await.unwrap();
+    db.create_structure(None, "H2O".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.reset().await.unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 0);
+    assert_eq!(db.path(), Some(path.as_path()));
+    db.close().await.unwrap();
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_open_outdated_read_only() {
+    use migration::{Migrator, MigratorTrait};
+
+    let folder = std::env::temp_dir().join(format!("chembank-outdated-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("outdated.db");
+    let db = Chembank::connect(&format!("sqlite:{}?mode=rwc", path.display()))
+        .await
+        .unwrap();
+    let steps = Migrator::migrations().len() as u32 - 1;
+    Migrator::up(db.connection(), Some(steps)).await.unwrap();
+    db.close().await.unwrap();
+    let latest = Migrator::migrations().last().unwrap().name().to_string();
+    let content = fs::read(&path).unwrap();
+
+    let db = Chembank::inspect(&path).await.unwrap();
+    let report = db.schema_report().await.unwrap();
+    assert_eq!(report.pending_migrations, vec![latest.clone()]);
+    db.close().await.unwrap();
+    assert_eq!(
+        Chembank::open_read_only(&path).await.err(),
+        Some(ChembankError::SchemaOutdated {
+            migrations: vec![latest]
+        })
+    );
+    assert_eq!(fs::read(&path).unwrap(), content);
+
+    let db = Chembank::open(&path).await.unwrap();
+    db.close().await.unwrap();
+    let db = Chembank::open_read_only(&path).await.unwrap();
+    assert_eq!(
+        db.schema_report().await.unwrap().pending_migrations,
+        Vec::<String>::new()
+    );
+    db.close().await.unwrap();
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_recover_broken_database() {
+    let folder = std::env::temp_dir().join(format!("chembank-recover-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let path = folder.join("chembank.db");
+    fs::write(&path, "not a sqlite database").unwrap();
+    assert!(Chembank::open(&path).await.is_err());
+    let db = Chembank::recreate(&path).await.unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 0);
+    db.close().await.unwrap();
+    assert!(folder.join("chembank.db.broken").is_file());
+
+    let backup = folder.join("backup.db");
+    let db = Chembank::create(&backup).await.unwrap();
+    db.create_structure(None, "NaCl".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.close().await.unwrap();
+    let db = Chembank::restore(&path, &backup).await.unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 1);
+    db.close().await.unwrap();
+    assert!(folder.join("chembank.db.broken.1").is_file());
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn test_settings_default_database() {
+    let folder = std::env::temp_dir().join(format!("chembank-settings-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    let settings_file = folder.join("settings.json");
+    let settings = Settings::load(&settings_file).unwrap();
+    fs::create_dir_all(&folder).unwrap();
+    assert_eq!(settings, Settings::default());
+    if std::env::var_os(DATABASE_ENV).is_none() {
+        assert_eq!(
+            settings.default_database(&folder.join("data")).unwrap(),
+            folder.join("data").join(DEFAULT_DATABASE)
+        );
+    }
+    fs::write(
+        &settings_file,
+        r#"{ "database": "/srv/chembank/salts.db" }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        Settings::load(&settings_file).unwrap().database,
+        Some(PathBuf::from("/srv/chembank/salts.db"))
+    );
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[test]
+fn test_move_legacy_database() {
+    let folder = std::env::temp_dir().join(format!("chembank-legacy-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let legacy = folder.join(DEFAULT_DATABASE);
+    let data_dir = folder.join("data");
+    let settings = Settings::default();
+
+    // 数据目录中还没有数据库时移入早期版本的数据库
+    fs::write(&legacy, "legacy").unwrap();
+    let path = settings.configured_database(&data_dir, &legacy).unwrap();
+    assert_eq!(path, data_dir.join(DEFAULT_DATABASE));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "legacy");
+    assert!(!legacy.exists());
+
+    // 数据目录中已有数据库时不覆盖，早期版本的数据库保留在原处
+    fs::write(&legacy, "older").unwrap();
+    settings.configured_database(&data_dir, &legacy).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "legacy");
+    assert_eq!(fs::read_to_string(&legacy).unwrap(), "older");
+
+    // 配置了数据库时不移动
+    fs::remove_file(&path).unwrap();
+    let settings = Settings {
+        database: Some(folder.join("salts.db")),
+    };
+    let configured = settings.configured_database(&data_dir, &legacy).unwrap();
+    assert_eq!(configured, folder.join("salts.db"));
+    assert!(legacy.is_file());
+    assert!(!path.exists());
+    fs::remove_dir_all(&folder).unwrap();
+}
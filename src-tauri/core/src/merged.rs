@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use entities::{quantity::Quantity, *};
+
+/// 合并表的文件名，导入目录中存在该文件时按合并表导入
+pub(crate) const MERGED_FILE: &str = "merged.csv";
+
+/// 合并表中的一行，包含结构信息、属性的文本列和组成摘要。数值列由文本列重新计算，不写入合并表
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub(crate) struct MergedRow {
+    id: u32,
+    name: Option<String>,
+    formula: String,
+    smiles: Option<String>,
+    charge: i8,
+    decomp_temp: Option<String>,
+    density: Option<String>,
+    diss_temp: Option<String>,
+    formation_enthalpy: Option<String>,
+    impact_sensitive: Option<String>,
+    friction_sensitivity: Option<String>,
+    det_velocity: Option<String>,
+    det_pressure: Option<String>,
+    n_content: Option<String>,
+    o_content: Option<String>,
+    no_content: Option<String>,
+    references: Option<String>,
+    remarks: Option<String>,
+    components: Option<String>,
+}
+
+impl MergedRow {
+    pub(crate) fn new(
+        structure: structure::Model,
+        property: Option<property::Model>,
+        components: Option<String>,
+    ) -> Self {
+        let property = property.unwrap_or_default();
+        Self {
+            id: structure.id,
+            name: structure.name,
+            formula: structure.formula,
+            smiles: structure.smiles,
+            charge: structure.charge,
+            decomp_temp: property.decomp_temp,
+            density: property.density,
+            diss_temp: property.diss_temp,
+            formation_enthalpy: property.formation_enthalpy,
+            impact_sensitive: property.impact_sensitive,
+            friction_sensitivity: property.friction_sensitivity,
+            det_velocity: property.det_velocity,
+            det_pressure: property.det_pressure,
+            n_content: property.n_content,
+            o_content: property.o_content,
+            no_content: property.no_content,
+            references: property.references,
+            remarks: property.remarks,
+            components,
+        }
+    }
+
+    /// 拆分为结构、属性和组成摘要，属性的文本全部为空时不生成属性
+    pub(crate) fn split(self) -> (structure::Model, Option<property::Model>, Option<String>) {
+        let structure = structure::Model {
+            id: self.id,
+            name: self.name,
+            formula: self.formula,
+            smiles: self.smiles,
+            charge: self.charge,
+        };
+        let property = property::Model {
+            structure_id: self.id,
+            decomp_temp: self.decomp_temp,
+            density: self.density,
+            diss_temp: self.diss_temp,
+            formation_enthalpy: self.formation_enthalpy,
+            impact_sensitive: self.impact_sensitive,
+            friction_sensitivity: self.friction_sensitivity,
+            det_velocity: self.det_velocity,
+            det_pressure: self.det_pressure,
+            n_content: self.n_content,
+            o_content: self.o_content,
+            no_content: self.no_content,
+            references: self.references,
+            remarks: self.remarks,
+            ..Default::default()
+        };
+        let empty = Quantity::ALL
+            .into_iter()
+            .all(|quantity| property.text(quantity).is_none())
+            && property.references.is_none()
+            && property.remarks.is_none();
+        (structure, (!empty).then_some(property), self.components)
+    }
+}
+
+/// 组成摘要中的组成部分，没有名称的结构写作`#ID`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Member {
+    Id(u32),
+    Name(String),
+}
+
+impl Member {
+    /// 按名称引用组成部分；名称会被解析为分隔符、个数或ID时改用`#ID`，保证组成摘要能原样读回
+    pub(crate) fn new(id: u32, name: Option<String>) -> Self {
+        match name {
+            Some(name)
+                if !name.is_empty()
+                    && name.trim() == name
+                    && !name.contains(" + ")
+                    && !name.ends_with(" +")
+                    && !name.contains('×')
+                    && !name.starts_with('#') =>
+            {
+                Self::Name(name)
+            }
+            _ => Self::Id(id),
+        }
+    }
+}
+
+/// 生成组成摘要，例如`2×钠离子 + 1×碳酸根`
+pub(crate) fn format_summary(members: &[(u32, Member)]) -> String {
+    members
+        .iter()
+        .map(|(count, member)| match member {
+            Member::Id(id) => format!("{}×#{}", count, id),
+            Member::Name(name) => format!("{}×{}", count, name),
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// 解析组成摘要，各部分以` + `分隔，个数为1时可以省略`1×`
+pub(crate) fn parse_summary(text: &str) -> std::result::Result<Vec<(u32, Member)>, String> {
+    let mut members = vec![];
+    for part in text
+        .split(" + ")
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (count, member) = match part.split_once('×') {
+            Some((count, member)) => {
+                let count = count
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| format!("“{}”中的个数不是正整数", part))?;
+                (count, member.trim())
+            }
+            None => (1, part),
+        };
+        if count == 0 || member.is_empty() {
+            return Err(format!("无法识别的组成部分“{}”", part));
+        }
+        let member = match member.strip_prefix('#').map(str::parse::<u32>) {
+            Some(Ok(id)) => Member::Id(id),
+            Some(Err(_)) => return Err(format!("“{}”中的ID不是正整数", part)),
+            None => Member::Name(member.to_string()),
+        };
+        members.push((count, member));
+    }
+    Ok(members)
+}
+
+#[test]
+fn test_component_summary() {
+    let members = vec![
+        (2, Member::Name("钠离子".to_string())),
+        (1, Member::Name("NH4+".to_string())),
+        (1, Member::Id(7)),
+    ];
+    let summary = format_summary(&members);
+    assert_eq!(summary, "2×钠离子 + 1×NH4+ + 1×#7");
+    assert_eq!(parse_summary(&summary), Ok(members));
+    assert_eq!(
+        parse_summary("钠离子 + 3 × 碳酸根"),
+        Ok(vec![
+            (1, Member::Name("钠离子".to_string())),
+            (3, Member::Name("碳酸根".to_string()))
+        ])
+    );
+    assert_eq!(parse_summary(""), Ok(vec![]));
+    // 含有分隔符或形如ID的名称写作ID
+    for name in ["硝酸 + 水", "Ca +", "2×水", "#12", " 钠离子", ""] {
+        let members = vec![
+            (1, Member::new(3, Some(name.to_string()))),
+            (2, Member::new(4, Some("钠离子".to_string()))),
+        ];
+        let summary = format_summary(&members);
+        assert_eq!(
+            parse_summary(&summary),
+            Ok(vec![
+                (1, Member::Id(3)),
+                (2, Member::Name("钠离子".to_string()))
+            ])
+        );
+    }
+    assert!(parse_summary("0×钠离子").is_err());
+    assert!(parse_summary("two×钠离子").is_err());
+}
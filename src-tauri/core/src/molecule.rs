@@ -0,0 +1,847 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    f64::consts::{PI, TAU},
+};
+
+use entities::formula;
+
+/// 常见非金属元素的价态，按从小到大排列，用于推算隐含氢的个数
+const VALENCES: [(&str, &[u8]); 15] = [
+    ("H", &[1]),
+    ("B", &[3]),
+    ("C", &[4]),
+    ("N", &[3, 5]),
+    ("O", &[2]),
+    ("F", &[1]),
+    ("Si", &[4]),
+    ("P", &[3, 5]),
+    ("S", &[2, 4, 6]),
+    ("Cl", &[1, 3, 5, 7]),
+    ("As", &[3, 5]),
+    ("Se", &[2, 4, 6]),
+    ("Br", &[1]),
+    ("Te", &[2, 4, 6]),
+    ("I", &[1, 3, 5]),
+];
+
+/// SMILES中可以不写方括号的芳香原子
+const AROMATIC: [&str; 6] = ["b", "c", "n", "o", "p", "s"];
+
+/// 芳香键的键级
+pub(crate) const AROMATIC_BOND: u8 = 4;
+
+/// 生成二维坐标时的键长，与常见绘图软件一致
+const BOND_LENGTH: f64 = 1.5;
+
+/// 生成二维坐标时，原子数不超过该值的连通部分才用弹簧模型调整
+const LAYOUT_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Atom {
+    pub element: String,
+    pub charge: i8,
+    /// 包括氢在内的总价态，未指定时按常见价态推算隐含氢
+    pub valence: Option<u8>,
+    pub coords: [f64; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Bond {
+    /// 两端原子的序号，从0开始
+    pub atoms: (usize, usize),
+    /// 1、2、3为单键、双键、三键，4为芳香键
+    pub order: u8,
+}
+
+/// MOL块或SMILES中的连接表
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Molecule {
+    pub atoms: Vec<Atom>,
+    pub bonds: Vec<Bond>,
+}
+
+impl Molecule {
+    fn add_atom(&mut self, element: &str, charge: i8) -> usize {
+        self.atoms.push(Atom {
+            element: element.to_string(),
+            charge,
+            valence: None,
+            coords: [0.0; 3],
+        });
+        self.atoms.len() - 1
+    }
+
+    /// 原子已经使用的价态，芳香键按1.5计算并向下取整
+    fn bonded_valence(&self, atom: usize) -> u8 {
+        let halves: u32 = self
+            .bonds
+            .iter()
+            .filter(|bond| bond.atoms.0 == atom || bond.atoms.1 == atom)
+            .map(|bond| match bond.order {
+                AROMATIC_BOND => 3,
+                order => order as u32 * 2,
+            })
+            .sum();
+        (halves / 2).min(u8::MAX as u32) as u8
+    }
+
+    /// 隐含氢的个数。带电原子按等电子的元素确定价态，例如N+按C、O-按F计算；
+    /// 金属和不常见的元素没有隐含氢
+    fn implicit_hydrogens(&self, atom: usize) -> u8 {
+        let bonded = self.bonded_valence(atom);
+        let Atom {
+            element,
+            charge,
+            valence,
+            ..
+        } = &self.atoms[atom];
+        if let Some(valence) = valence {
+            return valence.saturating_sub(bonded);
+        }
+        let valences = |symbol: &str| {
+            VALENCES
+                .into_iter()
+                .find(|(element, _)| *element == symbol)
+                .map(|(_, valences)| valences)
+        };
+        if valences(element).is_none() {
+            return 0;
+        }
+        let Some(number) = formula::ELEMENTS.iter().position(|e| e == element) else {
+            return 0;
+        };
+        let effective = formula::ELEMENTS
+            .get((number as i32 - *charge as i32).max(0) as usize)
+            .and_then(|symbol| valences(symbol));
+        effective
+            .and_then(|valences| valences.iter().find(|valence| **valence >= bonded))
+            .map(|valence| valence - bonded)
+            .unwrap_or_default()
+    }
+
+    /// 各元素的原子个数，包括隐含氢。含有未知元素或没有原子时返回`None`
+    pub(crate) fn element_counts(&self) -> Option<BTreeMap<&'static str, u32>> {
+        let mut counts = BTreeMap::new();
+        for (index, atom) in self.atoms.iter().enumerate() {
+            *counts.entry(formula::element(&atom.element)?).or_default() += 1;
+            let hydrogens = self.implicit_hydrogens(index) as u32;
+            if hydrogens > 0 {
+                *counts.entry("H").or_default() += hydrogens;
+            }
+        }
+        (!counts.is_empty()).then_some(counts)
+    }
+
+    pub(crate) fn charge(&self) -> i8 {
+        let charge: i32 = self.atoms.iter().map(|atom| atom.charge as i32).sum();
+        charge.clamp(i8::MIN as i32, i8::MAX as i32) as i8
+    }
+
+    /// 生成二维坐标，覆盖原有坐标。各连通部分先按广度优先展开为树形，原子数不多时再以弹簧模型调整，
+    /// 使键长接近`BOND_LENGTH`、未成键的原子彼此分开，最后沿x轴依次排开。坐标只用于示意
+    pub(crate) fn layout(&mut self) {
+        let count = self.atoms.len();
+        let mut neighbors = vec![vec![]; count];
+        let mut bonded = HashSet::new();
+        for bond in &self.bonds {
+            let (first, second) = bond.atoms;
+            neighbors[first].push(second);
+            neighbors[second].push(first);
+            bonded.insert((first.min(second), first.max(second)));
+        }
+        let mut positions = vec![[0.0f64; 2]; count];
+        let mut placed = vec![false; count];
+        let mut offset = 0.0;
+        for start in 0..count {
+            if placed[start] {
+                continue;
+            }
+            placed[start] = true;
+            let mut members = vec![start];
+            // 原子、来自父原子的方向和深度
+            let mut queue = VecDeque::from([(start, 0.0, 0)]);
+            while let Some((atom, direction, depth)) = queue.pop_front() {
+                let children = neighbors[atom]
+                    .iter()
+                    .copied()
+                    .filter(|&neighbor| !placed[neighbor])
+                    .collect::<Vec<_>>();
+                let total = children.len() as f64;
+                for (index, child) in children.into_iter().enumerate() {
+                    // 子原子与父原子均匀分布在四周，只有一个子原子时左右交替形成折线
+                    let angle = match (depth, total as usize) {
+                        (0, _) => TAU * index as f64 / total,
+                        (_, 1) if depth % 2 == 0 => direction + PI / 3.0,
+                        (_, 1) => direction - PI / 3.0,
+                        _ => direction - PI + TAU * (index + 1) as f64 / (total + 1.0),
+                    };
+                    let [x, y] = positions[atom];
+                    positions[child] =
+                        [x + BOND_LENGTH * angle.cos(), y + BOND_LENGTH * angle.sin()];
+                    placed[child] = true;
+                    members.push(child);
+                    queue.push_back((child, angle, depth + 1));
+                }
+            }
+            if members.len() <= LAYOUT_LIMIT {
+                // 先按原子间隔的键数做应力优化，使环闭合、支链展开，再用弹簧模型修正键长
+                let index = (0..)
+                    .zip(&members)
+                    .map(|(index, atom)| (*atom, index))
+                    .collect::<HashMap<_, usize>>();
+                let hops = members
+                    .iter()
+                    .map(|&source| {
+                        let mut hops = vec![0; members.len()];
+                        let mut visited = vec![false; members.len()];
+                        visited[index[&source]] = true;
+                        let mut queue = VecDeque::from([source]);
+                        while let Some(atom) = queue.pop_front() {
+                            let next = hops[index[&atom]] + 1;
+                            for neighbor in &neighbors[atom] {
+                                if !visited[index[neighbor]] {
+                                    visited[index[neighbor]] = true;
+                                    hops[index[neighbor]] = next;
+                                    queue.push_back(*neighbor);
+                                }
+                            }
+                        }
+                        hops
+                    })
+                    .collect::<Vec<_>>();
+                for _ in 0..100 {
+                    for a in 0..members.len() {
+                        let (mut sum, mut total) = ([0.0f64; 2], 0.0);
+                        for b in (0..members.len()).filter(|b| *b != a) {
+                            let target = BOND_LENGTH * hops[a][b] as f64;
+                            let weight = 1.0 / (target * target);
+                            let (from, to) = (positions[members[a]], positions[members[b]]);
+                            let delta = [from[0] - to[0], from[1] - to[1]];
+                            let distance = delta[0].hypot(delta[1]).max(1e-6);
+                            for axis in 0..2 {
+                                sum[axis] += weight * (to[axis] + target * delta[axis] / distance);
+                            }
+                            total += weight;
+                        }
+                        if total > 0.0 {
+                            positions[members[a]] = [sum[0] / total, sum[1] / total];
+                        }
+                    }
+                }
+                // 未成键原子的最小距离取六元环中相隔一个原子的距离，使环接近正多边形
+                let spacing = BOND_LENGTH * 3.0f64.sqrt();
+                let iterations = 300;
+                for iteration in 0..iterations {
+                    let step = 0.5 * (1.0 - iteration as f64 / iterations as f64) + 0.05;
+                    let mut forces = vec![[0.0f64; 2]; members.len()];
+                    for a in 0..members.len() {
+                        for b in a + 1..members.len() {
+                            let (i, j) = (members[a], members[b]);
+                            let mut delta = [
+                                positions[j][0] - positions[i][0],
+                                positions[j][1] - positions[i][1],
+                            ];
+                            if delta == [0.0, 0.0] {
+                                delta = [1e-3 * (a + 1) as f64, 1e-3 * (b + 1) as f64];
+                            }
+                            let distance = delta[0].hypot(delta[1]);
+                            let force = match bonded.contains(&(i.min(j), i.max(j))) {
+                                true => distance - BOND_LENGTH,
+                                // 排斥弱于键长约束，取代基较多时优先保持键长
+                                false if distance < spacing => (distance - spacing) / 4.0,
+                                false => continue,
+                            } / 2.0;
+                            for axis in 0..2 {
+                                let component = force * delta[axis] / distance;
+                                forces[a][axis] += component;
+                                forces[b][axis] -= component;
+                            }
+                        }
+                    }
+                    for (atom, force) in members.iter().zip(&forces) {
+                        positions[*atom][0] += force[0] * step;
+                        positions[*atom][1] += force[1] * step;
+                    }
+                }
+            }
+            let bounds = |axis: usize| {
+                members
+                    .iter()
+                    .map(|atom| positions[*atom][axis])
+                    .fold((f64::MAX, f64::MIN), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    })
+            };
+            let ((min_x, max_x), (min_y, max_y)) = (bounds(0), bounds(1));
+            for atom in &members {
+                let [x, y] = positions[*atom];
+                self.atoms[*atom].coords = [x - min_x + offset, y - (min_y + max_y) / 2.0, 0.0];
+            }
+            offset += max_x - min_x + 2.0 * BOND_LENGTH;
+        }
+    }
+}
+
+/// 按字符位置截取定长字段并去掉空格，行长度不足时返回空字符串
+fn field(line: &str, start: usize, end: usize) -> &str {
+    line.get(start..end.min(line.len()))
+        .or_else(|| line.get(start..))
+        .unwrap_or_default()
+        .trim()
+}
+
+fn number<T: std::str::FromStr + Default>(text: &str, what: &str) -> Result<T, String> {
+    match text {
+        "" => Ok(T::default()),
+        text => text
+            .parse()
+            .map_err(|_| format!("{}“{}”不是有效的数字", what, text)),
+    }
+}
+
+/// 解析MOL块的连接表，支持V2000和V3000格式，`lines`从标题行开始
+pub(crate) fn parse_molblock(lines: &[&str]) -> Result<Molecule, String> {
+    let counts = lines.get(3).ok_or("MOL块不完整，缺少计数行")?;
+    if counts.contains("V3000") {
+        return parse_v3000(&lines[4..]);
+    }
+    let atom_count: usize = number(field(counts, 0, 3), "原子数")?;
+    let bond_count: usize = number(field(counts, 3, 6), "化学键数")?;
+    let mut molecule = Molecule::default();
+    let mut rest = lines[4..].iter();
+    for _ in 0..atom_count {
+        let line = rest.next().ok_or("MOL块中的原子数少于计数行中的原子数")?;
+        let charge = match number::<u8>(field(line, 36, 39), "电荷")? {
+            1 => 3,
+            2 => 2,
+            3 => 1,
+            5 => -1,
+            6 => -2,
+            7 => -3,
+            _ => 0,
+        };
+        let index = molecule.add_atom(field(line, 31, 34), charge);
+        let atom = &mut molecule.atoms[index];
+        for (axis, start) in [0, 10, 20].into_iter().enumerate() {
+            atom.coords[axis] = number(field(line, start, start + 10), "坐标")?;
+        }
+        atom.valence = match number::<u8>(field(line, 48, 51), "价态")? {
+            0 => None,
+            15 => Some(0),
+            valence => Some(valence),
+        };
+    }
+    for _ in 0..bond_count {
+        let line = rest
+            .next()
+            .ok_or("MOL块中的化学键数少于计数行中的化学键数")?;
+        let first: usize = number(field(line, 0, 3), "原子序号")?;
+        let second: usize = number(field(line, 3, 6), "原子序号")?;
+        let order = number(field(line, 6, 9), "键型")?;
+        add_bond(&mut molecule, first, second, order)?;
+    }
+    // 属性块中的电荷取代原子块中的电荷
+    let mut charged = false;
+    for line in rest {
+        if line.starts_with("M  END") {
+            break;
+        }
+        if let Some(values) = line.strip_prefix("M  CHG") {
+            if !charged {
+                molecule.atoms.iter_mut().for_each(|atom| atom.charge = 0);
+                charged = true;
+            }
+            let values = values.split_whitespace().skip(1).collect::<Vec<_>>();
+            for pair in values.chunks(2) {
+                let [atom, charge] = pair else {
+                    return Err("M  CHG行格式不正确".to_string());
+                };
+                let atom: usize = number(atom, "原子序号")?;
+                let atom = atom
+                    .checked_sub(1)
+                    .and_then(|atom| molecule.atoms.get_mut(atom))
+                    .ok_or("M  CHG行中的原子序号超出范围")?;
+                atom.charge = number(charge, "电荷")?;
+            }
+        }
+    }
+    Ok(molecule)
+}
+
+/// 添加化学键，`first`和`second`为从1开始的原子序号
+fn add_bond(molecule: &mut Molecule, first: usize, second: usize, order: u8) -> Result<(), String> {
+    let count = molecule.atoms.len();
+    if first == 0 || second == 0 || first > count || second > count || first == second {
+        return Err(format!("化学键{}-{}引用的原子不存在", first, second));
+    }
+    if !(1..=AROMATIC_BOND).contains(&order) {
+        return Err(format!("不支持的键型{}", order));
+    }
+    molecule.bonds.push(Bond {
+        atoms: (first - 1, second - 1),
+        order,
+    });
+    Ok(())
+}
+
+fn parse_v3000(lines: &[&str]) -> Result<Molecule, String> {
+    // 以`-`结尾的行与下一行相连
+    let mut entries = vec![];
+    let mut pending = String::new();
+    for line in lines {
+        if line.starts_with("M  END") {
+            break;
+        }
+        let Some(content) = line.strip_prefix("M  V30 ") else {
+            continue;
+        };
+        match content.trim_end().strip_suffix('-') {
+            Some(content) => pending.push_str(content),
+            None => {
+                pending.push_str(content.trim_end());
+                entries.push(std::mem::take(&mut pending));
+            }
+        }
+    }
+    let mut molecule = Molecule::default();
+    let mut indices = HashMap::new();
+    let mut section = "";
+    for entry in &entries {
+        let tokens = entry.split_whitespace().collect::<Vec<_>>();
+        match tokens.as_slice() {
+            ["BEGIN", name, ..] => section = name,
+            ["END", ..] => section = "",
+            [index, element, x, y, z, _, options @ ..] if section == "ATOM" => {
+                let index: u32 = number(index, "原子序号")?;
+                let atom = molecule.add_atom(element, 0);
+                indices.insert(index, atom + 1);
+                let atom = &mut molecule.atoms[atom];
+                atom.coords = [number(x, "坐标")?, number(y, "坐标")?, number(z, "坐标")?];
+                for option in options {
+                    match option.split_once('=') {
+                        Some(("CHG", charge)) => atom.charge = number(charge, "电荷")?,
+                        Some(("VAL", valence)) => {
+                            atom.valence = match number::<i8>(valence, "价态")? {
+                                -1 => Some(0),
+                                0 => None,
+                                valence => Some(valence as u8),
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            [_, order, first, second, ..] if section == "BOND" => {
+                let atom = |index: &str| -> Result<usize, String> {
+                    let index: u32 = number(index, "原子序号")?;
+                    Ok(indices.get(&index).copied().unwrap_or_default())
+                };
+                let (first, second) = (atom(first)?, atom(second)?);
+                add_bond(&mut molecule, first, second, number(order, "键型")?)?;
+            }
+            _ => {}
+        }
+    }
+    Ok(molecule)
+}
+
+/// 写出MOL块，原子和化学键都不超过999个时使用V2000格式，否则使用V3000格式
+pub(crate) fn write_molblock(molecule: &Molecule, title: &str) -> String {
+    let header = "  chembank          2D".to_string();
+    let mut lines = vec![title.to_string(), header, String::new()];
+    let (atoms, bonds) = (molecule.atoms.len(), molecule.bonds.len());
+    if atoms <= 999 && bonds <= 999 {
+        lines.push(format!(
+            "{:>3}{:>3}  0  0  0  0  0  0  0  0999 V2000",
+            atoms, bonds
+        ));
+        for atom in &molecule.atoms {
+            let valence = match atom.valence {
+                None => 0,
+                Some(0) => 15,
+                Some(valence) => valence,
+            };
+            let [x, y, z] = atom.coords;
+            lines.push(format!(
+                "{:>10.4}{:>10.4}{:>10.4} {:<3} 0  0  0  0  0{:>3}  0  0  0  0  0  0",
+                x, y, z, atom.element, valence
+            ));
+        }
+        for bond in &molecule.bonds {
+            let (first, second) = bond.atoms;
+            lines.push(format!(
+                "{:>3}{:>3}{:>3}  0",
+                first + 1,
+                second + 1,
+                bond.order
+            ));
+        }
+        let charged = (1..)
+            .zip(&molecule.atoms)
+            .filter(|(_, atom)| atom.charge != 0)
+            .collect::<Vec<_>>();
+        for chunk in charged.chunks(8) {
+            let mut line = format!("M  CHG{:>3}", chunk.len());
+            for (index, atom) in chunk {
+                line.push_str(&format!(" {:>3} {:>3}", index, atom.charge));
+            }
+            lines.push(line);
+        }
+    } else {
+        lines.push("  0  0  0     0  0            999 V3000".to_string());
+        lines.push("M  V30 BEGIN CTAB".to_string());
+        lines.push(format!("M  V30 COUNTS {} {} 0 0 0", atoms, bonds));
+        lines.push("M  V30 BEGIN ATOM".to_string());
+        for (index, atom) in (1..).zip(&molecule.atoms) {
+            let [x, y, z] = atom.coords;
+            let mut line = format!(
+                "M  V30 {} {} {:.4} {:.4} {:.4} 0",
+                index, atom.element, x, y, z
+            );
+            if atom.charge != 0 {
+                line.push_str(&format!(" CHG={}", atom.charge));
+            }
+            match atom.valence {
+                Some(0) => line.push_str(" VAL=-1"),
+                Some(valence) => line.push_str(&format!(" VAL={}", valence)),
+                None => {}
+            }
+            lines.push(line);
+        }
+        lines.push("M  V30 END ATOM".to_string());
+        if bonds > 0 {
+            lines.push("M  V30 BEGIN BOND".to_string());
+            for (index, bond) in (1..).zip(&molecule.bonds) {
+                let (first, second) = bond.atoms;
+                lines.push(format!(
+                    "M  V30 {} {} {} {}",
+                    index,
+                    bond.order,
+                    first + 1,
+                    second + 1
+                ));
+            }
+            lines.push("M  V30 END BOND".to_string());
+        }
+        lines.push("M  V30 END CTAB".to_string());
+    }
+    lines.push("M  END".to_string());
+    lines.join("\n")
+}
+
+/// 解析SMILES为连接表，坐标全部为0。不保留立体化学和同位素信息，方括号原子的氢个数记为价态
+pub(crate) fn parse_smiles(smiles: &str) -> Result<Molecule, String> {
+    let chars = smiles.trim().chars().collect::<Vec<_>>();
+    let mut molecule = Molecule::default();
+    let mut aromatic = vec![];
+    // 方括号原子的序号和氢个数，解析完成后换算为价态
+    let mut bracket_hydrogens = vec![];
+    let mut previous: Option<usize> = None;
+    let mut branches = vec![];
+    let mut pending: Option<u8> = None;
+    let mut rings: HashMap<u32, (usize, Option<u8>)> = HashMap::new();
+    let mut i = 0;
+    let connect = |molecule: &mut Molecule,
+                   aromatic: &[bool],
+                   first: usize,
+                   second: usize,
+                   order: Option<u8>| {
+        let order = order.unwrap_or(match aromatic[first] && aromatic[second] {
+            true => AROMATIC_BOND,
+            false => 1,
+        });
+        molecule.bonds.push(Bond {
+            atoms: (first, second),
+            order,
+        });
+    };
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        let atom = match c {
+            '(' => {
+                branches.push(previous.ok_or("分支前没有原子")?);
+                continue;
+            }
+            ')' => {
+                previous = Some(branches.pop().ok_or("括号不匹配")?);
+                continue;
+            }
+            '-' | '/' | '\\' => {
+                pending = Some(1);
+                continue;
+            }
+            '=' => {
+                pending = Some(2);
+                continue;
+            }
+            '#' => {
+                pending = Some(3);
+                continue;
+            }
+            ':' => {
+                pending = Some(AROMATIC_BOND);
+                continue;
+            }
+            '.' => {
+                previous = None;
+                continue;
+            }
+            '0'..='9' | '%' => {
+                let label = match c {
+                    '%' => {
+                        let digits = chars.get(i..i + 2).ok_or("%后应有两位环标记")?;
+                        i += 2;
+                        digits
+                            .iter()
+                            .collect::<String>()
+                            .parse()
+                            .map_err(|_| "%后应有两位环标记")?
+                    }
+                    c => c.to_digit(10).unwrap_or_default(),
+                };
+                let current = previous.ok_or("环标记前没有原子")?;
+                match rings.remove(&label) {
+                    Some((other, order)) => {
+                        connect(&mut molecule, &aromatic, other, current, pending.or(order))
+                    }
+                    None => {
+                        rings.insert(label, (current, pending));
+                    }
+                }
+                pending = None;
+                continue;
+            }
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| *c == ']')
+                    .ok_or("方括号不匹配")?;
+                let content = &chars[i..i + end];
+                i += end + 1;
+                let (element, is_aromatic, charge, hydrogens) = parse_bracket(content)?;
+                let atom = molecule.add_atom(&element, charge);
+                aromatic.push(is_aromatic);
+                bracket_hydrogens.push((atom, hydrogens));
+                atom
+            }
+            c => {
+                let two = chars
+                    .get(i - 1..i + 1)
+                    .map(|c| c.iter().collect::<String>());
+                let (symbol, length) = match two.as_deref() {
+                    Some("Cl") | Some("Br") => (two.clone().unwrap_or_default(), 2),
+                    _ => (c.to_string(), 1),
+                };
+                i += length - 1;
+                let is_aromatic = AROMATIC.contains(&symbol.as_str());
+                if !is_aromatic
+                    && !["B", "C", "N", "O", "P", "S", "F", "I", "Cl", "Br", "*"]
+                        .contains(&symbol.as_str())
+                {
+                    return Err(format!("无法识别的字符“{}”", symbol));
+                }
+                let atom = molecule.add_atom(&capitalize(&symbol), 0);
+                aromatic.push(is_aromatic);
+                atom
+            }
+        };
+        if let Some(previous) = previous {
+            connect(&mut molecule, &aromatic, previous, atom, pending.take());
+        }
+        previous = Some(atom);
+    }
+    if !branches.is_empty() {
+        return Err("括号不匹配".to_string());
+    }
+    if let Some(label) = rings.keys().min() {
+        return Err(format!("环标记{}没有闭合", label));
+    }
+    for (atom, hydrogens) in bracket_hydrogens {
+        let valence = molecule.bonded_valence(atom).saturating_add(hydrogens);
+        molecule.atoms[atom].valence = Some(valence);
+    }
+    Ok(molecule)
+}
+
+fn capitalize(symbol: &str) -> String {
+    let mut chars = symbol.chars();
+    chars
+        .next()
+        .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+        .unwrap_or_default()
+}
+
+/// 解析方括号原子，返回元素、是否芳香、电荷和氢个数
+fn parse_bracket(content: &[char]) -> Result<(String, bool, i8, u8), String> {
+    let text = content.iter().collect::<String>();
+    let error = || format!("无法识别的原子“[{}]”", text);
+    let mut i = 0;
+    while i < content.len() && content[i].is_ascii_digit() {
+        i += 1;
+    }
+    let start = i;
+    let (element, aromatic) = match content.get(i) {
+        Some('*') => {
+            i += 1;
+            ("*".to_string(), false)
+        }
+        Some(c) if c.is_ascii_uppercase() => {
+            i += 1;
+            if let Some(lower) = content.get(i).filter(|c| c.is_ascii_lowercase()) {
+                let symbol = format!("{}{}", c, lower);
+                if formula::element(&symbol).is_some() {
+                    i += 1;
+                }
+            }
+            (content[start..i].iter().collect::<String>(), false)
+        }
+        Some(c) if c.is_ascii_lowercase() => {
+            let two = content.get(i..i + 2).map(|c| c.iter().collect::<String>());
+            let symbol = match two.as_deref() {
+                Some("se") | Some("as") | Some("te") => two.unwrap_or_default(),
+                _ => c.to_string(),
+            };
+            i += symbol.len();
+            (capitalize(&symbol), true)
+        }
+        _ => return Err(error()),
+    };
+    while content.get(i) == Some(&'@') {
+        i += 1;
+    }
+    let mut hydrogens = 0;
+    if content.get(i) == Some(&'H') {
+        i += 1;
+        hydrogens = 1;
+        let start = i;
+        while i < content.len() && content[i].is_ascii_digit() {
+            i += 1;
+        }
+        if i > start {
+            hydrogens = content[start..i]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .map_err(|_| error())?;
+        }
+    }
+    let mut charge: i8 = 0;
+    if let Some(sign @ ('+' | '-')) = content.get(i).copied() {
+        let unit = if sign == '+' { 1 } else { -1 };
+        i += 1;
+        let start = i;
+        while i < content.len() && content[i].is_ascii_digit() {
+            i += 1;
+        }
+        charge = match i > start {
+            true => {
+                let magnitude: i8 = content[start..i]
+                    .iter()
+                    .collect::<String>()
+                    .parse()
+                    .map_err(|_| error())?;
+                unit * magnitude
+            }
+            false => {
+                let mut charge = unit;
+                while content.get(i) == Some(&sign) {
+                    charge += unit;
+                    i += 1;
+                }
+                charge
+            }
+        };
+    }
+    if content.get(i) == Some(&':') {
+        i = content.len();
+    }
+    if i != content.len() {
+        return Err(error());
+    }
+    Ok((element, aromatic, charge, hydrogens))
+}
+
+#[test]
+fn test_smiles_formula() {
+    let formula = |smiles: &str| {
+        let molecule = parse_smiles(smiles).unwrap();
+        (
+            formula::hill(&molecule.element_counts().unwrap()),
+            molecule.charge(),
+        )
+    };
+    assert_eq!(formula("O=C([O-])[O-]"), ("CO3".to_string(), -2));
+    assert_eq!(formula("c1ccccc1"), ("C6H6".to_string(), 0));
+    assert_eq!(formula("C[N+](=O)[O-]"), ("CH3NO2".to_string(), 0));
+    assert_eq!(formula("[NH4+]"), ("H4N".to_string(), 1));
+    assert_eq!(formula("[Na+].[Cl-]"), ("ClNa".to_string(), 0));
+    assert_eq!(formula("c1cc[nH]c1"), ("C4H5N".to_string(), 0));
+    assert_eq!(
+        formula("Cc1c(cc(cc1[N+](=O)[O-])[N+](=O)[O-])[N+](=O)[O-]"),
+        ("C7H5N3O6".to_string(), 0)
+    );
+    assert_eq!(formula("C1CC%10CC1.C%10"), ("C6H12".to_string(), 0));
+    assert!(parse_smiles("C(C").is_err());
+    assert!(parse_smiles("C1CC").is_err());
+    assert!(parse_smiles("[Xx+]C").is_err());
+}
+
+#[test]
+fn test_layout() {
+    for smiles in [
+        "c1ccccc1",
+        "[Na+].[Cl-]",
+        "C1CC%10CC1.C%10",
+        "Cc1c(cc(cc1[N+](=O)[O-])[N+](=O)[O-])[N+](=O)[O-]",
+        "O=S(=O)([O-])[O-]",
+        "CC(C)(C)CCCCO",
+    ] {
+        let Ok(mut molecule) = parse_smiles(smiles) else {
+            continue;
+        };
+        molecule.layout();
+        let distance = |i: usize, j: usize| {
+            let (a, b) = (molecule.atoms[i].coords, molecule.atoms[j].coords);
+            (a[0] - b[0]).hypot(a[1] - b[1])
+        };
+        for bond in &molecule.bonds {
+            let length = distance(bond.atoms.0, bond.atoms.1);
+            assert!((1.3..1.7).contains(&length), "{}：键长{}", smiles, length);
+        }
+        for i in 0..molecule.atoms.len() {
+            for j in i + 1..molecule.atoms.len() {
+                assert!(distance(i, j) > 1.0, "{}：原子{}和{}重叠", smiles, i, j);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_molblock_round_trip() {
+    let molecule = parse_smiles("[O-][N+](=O)C=C").unwrap();
+    let molblock = write_molblock(&molecule, "硝基乙烯");
+    let lines = molblock.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "硝基乙烯");
+    assert_eq!(lines[3], "  5  4  0  0  0  0  0  0  0  0999 V2000");
+    assert_eq!(
+        lines[4],
+        "    0.0000    0.0000    0.0000 O   0  0  0  0  0  1  0  0  0  0  0  0"
+    );
+    assert_eq!(lines[13], "M  CHG  2   1  -1   2   1");
+    let parsed = parse_molblock(&lines).unwrap();
+    assert_eq!(parsed, molecule);
+    assert_eq!(formula::hill(&parsed.element_counts().unwrap()), "C2H3NO2");
+
+    // 超过999个原子时改用V3000格式
+    let chain = parse_smiles(&"C".repeat(1000)).unwrap();
+    let molblock = write_molblock(&chain, "");
+    let lines = molblock.lines().collect::<Vec<_>>();
+    assert!(lines[3].ends_with("V3000"));
+    let parsed = parse_molblock(&lines).unwrap();
+    assert_eq!(parsed, chain);
+    assert_eq!(
+        formula::hill(&parsed.element_counts().unwrap()),
+        "C1000H2002"
+    );
+}
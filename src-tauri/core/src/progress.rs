@@ -0,0 +1,90 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::{error::Result, ChembankError};
+
+/// 每处理这么多条记录报告一次进度
+const REPORT_INTERVAL: u32 = 100;
+
+/// 导入导出的阶段
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub enum Phase {
+    Structures,
+    Properties,
+    Components,
+    Images,
+}
+
+/// 导入导出的进度，`processed`为当前阶段已处理的记录数
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub struct Progress {
+    pub phase: Phase,
+    pub processed: u32,
+    pub total: u32,
+}
+
+type Callback = Arc<dyn Fn(Progress) + Send + Sync>;
+
+/// 导入导出任务的进度回调和取消标记。克隆得到的句柄共享同一个取消标记，
+/// 可以在任务运行时从其他线程调用`cancel`
+#[derive(Clone, Default)]
+pub struct Job {
+    cancelled: Arc<AtomicBool>,
+    on_progress: Option<Callback>,
+}
+
+impl Job {
+    pub fn new(on_progress: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            on_progress: Some(Arc::new(on_progress)),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// 处理每条记录前调用，在阶段开始、结束和每隔一定数量的记录时报告进度；任务已取消时返回错误
+    pub(crate) fn step(&self, phase: Phase, processed: u32, total: u32) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(ChembankError::Cancelled);
+        }
+        if processed.is_multiple_of(REPORT_INTERVAL) || processed == total {
+            if let Some(on_progress) = &self.on_progress {
+                on_progress(Progress {
+                    phase,
+                    processed,
+                    total,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_job_progress() {
+    use std::sync::Mutex;
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let job = Job::new({
+        let reports = reports.clone();
+        move |progress| reports.lock().unwrap().push(progress.processed)
+    });
+    for processed in 0..=250 {
+        job.step(Phase::Structures, processed, 250).unwrap();
+    }
+    assert_eq!(*reports.lock().unwrap(), vec![0, 100, 200, 250]);
+    job.clone().cancel();
+    assert_eq!(job.step(Phase::Images, 0, 1), Err(ChembankError::Cancelled));
+}
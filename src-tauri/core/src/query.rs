@@ -0,0 +1,510 @@
+use std::str::FromStr;
+
+use sea_orm::{
+    prelude::Expr,
+    sea_query::{Alias, ExprTrait, Func, JoinType, SelectStatement, SimpleExpr},
+    Condition,
+};
+
+use entities::{formula, quantity::Quantity, *};
+
+use crate::{error::Result, ChembankError};
+
+/// 检索表达式，例如`name:硝酸 AND charge=0 AND NOT remarks:"待核实"`。
+///
+/// - `字段:值`对文本字段表示包含，对数值字段表示等于；另有`=`、`!=`、`<`、`<=`、`>`、`>=`；
+/// - 文本字段为`name`、`formula`、`smiles`、`remarks`、`references`，缺失的文本按空字符串比较；
+/// - 数值字段为`id`、`charge`和各属性数值（如`density`、`det_velocity`），没有数值的结构不满足任何比较；
+/// - 元素符号（如`C`、`Na`）作为数值字段表示该元素的原子个数，分子式中没有该元素时为0；
+/// - `element:N`表示含有该元素，`element!=N`表示不含该元素，`element:metal`表示含有任意金属元素；
+///   分子式无法解析的结构不满足任何元素条件；
+/// - 单独的值在名称、分子式、SMILES、备注和参考文献中检索；
+/// - `component:1`表示直接含有ID为1的组成部分，`component:钠离子`按名称指定组成部分，
+///   `component:(charge=-2)`表示含有满足括号内条件的组成部分；`component*`同时匹配间接的组成部分，
+///   `!=`表示不含有这样的组成部分；
+/// - 条件之间可以用`AND`、`OR`、`NOT`和括号组合，相邻的条件默认为`AND`，`NOT`表示取补集；
+/// - 包含空白、括号、冒号或比较符号的值需要用双引号括起，引号内可用`\"`和`\\`转义
+#[derive(Debug, Clone)]
+pub struct Query {
+    condition: Condition,
+}
+
+impl Query {
+    /// 转换为检索条件，其中引用了`structures`和`property`两张表，查询时需要连接属性表
+    pub fn condition(&self) -> Condition {
+        self.condition.clone()
+    }
+}
+
+impl FromStr for Query {
+    type Err = ChembankError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            query: s,
+            tokens: tokenize(s)?,
+            index: 0,
+        };
+        let condition = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error(token.position, "无法识别的内容，可能缺少左括号或AND/OR"));
+        }
+        Ok(Self { condition })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Colon,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Operator(Operator),
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+}
+
+/// 词法单元，`position`为其首字符在表达式中的位置，从0开始按字符计数
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn syntax_error(query: &str, position: usize, message: &str) -> ChembankError {
+    ChembankError::QuerySyntax {
+        query: query.to_string(),
+        position: position as u32,
+        message: message.to_string(),
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '(' | ')' | '"' | ':' | '=' | '!' | '<' | '>')
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let chars = query.chars().collect::<Vec<_>>();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let position = i;
+        let next = chars.get(i + 1).copied();
+        let kind = match chars[i] {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            ':' => TokenKind::Operator(Operator::Colon),
+            '=' => TokenKind::Operator(Operator::Eq),
+            '!' if next == Some('=') => TokenKind::Operator(Operator::Ne),
+            '!' => return Err(syntax_error(query, position, "“!”只能用于“!=”")),
+            '<' if next == Some('=') => TokenKind::Operator(Operator::Le),
+            '<' => TokenKind::Operator(Operator::Lt),
+            '>' if next == Some('=') => TokenKind::Operator(Operator::Ge),
+            '>' => TokenKind::Operator(Operator::Gt),
+            '"' => {
+                let mut text = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(syntax_error(query, position, "引号没有闭合")),
+                        Some('"') => break,
+                        Some('\\') if matches!(chars.get(i + 1), Some('"' | '\\')) => {
+                            text.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&c) => {
+                            text.push(c);
+                            i += 1;
+                        }
+                    }
+                }
+                TokenKind::Quoted(text)
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !is_delimiter(chars[i]) {
+                    i += 1;
+                }
+                let word = chars[start..i].iter().collect::<String>();
+                tokens.push(Token {
+                    kind: match word.as_str() {
+                        "AND" => TokenKind::And,
+                        "OR" => TokenKind::Or,
+                        "NOT" => TokenKind::Not,
+                        _ => TokenKind::Word(word),
+                    },
+                    position,
+                });
+                continue;
+            }
+        };
+        i += match kind {
+            TokenKind::Operator(Operator::Ne | Operator::Le | Operator::Ge) => 2,
+            _ => 1,
+        };
+        tokens.push(Token { kind, position });
+    }
+    Ok(tokens)
+}
+
+/// 检索表达式中可用的字段
+enum Field {
+    Text(SimpleExpr),
+    Number(SimpleExpr),
+    Element,
+}
+
+impl Field {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "name" => Self::Text(Expr::col((structure::Entity, structure::Column::Name)).into()),
+            "formula" => {
+                Self::Text(Expr::col((structure::Entity, structure::Column::Formula)).into())
+            }
+            "smiles" => {
+                Self::Text(Expr::col((structure::Entity, structure::Column::Smiles)).into())
+            }
+            "remarks" => {
+                Self::Text(Expr::col((property::Entity, property::Column::Remarks)).into())
+            }
+            "references" => {
+                Self::Text(Expr::col((property::Entity, property::Column::References)).into())
+            }
+            "id" => Self::Number(Expr::col((structure::Entity, structure::Column::Id)).into()),
+            "charge" => {
+                Self::Number(Expr::col((structure::Entity, structure::Column::Charge)).into())
+            }
+            "element" => Self::Element,
+            name => match formula::element(name) {
+                Some(element) => Self::Number(Expr::cust_with_values(
+                    "(SELECT SUM(CASE WHEN element = ? THEN count ELSE 0 END) FROM compositions \
+                    WHERE compositions.structure_id = structures.id)",
+                    [element],
+                )),
+                None => Self::Number(
+                    Expr::col((property::Entity, Quantity::from_name(name)?.value_column())).into(),
+                ),
+            },
+        })
+    }
+
+    /// 不加字段名时检索的文本字段
+    fn keyword_columns() -> [SimpleExpr; 5] {
+        [
+            Expr::col((structure::Entity, structure::Column::Name)).into(),
+            Expr::col((structure::Entity, structure::Column::Formula)).into(),
+            Expr::col((structure::Entity, structure::Column::Smiles)).into(),
+            Expr::col((property::Entity, property::Column::Remarks)).into(),
+            Expr::col((property::Entity, property::Column::References)).into(),
+        ]
+    }
+}
+
+/// 缺失的文本按空字符串处理，保证比较结果不为NULL，`NOT`才能得到补集
+fn text(column: SimpleExpr) -> Expr {
+    Expr::expr(Func::coalesce([column, Expr::val("").into()]))
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    tokens: Vec<Token>,
+    index: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    /// 下一个词法单元的位置，已经到达末尾时指向表达式末尾
+    fn position(&self) -> usize {
+        self.peek()
+            .map(|token| token.position)
+            .unwrap_or_else(|| self.query.chars().count())
+    }
+
+    fn error(&self, position: usize, message: &str) -> ChembankError {
+        syntax_error(self.query, position, message)
+    }
+
+    fn parse_or(&mut self) -> Result<Condition> {
+        let mut conditions = vec![self.parse_and()?];
+        while matches!(
+            self.peek(),
+            Some(Token {
+                kind: TokenKind::Or,
+                ..
+            })
+        ) {
+            self.next();
+            conditions.push(self.parse_and()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.pop().unwrap(),
+            _ => conditions
+                .into_iter()
+                .fold(Condition::any(), Condition::add),
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Condition> {
+        let mut conditions = vec![self.parse_unary()?];
+        loop {
+            match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::And) => {
+                    self.next();
+                }
+                Some(
+                    TokenKind::Word(_)
+                    | TokenKind::Quoted(_)
+                    | TokenKind::LeftParen
+                    | TokenKind::Not,
+                ) => {}
+                _ => break,
+            }
+            conditions.push(self.parse_unary()?);
+        }
+        Ok(match conditions.len() {
+            1 => conditions.pop().unwrap(),
+            _ => conditions
+                .into_iter()
+                .fold(Condition::all(), Condition::add),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Condition> {
+        match self.peek().map(|token| token.kind.clone()) {
+            Some(TokenKind::Not) => {
+                self.next();
+                Ok(self.parse_unary()?.not())
+            }
+            Some(TokenKind::LeftParen) => {
+                let position = self.position();
+                self.next();
+                let condition = self.parse_or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RightParen,
+                        ..
+                    }) => Ok(condition),
+                    _ => Err(self.error(position, "括号没有闭合")),
+                }
+            }
+            _ => self.parse_term(),
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Condition> {
+        let position = self.position();
+        let (word, quoted) = match self.next().map(|token| token.kind) {
+            Some(TokenKind::Word(word)) => (word, false),
+            Some(TokenKind::Quoted(text)) => (text, true),
+            _ => return Err(self.error(position, "此处应为检索条件")),
+        };
+        let operator = match self.peek() {
+            Some(Token {
+                kind: TokenKind::Operator(operator),
+                ..
+            }) if !quoted => *operator,
+            _ => {
+                let keyword = format!("%{}%", word);
+                return Ok(Field::keyword_columns()
+                    .into_iter()
+                    .fold(Condition::any(), |condition, column| {
+                        condition.add(text(column).like(&keyword))
+                    }));
+            }
+        };
+        let operator_position = self.position();
+        self.next();
+        // 组成部分条件的值可以是括号括起的子表达式，需要单独解析
+        match word.as_str() {
+            "component" => return self.parse_component(false, operator, operator_position),
+            "component*" => return self.parse_component(true, operator, operator_position),
+            _ => {}
+        }
+        let field = Field::from_name(&word)
+            .ok_or_else(|| self.error(position, &format!("未知的字段“{}”", word)))?;
+        let value_position = self.position();
+        let value = match self.next().map(|token| token.kind) {
+            Some(TokenKind::Word(value) | TokenKind::Quoted(value)) => value,
+            _ => return Err(self.error(value_position, "缺少检索值")),
+        };
+        let expr = match field {
+            Field::Text(column) => {
+                let column = text(column);
+                match operator {
+                    Operator::Colon => column.like(format!("%{}%", value)),
+                    Operator::Eq => column.eq(value),
+                    Operator::Ne => column.ne(value),
+                    _ => {
+                        return Err(self.error(operator_position, "文本字段只能使用“:”、“=”或“!=”"))
+                    }
+                }
+            }
+            Field::Number(column) => {
+                let number = value
+                    .parse::<f64>()
+                    .map_err(|_| self.error(value_position, &format!("“{}”不是数值", value)))?;
+                let compared = match operator {
+                    Operator::Colon | Operator::Eq => column.clone().eq(number),
+                    Operator::Ne => column.clone().ne(number),
+                    Operator::Lt => column.clone().lt(number),
+                    Operator::Le => column.clone().lte(number),
+                    Operator::Gt => column.clone().gt(number),
+                    Operator::Ge => column.clone().gte(number),
+                };
+                Expr::expr(column).is_not_null().and(compared)
+            }
+            Field::Element => {
+                let elements = match value.as_str() {
+                    formula::METAL => formula::ELEMENTS
+                        .into_iter()
+                        .filter(|element| formula::is_metal(element))
+                        .collect::<Vec<_>>(),
+                    value => vec![formula::element(value).ok_or_else(|| {
+                        self.error(value_position, &format!("未知的元素“{}”", value))
+                    })?],
+                };
+                let contains = Expr::cust_with_values(
+                    format!(
+                        "EXISTS (SELECT 1 FROM compositions WHERE compositions.structure_id = structures.id \
+                        AND element IN ({}))",
+                        vec!["?"; elements.len()].join(", ")
+                    ),
+                    elements,
+                );
+                match operator {
+                    Operator::Colon | Operator::Eq => contains,
+                    Operator::Ne => Expr::cust(
+                        "EXISTS (SELECT 1 FROM compositions WHERE compositions.structure_id = structures.id)",
+                    )
+                    .and(contains.not()),
+                    _ => {
+                        return Err(
+                            self.error(operator_position, "element只能使用“:”、“=”或“!=”")
+                        )
+                    }
+                }
+            }
+        };
+        Ok(Condition::all().add(expr))
+    }
+
+    /// 组成部分条件的值可以是结构ID、结构名称或括号括起的检索表达式
+    fn parse_component(
+        &mut self,
+        transitive: bool,
+        operator: Operator,
+        operator_position: usize,
+    ) -> Result<Condition> {
+        if !matches!(operator, Operator::Colon | Operator::Eq | Operator::Ne) {
+            return Err(self.error(operator_position, "component只能使用“:”、“=”或“!=”"));
+        }
+        let table = Alias::new(match transitive {
+            true => "component_closure",
+            false => "components",
+        });
+        let mut select = SelectStatement::new();
+        select
+            .column((table.clone(), Alias::new("structure_id")))
+            .from(table.clone());
+        let value_position = self.position();
+        let component = match self.next().map(|token| token.kind) {
+            Some(TokenKind::LeftParen) => {
+                let condition = self.parse_or()?;
+                match self.next() {
+                    Some(Token {
+                        kind: TokenKind::RightParen,
+                        ..
+                    }) => Some(condition),
+                    _ => return Err(self.error(value_position, "括号没有闭合")),
+                }
+            }
+            Some(TokenKind::Word(value) | TokenKind::Quoted(value)) => match value.parse::<u32>() {
+                Ok(id) => {
+                    select.and_where(Expr::col((table.clone(), Alias::new("component_id"))).eq(id));
+                    None
+                }
+                Err(_) => Some(Condition::all().add(
+                    text(Expr::col((structure::Entity, structure::Column::Name)).into()).eq(value),
+                )),
+            },
+            _ => return Err(self.error(value_position, "缺少检索值")),
+        };
+        // 子查询中的structures和property指组成部分本身
+        if let Some(condition) = component {
+            select
+                .join(
+                    JoinType::InnerJoin,
+                    structure::Entity,
+                    Expr::col((structure::Entity, structure::Column::Id))
+                        .equals((table, Alias::new("component_id"))),
+                )
+                .join(
+                    JoinType::LeftJoin,
+                    property::Entity,
+                    Expr::col((property::Entity, property::Column::StructureId))
+                        .equals((structure::Entity, structure::Column::Id)),
+                )
+                .cond_where(condition);
+        }
+        let id = Expr::col((structure::Entity, structure::Column::Id));
+        Ok(Condition::all().add(match operator {
+            Operator::Ne => id.not_in_subquery(select),
+            _ => id.in_subquery(select),
+        }))
+    }
+}
+
+#[test]
+fn test_query_syntax() {
+    let position = |query: &str| match query.parse::<Query>() {
+        Err(ChembankError::QuerySyntax { position, .. }) => Some(position),
+        _ => None,
+    };
+    assert!("name:硝酸 AND charge=0 AND NOT remarks:待核实"
+        .parse::<Query>()
+        .is_ok());
+    assert!("(density>=1.8 OR det_velocity>8000) \"C[N+](=O)[O-]\""
+        .parse::<Query>()
+        .is_ok());
+    assert_eq!(position("name:\"硝酸"), Some(5));
+    assert_eq!(position("(name:硝酸 OR formula:NO3"), Some(0));
+    assert_eq!(position("viscosity>1"), Some(0));
+    assert_eq!(position("charge>负一"), Some(7));
+    assert_eq!(position("name>硝酸"), Some(4));
+    assert_eq!(position("name: AND charge=0"), Some(6));
+    assert_eq!(position("name:硝酸 OR"), Some(10));
+    assert_eq!(position("name:硝酸)"), Some(7));
+    assert_eq!(position("element:Xx"), Some(8));
+    assert_eq!(position("element>N"), Some(7));
+    assert_eq!(position("Xx<=6"), Some(0));
+    assert!("component:(charge=-2 component*:1) component!=钠离子"
+        .parse::<Query>()
+        .is_ok());
+    assert_eq!(position("component:(charge=-2"), Some(10));
+    assert_eq!(position("component>1"), Some(9));
+}
@@ -0,0 +1,249 @@
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, ModelTrait,
+    PaginatorTrait, QueryFilter, TransactionTrait,
+};
+
+use entities::*;
+
+use crate::{error::Result, Chembank, ChembankError, Table};
+
+/// 结构详细信息：结构本身、属性、图片、组成部分以及以该结构为组成部分的结构
+pub type StructureDetail = (
+    structure::Model,
+    Option<property::Model>,
+    Option<image::Model>,
+    Vec<(component::Model, Option<structure::Model>)>,
+    Vec<(component::Model, Option<structure::Model>)>,
+);
+
+/// 根据结构的分子式重新写入元素组成，分子式无法解析时不保留任何组成
+pub(crate) async fn write_composition<C: ConnectionTrait>(
+    db: &C,
+    model: &structure::Model,
+) -> Result<()> {
+    composition::Entity::delete_many()
+        .filter(composition::Column::StructureId.eq(model.id))
+        .exec(db)
+        .await?;
+    if let Some(counts) = formula::parse(&model.formula, model.charge) {
+        composition::Entity::insert_many(counts.into_iter().map(|(element, count)| {
+            composition::ActiveModel {
+                structure_id: ActiveValue::set(model.id),
+                element: ActiveValue::set(element.to_string()),
+                count: ActiveValue::set(count),
+            }
+        }))
+        .exec(db)
+        .await?;
+    }
+    Ok(())
+}
+
+impl Chembank {
+    pub async fn structure_count(&self) -> Result<u32> {
+        let count = structure::Entity::find().count(&self.db).await?;
+        Ok(count as u32)
+    }
+
+    pub async fn create_structure(
+        &self,
+        name: Option<String>,
+        formula: String,
+        smiles: Option<String>,
+        charge: i8,
+    ) -> Result<u32> {
+        self.ensure_writable()?;
+        let model = structure::ActiveModel {
+            id: ActiveValue::not_set(),
+            name: ActiveValue::set(name.clone()),
+            formula: ActiveValue::set(formula),
+            smiles: ActiveValue::set(smiles.clone()),
+            charge: ActiveValue::set(charge),
+        };
+        let txn = self.db.begin().await?;
+        let model = model
+            .insert(&txn)
+            .await
+            .map_err(|e| ChembankError::from_structure_write(e, &name, &smiles))?;
+        write_composition(&txn, &model).await?;
+        txn.commit().await?;
+        Ok(model.id)
+    }
+
+    pub async fn update_structure(
+        &self,
+        id: u32,
+        name: Option<String>,
+        formula: String,
+        smiles: Option<String>,
+        charge: i8,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let model = structure::Entity::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or(ChembankError::not_found(Table::Structure, [id]))?;
+        let smiles_changed = model.smiles != smiles;
+        let mut model: structure::ActiveModel = model.into();
+        model.name = ActiveValue::set(name.clone());
+        model.formula = ActiveValue::set(formula);
+        model.smiles = ActiveValue::set(smiles.clone());
+        model.charge = ActiveValue::set(charge);
+        let txn = self.db.begin().await?;
+        let model = model
+            .update(&txn)
+            .await
+            .map_err(|e| ChembankError::from_structure_write(e, &name, &smiles))?;
+        write_composition(&txn, &model).await?;
+        // 导入的结构式与新的SMILES不再对应
+        if smiles_changed {
+            molfile::Entity::delete_by_id(id).exec(&txn).await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn remove_structure(&self, id: u32) -> Result<()> {
+        self.ensure_writable()?;
+        let txn = self.db.begin().await?;
+        let component_of = component::Entity::find()
+            .filter(component::Column::ComponentId.eq(id))
+            .all(&txn)
+            .await?;
+        if !component_of.is_empty() {
+            Err(ChembankError::StillReferencedAsComponent {
+                id,
+                referenced_by: component_of
+                    .into_iter()
+                    .map(|component| component.structure_id)
+                    .collect(),
+            })?;
+        };
+        component::Entity::delete_many()
+            .filter(component::Column::StructureId.eq(id))
+            .exec(&txn)
+            .await?;
+        image::Entity::delete_many()
+            .filter(image::Column::StructureId.eq(id))
+            .exec(&txn)
+            .await?;
+        property::Entity::delete_many()
+            .filter(property::Column::StructureId.eq(id))
+            .exec(&txn)
+            .await?;
+        molfile::Entity::delete_by_id(id).exec(&txn).await?;
+        composition::Entity::delete_many()
+            .filter(composition::Column::StructureId.eq(id))
+            .exec(&txn)
+            .await?;
+        structure::Entity::find_by_id(id)
+            .one(&txn)
+            .await?
+            .ok_or(ChembankError::not_found(Table::Structure, [id]))?
+            .delete(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn set_component(
+        &self,
+        structure_id: u32,
+        component_id: u32,
+        count: u32,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let model = component::ActiveModel {
+            structure_id: ActiveValue::set(structure_id),
+            component_id: ActiveValue::set(component_id),
+            count: ActiveValue::Set(count),
+        };
+        if component::Entity::find_by_id((structure_id, component_id))
+            .one(&self.db)
+            .await?
+            .is_some()
+        {
+            model.update(&self.db).await?;
+        } else {
+            model.insert(&self.db).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn delete_component(&self, structure_id: u32, component_id: u32) -> Result<()> {
+        self.ensure_writable()?;
+        let model = component::Entity::find_by_id((structure_id, component_id))
+            .one(&self.db)
+            .await?
+            .ok_or(ChembankError::not_found(
+                Table::Component,
+                [structure_id, component_id],
+            ))?;
+        model.delete(&self.db).await?;
+        Ok(())
+    }
+
+    pub async fn set_image(
+        &self,
+        structure_id: u32,
+        image: Vec<u8>,
+        filename: String,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let model = image::ActiveModel {
+            structure_id: ActiveValue::set(structure_id),
+            image: ActiveValue::set(image),
+            filename: ActiveValue::set(filename),
+        };
+        if image::Entity::find_by_id(structure_id)
+            .one(&self.db)
+            .await?
+            .is_some()
+        {
+            model.update(&self.db).await?;
+        } else {
+            model.insert(&self.db).await?;
+        };
+
+        Ok(())
+    }
+
+    /// 写入属性，数值列和`value_note`根据文本列重新计算
+    pub async fn set_property(&self, mut model: property::Model) -> Result<()> {
+        self.ensure_writable()?;
+        model.fill_values();
+        let structure_id = model.structure_id;
+        let model: property::ActiveModel = model.into();
+        let model = model.reset_all();
+        if property::Entity::find_by_id(structure_id)
+            .one(&self.db)
+            .await?
+            .is_some()
+        {
+            model.update(&self.db).await?;
+        } else {
+            model.insert(&self.db).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_structure_detail(&self, id: u32) -> Result<StructureDetail> {
+        let model = structure::Entity::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or(ChembankError::not_found(Table::Structure, [id]))?;
+        let property_model = model.find_related(property::Entity).one(&self.db).await?;
+        let image_model = model.find_related(image::Entity).one(&self.db).await?;
+        let components = component::Entity::find()
+            .filter(component::Column::StructureId.eq(model.id))
+            .find_also_linked(links::ComponentStructure)
+            .all(&self.db)
+            .await?;
+        let relateds = component::Entity::find()
+            .filter(component::Column::ComponentId.eq(model.id))
+            .find_also_linked(links::StructureComponent)
+            .all(&self.db)
+            .await?;
+        Ok((model, property_model, image_model, components, relateds))
+    }
+}
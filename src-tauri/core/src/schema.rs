@@ -0,0 +1,292 @@
+use sea_orm::{
+    sea_query::ColumnType, ColumnTrait, EntityName, EntityTrait, IdenStatic, Iterable,
+    PrimaryKeyToColumn,
+};
+use sea_schema::sqlite::{def::TableDef, discovery::SchemaDiscovery};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use entities::*;
+
+use crate::{error::Result, Chembank, ChembankError};
+
+/// SQLite的列类型亲和性，用于比较实体定义与数据库中实际的列类型
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub enum TypeAffinity {
+    Integer,
+    Text,
+    Blob,
+    Real,
+    Numeric,
+}
+
+/// 实体定义与数据库文件之间的一处差异
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[serde(tag = "kind")]
+pub enum SchemaDrift {
+    MissingTable {
+        table: String,
+    },
+    MissingColumn {
+        table: String,
+        column: String,
+    },
+    ExtraColumn {
+        table: String,
+        column: String,
+    },
+    ColumnType {
+        table: String,
+        column: String,
+        expected: TypeAffinity,
+        found: TypeAffinity,
+    },
+    Nullability {
+        table: String,
+        column: String,
+        expected_not_null: bool,
+    },
+    PrimaryKey {
+        table: String,
+        expected: Vec<String>,
+        found: Vec<String>,
+    },
+    MissingUnique {
+        table: String,
+        column: String,
+    },
+}
+
+/// 数据库结构检查结果
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+pub struct SchemaReport {
+    /// 数据库中除迁移记录、全文索引和SQLite内部表以外的其他表
+    pub unexpected_tables: Vec<String>,
+    pub drifts: Vec<SchemaDrift>,
+    /// 数据库中存在其他表但没有结构表，说明它显然不是化合物库
+    pub foreign: bool,
+    /// 尚未执行的迁移，打开数据库进行写入时会自动执行
+    pub pending_migrations: Vec<String>,
+}
+
+struct ExpectedColumn {
+    name: String,
+    affinity: TypeAffinity,
+    not_null: bool,
+    unique: bool,
+}
+
+struct ExpectedTable {
+    name: String,
+    columns: Vec<ExpectedColumn>,
+    primary_key: Vec<String>,
+}
+
+fn expected_table<E: EntityTrait>(entity: E) -> ExpectedTable {
+    ExpectedTable {
+        name: entity.table_name().to_string(),
+        columns: E::Column::iter()
+            .map(|column| {
+                let def = column.def();
+                ExpectedColumn {
+                    name: column.as_str().to_string(),
+                    affinity: affinity(def.get_column_type()),
+                    not_null: !def.is_null(),
+                    unique: def.is_unique(),
+                }
+            })
+            .collect(),
+        primary_key: E::PrimaryKey::iter()
+            .map(|key| key.into_column().as_str().to_string())
+            .collect(),
+    }
+}
+
+fn expected_tables() -> [ExpectedTable; 6] {
+    [
+        expected_table(structure::Entity),
+        expected_table(component::Entity),
+        expected_table(property::Entity),
+        expected_table(image::Entity),
+        expected_table(composition::Entity),
+        expected_table(molfile::Entity),
+    ]
+}
+
+/// 按照SQLite文档中的规则确定声明类型的亲和性
+fn declared_affinity(declared: &str) -> TypeAffinity {
+    let declared = declared.to_uppercase();
+    if declared.contains("INT") {
+        TypeAffinity::Integer
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|name| declared.contains(name))
+    {
+        TypeAffinity::Text
+    } else if declared.contains("BLOB") || declared.is_empty() {
+        TypeAffinity::Blob
+    } else if ["REAL", "FLOA", "DOUB"]
+        .iter()
+        .any(|name| declared.contains(name))
+    {
+        TypeAffinity::Real
+    } else {
+        TypeAffinity::Numeric
+    }
+}
+
+fn affinity(column_type: &ColumnType) -> TypeAffinity {
+    match column_type {
+        ColumnType::TinyInteger
+        | ColumnType::SmallInteger
+        | ColumnType::Integer
+        | ColumnType::BigInteger
+        | ColumnType::TinyUnsigned
+        | ColumnType::SmallUnsigned
+        | ColumnType::Unsigned
+        | ColumnType::BigUnsigned
+        | ColumnType::Boolean => TypeAffinity::Integer,
+        ColumnType::Char(_) | ColumnType::String(_) | ColumnType::Text => TypeAffinity::Text,
+        ColumnType::Binary(_) | ColumnType::VarBinary(_) | ColumnType::Blob => TypeAffinity::Blob,
+        ColumnType::Float | ColumnType::Double => TypeAffinity::Real,
+        ColumnType::Custom(name) => declared_affinity(&name.to_string()),
+        _ => TypeAffinity::Numeric,
+    }
+}
+
+fn compare_table(
+    expected: &ExpectedTable,
+    found: &TableDef,
+    unique_columns: &[String],
+) -> Vec<SchemaDrift> {
+    let table = &expected.name;
+    let mut drifts = vec![];
+    for column in &expected.columns {
+        let Some(info) = found.columns.iter().find(|info| info.name == column.name) else {
+            drifts.push(SchemaDrift::MissingColumn {
+                table: table.clone(),
+                column: column.name.clone(),
+            });
+            continue;
+        };
+        let found_affinity = affinity(&info.r#type);
+        if found_affinity != column.affinity {
+            drifts.push(SchemaDrift::ColumnType {
+                table: table.clone(),
+                column: column.name.clone(),
+                expected: column.affinity,
+                found: found_affinity,
+            });
+        }
+        // SQLite中整数主键总是不可为空，无需检查
+        if info.not_null != column.not_null && !info.primary_key {
+            drifts.push(SchemaDrift::Nullability {
+                table: table.clone(),
+                column: column.name.clone(),
+                expected_not_null: column.not_null,
+            });
+        }
+        if column.unique && !unique_columns.contains(&column.name) {
+            drifts.push(SchemaDrift::MissingUnique {
+                table: table.clone(),
+                column: column.name.clone(),
+            });
+        }
+    }
+    for info in &found.columns {
+        if !expected
+            .columns
+            .iter()
+            .any(|column| column.name == info.name)
+        {
+            drifts.push(SchemaDrift::ExtraColumn {
+                table: table.clone(),
+                column: info.name.clone(),
+            });
+        }
+    }
+    let mut found_primary_key = found
+        .columns
+        .iter()
+        .filter(|info| info.primary_key)
+        .map(|info| info.name.clone())
+        .collect::<Vec<_>>();
+    let mut expected_primary_key = expected.primary_key.clone();
+    found_primary_key.sort();
+    expected_primary_key.sort();
+    if found_primary_key != expected_primary_key {
+        drifts.push(SchemaDrift::PrimaryKey {
+            table: table.clone(),
+            expected: expected_primary_key,
+            found: found_primary_key,
+        });
+    }
+    drifts
+}
+
+impl Chembank {
+    /// 读取数据库文件的实际结构，与`entities`中的定义逐表逐列比较
+    pub async fn schema_report(&self) -> Result<SchemaReport> {
+        let discovery = SchemaDiscovery::new(self.db.get_sqlite_connection_pool().clone());
+        let schema = discovery
+            .discover()
+            .await
+            .map_err(|e| ChembankError::Database {
+                message: e.to_string(),
+            })?;
+        let expected = expected_tables();
+        let mut drifts = vec![];
+        for expected in &expected {
+            let Some(found) = schema
+                .tables
+                .iter()
+                .find(|table| table.name == expected.name)
+            else {
+                drifts.push(SchemaDrift::MissingTable {
+                    table: expected.name.clone(),
+                });
+                continue;
+            };
+            let unique_columns = found
+                .constraints
+                .iter()
+                .chain(schema.indexes.iter())
+                .filter(|index| index.table_name == expected.name && index.unique)
+                .filter(|index| index.columns.len() == 1)
+                .map(|index| index.columns[0].clone())
+                .collect::<Vec<_>>();
+            drifts.append(&mut compare_table(expected, found, &unique_columns));
+        }
+        let unexpected_tables = schema
+            .tables
+            .iter()
+            .map(|table| table.name.clone())
+            .filter(|name| !name.starts_with("sqlite_") && name != "seaql_migrations")
+            // 全文索引及其影子表
+            .filter(|name| !name.starts_with("structures_fts"))
+            .filter(|name| !expected.iter().any(|table| &table.name == name))
+            .collect::<Vec<_>>();
+        let foreign = !unexpected_tables.is_empty()
+            && drifts.contains(&SchemaDrift::MissingTable {
+                table: structure::Entity.table_name().to_string(),
+            });
+        let pending_migrations = match foreign {
+            true => vec![],
+            false => self.migration_state().await?.1,
+        };
+        Ok(SchemaReport {
+            unexpected_tables,
+            drifts,
+            foreign,
+            pending_migrations,
+        })
+    }
+
+    /// 写入操作前调用，拒绝向显然不是化合物库的数据库写入
+    pub(crate) fn ensure_writable(&self) -> Result<()> {
+        if self.foreign {
+            Err(ChembankError::NotChembankDatabase)?;
+        }
+        Ok(())
+    }
+}
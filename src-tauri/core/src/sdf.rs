@@ -0,0 +1,573 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ConnectionTrait, EntityTrait, IdenStatic, QueryOrder,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use entities::{quantity::Quantity, *};
+
+use crate::{
+    error::Result,
+    molecule::{parse_molblock, parse_smiles, write_molblock},
+    progress::{Job, Phase},
+    transfer::{at_record, import_property, import_structure, ImportOptions, Target},
+    Chembank, ChembankError,
+};
+
+/// SD标签对应的字段
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub enum SdfField {
+    Id,
+    Name,
+    Formula,
+    Smiles,
+    Charge,
+    /// 属性的文本列
+    Property(Quantity),
+    References,
+    Remarks,
+}
+
+impl SdfField {
+    fn all() -> Vec<SdfField> {
+        let mut fields = vec![
+            SdfField::Id,
+            SdfField::Name,
+            SdfField::Formula,
+            SdfField::Smiles,
+            SdfField::Charge,
+        ];
+        fields.extend(Quantity::ALL.map(SdfField::Property));
+        fields.extend([SdfField::References, SdfField::Remarks]);
+        fields
+    }
+
+    /// 按默认标签查找字段，不区分大小写
+    pub fn from_tag(tag: &str) -> Option<SdfField> {
+        SdfField::all()
+            .into_iter()
+            .find(|field| field.default_tag().eq_ignore_ascii_case(tag))
+    }
+
+    /// 默认标签，与CSV表格的列名相同
+    fn default_tag(self) -> String {
+        match self {
+            SdfField::Id => "id".to_string(),
+            SdfField::Name => "name".to_string(),
+            SdfField::Formula => "formula".to_string(),
+            SdfField::Smiles => "smiles".to_string(),
+            SdfField::Charge => "charge".to_string(),
+            SdfField::Property(quantity) => quantity.text_column().as_str().to_string(),
+            SdfField::References => "references".to_string(),
+            SdfField::Remarks => "remarks".to_string(),
+        }
+    }
+}
+
+/// SD文件的导入导出选项
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default, Type)]
+pub struct SdfOptions {
+    /// SD标签到字段的映射。未列出的标签按字段的默认标签（即CSV表格的列名，如`name`、`density`）识别，
+    /// 不区分大小写；导出时使用映射中字段对应的标签，没有时使用默认标签
+    #[serde(default)]
+    pub fields: HashMap<String, SdfField>,
+}
+
+impl SdfOptions {
+    fn field(&self, tag: &str) -> Option<SdfField> {
+        self.fields
+            .get(tag)
+            .copied()
+            .or_else(|| SdfField::from_tag(tag))
+    }
+
+    fn tag(&self, field: SdfField) -> String {
+        self.fields
+            .iter()
+            .filter(|(_, mapped)| **mapped == field)
+            .map(|(tag, _)| tag.clone())
+            .min()
+            .unwrap_or_else(|| field.default_tag())
+    }
+}
+
+/// SD文件中的一条记录
+struct SdfRecord<'a> {
+    /// 标题行的行号，从1开始
+    line: u32,
+    /// MOL块的各行，从标题行到`M  END`
+    molblock: Vec<&'a str>,
+    /// SD标签和对应的值，多行的值以换行符连接
+    data: Vec<(String, String)>,
+}
+
+/// 按`$$$$`拆分SD文件，末尾的空行不构成记录
+fn read_sdf_records<'a>(path: &Path, text: &'a str) -> Vec<Result<SdfRecord<'a>>> {
+    let mut records = vec![];
+    let mut lines = (1..).zip(text.lines()).peekable();
+    while let Some(&(line, _)) = lines.peek() {
+        let block = lines
+            .by_ref()
+            .take_while(|(_, content)| content.trim_end() != "$$$$")
+            .map(|(_, content)| content)
+            .collect::<Vec<_>>();
+        if block.iter().all(|content| content.trim().is_empty()) {
+            continue;
+        }
+        let Some(end) = block
+            .iter()
+            .position(|content| content.starts_with("M  END"))
+        else {
+            records.push(Err(ChembankError::SdfFormat {
+                file: path.to_string_lossy().to_string(),
+                line: Some(line),
+                message: "记录中没有`M  END`行".to_string(),
+            }));
+            continue;
+        };
+        let mut data = vec![];
+        let mut current: Option<(String, Vec<&str>)> = None;
+        for content in &block[end + 1..] {
+            if content.starts_with('>') {
+                data.extend(current.take());
+                // 没有`<标签>`的数据项无法对应到字段，直接忽略
+                current = content
+                    .split_once('<')
+                    .and_then(|(_, rest)| rest.split_once('>'))
+                    .map(|(tag, _)| (tag.to_string(), vec![]));
+            } else if content.trim().is_empty() {
+                data.extend(current.take());
+            } else if let Some((_, values)) = &mut current {
+                values.push(content);
+            }
+        }
+        data.extend(current);
+        records.push(Ok(SdfRecord {
+            line,
+            molblock: block[..=end].to_vec(),
+            data: data
+                .into_iter()
+                .map(|(tag, values)| (tag, values.join("\n").trim().to_string()))
+                .filter(|(_, value)| !value.is_empty())
+                .collect(),
+        }));
+    }
+    records
+}
+
+/// 由一条SD记录得到的结构、属性和MOL块
+struct SdfEntry {
+    line: u32,
+    id: Option<u32>,
+    structure: structure::Model,
+    property: Option<property::Model>,
+    molblock: Option<String>,
+}
+
+/// 按标签映射转换SD记录。没有名称标签时使用标题行作为名称，没有分子式和电荷标签时由结构式计算
+fn sdf_entry(path: &Path, record: SdfRecord, options: &SdfOptions) -> Result<SdfEntry> {
+    let error = |message: String| ChembankError::SdfFormat {
+        file: path.to_string_lossy().to_string(),
+        line: Some(record.line),
+        message,
+    };
+    let molecule = parse_molblock(&record.molblock).map_err(error)?;
+    let (mut id, mut name, mut formula, mut smiles, mut charge) = (None, None, None, None, None);
+    let mut property = property::Model::default();
+    for (tag, value) in record.data {
+        let Some(field) = options.field(&tag) else {
+            continue;
+        };
+        match field {
+            SdfField::Id => {
+                id = Some(
+                    value
+                        .parse()
+                        .map_err(|_| error(format!("标签{}中的ID“{}”不是正整数", tag, value)))?,
+                )
+            }
+            SdfField::Name => name = Some(value),
+            SdfField::Formula => formula = Some(value),
+            SdfField::Smiles => smiles = Some(value),
+            SdfField::Charge => {
+                charge = Some(
+                    value
+                        .parse()
+                        .map_err(|_| error(format!("标签{}中的电荷“{}”不是整数", tag, value)))?,
+                )
+            }
+            SdfField::Property(quantity) => *property.text_mut(quantity) = Some(value),
+            SdfField::References => property.references = Some(value),
+            SdfField::Remarks => property.remarks = Some(value),
+        }
+    }
+    let formula = match formula {
+        Some(formula) => formula,
+        None => molecule
+            .element_counts()
+            .map(|counts| formula::hill(&counts))
+            .ok_or_else(|| error("无法由结构式得到分子式，请在SD标签中提供分子式".to_string()))?,
+    };
+    let title = record.molblock[0].trim();
+    let empty = Quantity::ALL
+        .into_iter()
+        .all(|quantity| property.text(quantity).is_none())
+        && property.references.is_none()
+        && property.remarks.is_none();
+    Ok(SdfEntry {
+        line: record.line,
+        id,
+        structure: structure::Model {
+            id: id.unwrap_or_default(),
+            name: name.or_else(|| (!title.is_empty()).then(|| title.to_string())),
+            formula,
+            smiles,
+            charge: charge.unwrap_or_else(|| molecule.charge()),
+        },
+        property: (!empty).then_some(property),
+        molblock: (!molecule.atoms.is_empty()).then(|| record.molblock.join("\n")),
+    })
+}
+
+/// 写入导入的MOL块，跳过的结构不写入，合并的结构只在没有MOL块时写入
+async fn import_molfile<C: ConnectionTrait>(
+    db: &C,
+    structure_id: u32,
+    molblock: String,
+    target: Target,
+) -> Result<()> {
+    let exists = match target {
+        Target::Skipped => true,
+        Target::Merged => molfile::Entity::find_by_id(structure_id)
+            .one(db)
+            .await?
+            .is_some(),
+        _ => false,
+    };
+    if !exists {
+        molfile::ActiveModel {
+            structure_id: ActiveValue::set(structure_id),
+            molblock: ActiveValue::set(molblock),
+        }
+        .insert(db)
+        .await?;
+    }
+    Ok(())
+}
+
+/// 结构和属性中非空的字段，不带单位的属性数值补上单位，多行的值去掉空行以免提前结束SD数据项
+fn sdf_values(structure: &structure::Model, property: &property::Model) -> Vec<(SdfField, String)> {
+    let mut values = vec![
+        (SdfField::Id, Some(structure.id.to_string())),
+        (SdfField::Name, structure.name.clone()),
+        (SdfField::Formula, Some(structure.formula.clone())),
+        (SdfField::Smiles, structure.smiles.clone()),
+        (SdfField::Charge, Some(structure.charge.to_string())),
+    ];
+    for quantity in Quantity::ALL {
+        values.push((
+            SdfField::Property(quantity),
+            property.text(quantity).map(|text| quantity.with_unit(text)),
+        ));
+    }
+    values.push((SdfField::References, property.references.clone()));
+    values.push((SdfField::Remarks, property.remarks.clone()));
+    values
+        .into_iter()
+        .filter_map(|(field, value)| {
+            let value = value?
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            (!value.is_empty()).then_some((field, value))
+        })
+        .collect()
+}
+
+impl Chembank {
+    /// 从SD文件导入结构和属性，支持V2000和V3000格式的MOL块，结构式保存在数据库中以便原样导出。
+    /// 没有ID标签的记录依次使用数据库和文件中最大ID之后的ID。
+    /// 全部记录在同一个事务中写入，任何一条记录出错都会撤销整个导入，错误中给出记录标题行的行号
+    pub async fn import_sdf(
+        &self,
+        path: &Path,
+        sdf: &SdfOptions,
+        options: ImportOptions,
+        job: &Job,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let text = fs::read_to_string(path).map_err(|e| ChembankError::io(path, e))?;
+        let entries = read_sdf_records(path, &text)
+            .into_iter()
+            .map(|record| sdf_entry(path, record?, sdf))
+            .collect::<Result<Vec<_>>>()?;
+
+        let txn = self.begin_import(options).await?;
+        let mut next_id = structure::Entity::find()
+            .order_by_desc(structure::Column::Id)
+            .one(&txn)
+            .await?
+            .map(|model| model.id)
+            .into_iter()
+            .chain(entries.iter().filter_map(|entry| entry.id))
+            .max()
+            .unwrap_or_default();
+        let total = entries.len() as u32;
+        for (processed, entry) in (0..).zip(entries) {
+            job.step(Phase::Structures, processed, total)?;
+            let at = || at_record(path, Some(entry.line));
+            let id = entry.id.unwrap_or_else(|| {
+                next_id += 1;
+                next_id
+            });
+            let structure = structure::Model {
+                id,
+                ..entry.structure
+            };
+            let (id, target) = import_structure(&txn, structure, options)
+                .await
+                .map_err(at())?;
+            if let Some(property) = entry.property {
+                let property = property::Model {
+                    structure_id: id,
+                    ..property
+                };
+                import_property(&txn, property, target)
+                    .await
+                    .map_err(at())?;
+            }
+            if let Some(molblock) = entry.molblock {
+                import_molfile(&txn, id, molblock, target)
+                    .await
+                    .map_err(at())?;
+            }
+        }
+        job.step(Phase::Structures, total, total)?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// 将全部结构导出为SD文件，结构和属性写为SD标签。从SD文件导入的结构使用保存的结构式，
+    /// 其余结构由SMILES生成带有示意二维坐标的结构式，没有SMILES或无法解析时结构式中没有原子
+    pub async fn export_sdf(&self, path: &Path, sdf: &SdfOptions, job: &Job) -> Result<()> {
+        let db = &self.db;
+        let structures = structure::Entity::find()
+            .order_by_asc(structure::Column::Id)
+            .all(db)
+            .await?;
+        let mut properties = property::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|model| (model.structure_id, model))
+            .collect::<HashMap<_, _>>();
+        let mut molfiles = molfile::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|model| (model.structure_id, model.molblock))
+            .collect::<HashMap<_, _>>();
+        let mut file = File::create(path)
+            .map(BufWriter::new)
+            .map_err(|e| ChembankError::io(path, e))?;
+        let total = structures.len() as u32;
+        for (processed, structure) in (0..).zip(structures) {
+            job.step(Phase::Structures, processed, total)?;
+            let title = structure.name.clone().unwrap_or_default();
+            let molblock = match molfiles.remove(&structure.id) {
+                // 标题行改为当前的名称
+                Some(molblock) => match molblock.split_once('\n') {
+                    Some((_, rest)) => format!("{}\n{}", title, rest),
+                    None => molblock,
+                },
+                None => {
+                    let mut molecule = structure
+                        .smiles
+                        .as_deref()
+                        .and_then(|smiles| parse_smiles(smiles).ok())
+                        .unwrap_or_default();
+                    molecule.layout();
+                    write_molblock(&molecule, &title)
+                }
+            };
+            let property = properties.remove(&structure.id).unwrap_or_default();
+            let mut text = molblock + "\n";
+            for (field, value) in sdf_values(&structure, &property) {
+                text.push_str(&format!("> <{}>\n{}\n\n", sdf.tag(field), value));
+            }
+            text.push_str("$$$$\n");
+            file.write_all(text.as_bytes())
+                .map_err(|e| ChembankError::io(path, e))?;
+        }
+        job.step(Phase::Structures, total, total)?;
+        file.flush().map_err(|e| ChembankError::io(path, e))?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_sdf_import_export() {
+    let folder = std::env::temp_dir().join(format!("chembank-sdf-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let source_path = folder.join("source.sdf");
+    let source = "\
+硝基甲烷
+  test
+
+  4  3  0  0  0  0  0  0  0  0999 V2000
+    0.0000    0.0000    0.0000 C   0  0  0  0  0  0  0  0  0  0  0  0
+    1.5000    0.0000    0.0000 N   0  3  0  0  0  0  0  0  0  0  0  0
+    2.2000    1.2000    0.0000 O   0  0  0  0  0  0  0  0  0  0  0  0
+    2.2000   -1.2000    0.0000 O   0  5  0  0  0  0  0  0  0  0  0  0
+  1  2  1  0
+  2  3  2  0
+  2  4  1  0
+M  END
+> <SMILES>
+C[N+](=O)[O-]
+
+> <Density (g/cm3)>
+1.14
+
+$$$$
+
+  test
+
+  0  0  0     0  0            999 V3000
+M  V30 BEGIN CTAB
+M  V30 COUNTS 1 0 0 0 0
+M  V30 BEGIN ATOM
+M  V30 1 Na 0 0 0 0 CHG=1
+M  V30 END ATOM
+M  V30 END CTAB
+M  END
+> <name>
+钠离子
+
+$$$$
+";
+    fs::write(&source_path, source).unwrap();
+    let sdf = SdfOptions {
+        fields: HashMap::from([(
+            "Density (g/cm3)".to_string(),
+            SdfField::Property(Quantity::Density),
+        )]),
+    };
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    db.create_structure(Some("水".to_string()), "H2O".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.import_sdf(
+        &source_path,
+        &sdf,
+        ImportOptions::default(),
+        &Job::default(),
+    )
+    .await
+    .unwrap();
+    let structures = structure::Entity::find()
+        .order_by_asc(structure::Column::Id)
+        .all(db.connection())
+        .await
+        .unwrap();
+    let summary = |models: &[structure::Model]| {
+        models
+            .iter()
+            .map(|model| {
+                let name = model.name.clone().unwrap_or_default();
+                (model.id, name, model.formula.clone(), model.charge)
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        summary(&structures),
+        vec![
+            (1, "水".to_string(), "H2O".to_string(), 0),
+            (2, "硝基甲烷".to_string(), "CH3NO2".to_string(), 0),
+            (3, "钠离子".to_string(), "Na".to_string(), 1)
+        ]
+    );
+    let property = property::Entity::find_by_id(2u32)
+        .one(db.connection())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(property.density.as_deref(), Some("1.14"));
+    assert_eq!(property.density_value, Some(1.14));
+
+    // 导出后重新导入得到相同的结构，导入的结构式原样导出
+    let export_path = folder.join("export.sdf");
+    db.export_sdf(&export_path, &sdf, &Job::default())
+        .await
+        .unwrap();
+    let exported = fs::read_to_string(&export_path).unwrap();
+    assert!(exported.contains("    2.2000   -1.2000    0.0000 O   0  5"));
+    assert!(exported.contains("> <Density (g/cm3)>\n1.14 g·cm-3\n\n"));
+    assert!(exported.contains("> <smiles>\nC[N+](=O)[O-]\n\n"));
+    let mut copy = Chembank::connect("sqlite::memory:").await.unwrap();
+    copy.migrate().await.unwrap();
+    copy.import_sdf(
+        &export_path,
+        &sdf,
+        ImportOptions::default(),
+        &Job::default(),
+    )
+    .await
+    .unwrap();
+    let copied = structure::Entity::find()
+        .order_by_asc(structure::Column::Id)
+        .all(copy.connection())
+        .await
+        .unwrap();
+    assert_eq!(summary(&copied), summary(&structures));
+    assert_eq!(copied[1].smiles.as_deref(), Some("C[N+](=O)[O-]"));
+
+    // 重复的结构撤销整个导入，错误中给出记录的行号
+    let result = db
+        .import_sdf(
+            &source_path,
+            &sdf,
+            ImportOptions::default(),
+            &Job::default(),
+        )
+        .await;
+    assert_eq!(
+        result,
+        Err(ChembankError::ImportRecord {
+            file: source_path.to_string_lossy().to_string(),
+            line: Some(1),
+            cause: Box::new(ChembankError::DuplicateSmiles {
+                smiles: "C[N+](=O)[O-]".to_string()
+            })
+        })
+    );
+    assert_eq!(db.structure_count().await.unwrap(), 3);
+    fs::write(
+        &source_path,
+        "标题\n\n\n  1  0  0  0  0  0  0  0  0  0999 V2000\n$$$$\n",
+    )
+    .unwrap();
+    let result = db
+        .import_sdf(
+            &source_path,
+            &sdf,
+            ImportOptions::default(),
+            &Job::default(),
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(ChembankError::SdfFormat { line: Some(1), .. })
+    ));
+    fs::remove_dir_all(&folder).unwrap();
+}
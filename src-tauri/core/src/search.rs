@@ -0,0 +1,961 @@
+use std::str::FromStr;
+
+use sea_orm::{
+    prelude::Expr,
+    sea_query::{Alias, JoinType, Order, SimpleExpr},
+    ColumnTrait, Condition, ConnectionTrait, EntityTrait, FromQueryResult, PaginatorTrait,
+    QueryFilter, QueryOrder, QueryResult, QuerySelect, QueryTrait, Select, Value,
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use entities::{quantity::Quantity, *};
+
+use crate::{error::Result, Chembank, ChembankError, Query};
+
+/// 属性数值的检索范围，上下限均包含在内，省略表示不限。
+/// 比较的是换算为`Quantity::unit`后的数值，没有数值的结构不会被检索到
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+pub struct PropertyRange {
+    pub quantity: Quantity,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// 从`density:1.80..1.95`、`decomp_temp:200..`、`det_velocity:..9000`形式的文本解析
+impl FromStr for PropertyRange {
+    type Err = ChembankError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |message: String| ChembankError::InvalidSearch {
+            input: s.to_string(),
+            message,
+        };
+        let (name, range) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("应当写作“属性:下限..上限”".to_string()))?;
+        let quantity = Quantity::from_name(name.trim())
+            .ok_or_else(|| invalid(format!("未知的属性{}", name.trim())))?;
+        let (min, max) = range
+            .split_once("..")
+            .ok_or_else(|| invalid("范围应当写作“下限..上限”".to_string()))?;
+        let bound = |text: &str| -> Result<Option<f64>> {
+            let text = text.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            text.parse()
+                .map(Some)
+                .map_err(|_| invalid(format!("{}不是数值", text)))
+        };
+        Ok(Self {
+            quantity,
+            min: bound(min)?,
+            max: bound(max)?,
+        })
+    }
+}
+
+/// 检索结果摘要中的一段文本，`matched`表示该段与关键词匹配，需要突出显示
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+pub struct SnippetPart {
+    pub text: String,
+    pub matched: bool,
+}
+
+/// 检索到的结构，使用全文索引检索时附带匹配内容的摘要
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct SearchHit {
+    pub structure: structure::Model,
+    pub snippet: Vec<SnippetPart>,
+}
+
+#[derive(FromQueryResult)]
+struct HitRow {
+    id: u32,
+    name: Option<String>,
+    formula: String,
+    smiles: Option<String>,
+    charge: i8,
+    snippet: Option<String>,
+}
+
+impl From<HitRow> for SearchHit {
+    fn from(row: HitRow) -> Self {
+        Self {
+            structure: structure::Model {
+                id: row.id,
+                name: row.name,
+                formula: row.formula,
+                smiles: row.smiles,
+                charge: row.charge,
+            },
+            snippet: row
+                .snippet
+                .as_deref()
+                .map(parse_snippet)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// 全文索引使用trigram分词器，只能匹配不少于三个字符的片段，更短的关键词退回逐行匹配
+const MIN_INDEXED_KEYWORD: usize = 3;
+
+/// 摘要中匹配部分的起止标记
+const MATCH_START: char = '\u{1}';
+const MATCH_END: char = '\u{2}';
+
+fn parse_snippet(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    for c in snippet.chars() {
+        if c == MATCH_START || c == MATCH_END {
+            if !text.is_empty() {
+                parts.push(SnippetPart {
+                    text: std::mem::take(&mut text),
+                    matched: c == MATCH_END,
+                });
+            }
+        } else {
+            text.push(c);
+        }
+    }
+    if !text.is_empty() {
+        parts.push(SnippetPart {
+            text,
+            matched: false,
+        });
+    }
+    parts
+}
+
+/// 排序依据。`Relevance`为全文检索的相关度，只在关键词使用全文索引时有效，否则忽略
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+pub enum SortField {
+    Relevance,
+    Id,
+    Name,
+    Formula,
+    Smiles,
+    Charge,
+    Property(Quantity),
+}
+
+/// 排序键，`descending`为真时倒序。没有值的结构无论正序倒序都排在最后
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Type)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+/// 从`name`、`charge:desc`、`density:asc`形式的文本解析，属性使用导入导出格式中的列名
+impl FromStr for SortKey {
+    type Err = ChembankError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, direction) = s.split_once(':').unwrap_or((s, "asc"));
+        let field = match name.trim() {
+            "relevance" => SortField::Relevance,
+            "id" => SortField::Id,
+            "name" => SortField::Name,
+            "formula" => SortField::Formula,
+            "smiles" => SortField::Smiles,
+            "charge" => SortField::Charge,
+            name => SortField::Property(Quantity::from_name(name).ok_or_else(|| {
+                ChembankError::InvalidSearch {
+                    input: s.to_string(),
+                    message: format!("不能按{}排序", name),
+                }
+            })?),
+        };
+        let descending = match direction.trim() {
+            "asc" => false,
+            "desc" => true,
+            _ => Err(ChembankError::InvalidSearch {
+                input: s.to_string(),
+                message: "排序方向应为asc或desc".to_string(),
+            })?,
+        };
+        Ok(Self { field, descending })
+    }
+}
+
+/// 排序值在SQLite中的类型，用于读取结果和还原分页位置
+#[derive(Clone, Copy)]
+enum KeyType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl SortField {
+    fn expr(self) -> SimpleExpr {
+        match self {
+            SortField::Relevance => Expr::cust("structures_fts.rank"),
+            SortField::Id => Expr::col((structure::Entity, structure::Column::Id)).into(),
+            SortField::Name => Expr::col((structure::Entity, structure::Column::Name)).into(),
+            SortField::Formula => Expr::col((structure::Entity, structure::Column::Formula)).into(),
+            SortField::Smiles => Expr::col((structure::Entity, structure::Column::Smiles)).into(),
+            SortField::Charge => Expr::col((structure::Entity, structure::Column::Charge)).into(),
+            SortField::Property(quantity) => {
+                Expr::col((property::Entity, quantity.value_column())).into()
+            }
+        }
+    }
+
+    fn key_type(self) -> KeyType {
+        match self {
+            SortField::Id | SortField::Charge => KeyType::Integer,
+            SortField::Relevance | SortField::Property(_) => KeyType::Real,
+            SortField::Name | SortField::Formula | SortField::Smiles => KeyType::Text,
+        }
+    }
+
+    fn nullable(self) -> bool {
+        matches!(
+            self,
+            SortField::Name | SortField::Smiles | SortField::Property(_)
+        )
+    }
+}
+
+/// 一页检索结果。`total`为满足条件的结构总数，`next_cursor`用于获取下一页，为空表示没有更多结果
+#[derive(Serialize, Deserialize, Debug, Clone, Type)]
+pub struct SearchPage {
+    pub hits: Vec<SearchHit>,
+    pub total: u32,
+    pub next_cursor: Option<String>,
+}
+
+/// 检索条件，含义与`Chembank::search_structure`的同名参数相同，省略的条件不限
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+#[serde(default)]
+pub struct SearchFilter {
+    pub keyword: Option<String>,
+    pub max_charge: i8,
+    pub min_charge: i8,
+    pub ranges: Vec<PropertyRange>,
+    pub query: Option<String>,
+}
+
+impl Default for SearchFilter {
+    fn default() -> Self {
+        Self {
+            keyword: None,
+            max_charge: i8::MAX,
+            min_charge: i8::MIN,
+            ranges: vec![],
+            query: None,
+        }
+    }
+}
+
+/// 满足检索条件的结构查询，第二项表示关键词是否经由全文索引匹配
+fn filtered(filter: SearchFilter) -> Result<(Select<structure::Entity>, bool)> {
+    let query = filter
+        .query
+        .filter(|query| !query.trim().is_empty())
+        .map(|query| query.parse::<Query>())
+        .transpose()?;
+    let mut models = structure::Entity::find().left_join(property::Entity);
+    let indexed = match filter.keyword {
+        Some(keyword) if keyword.chars().count() >= MIN_INDEXED_KEYWORD => {
+            // 作为FTS5短语检索，避免关键词中的符号被当作检索语法
+            let phrase = format!("\"{}\"", keyword.replace('"', "\"\""));
+            let index = Alias::new("structures_fts");
+            QueryTrait::query(&mut models)
+                .join(
+                    JoinType::InnerJoin,
+                    index.clone(),
+                    Expr::col((index, Alias::new("rowid")))
+                        .equals((structure::Entity, structure::Column::Id)),
+                )
+                .and_where(Expr::cust_with_values("structures_fts MATCH ?", [phrase]));
+            true
+        }
+        keyword => {
+            if let Some(keyword) = keyword {
+                let keyword = format!("%{}%", keyword);
+                models = models.filter(
+                    Expr::col((structure::Entity, structure::Column::Name))
+                        .like(&keyword)
+                        .or(Expr::col((structure::Entity, structure::Column::Formula))
+                            .like(&keyword))
+                        .or(Expr::col((structure::Entity, structure::Column::Smiles))
+                            .like(&keyword))
+                        .or(Expr::col((property::Entity, property::Column::Remarks)).like(&keyword))
+                        .or(Expr::col((property::Entity, property::Column::References))
+                            .like(&keyword)),
+                );
+            }
+            false
+        }
+    };
+    for range in filter.ranges {
+        let value = Expr::col((property::Entity, range.quantity.value_column()));
+        if let Some(min) = range.min {
+            models = models.filter(value.clone().gte(min));
+        }
+        if let Some(max) = range.max {
+            models = models.filter(value.lte(max));
+        }
+    }
+    if let Some(query) = query {
+        models = models.filter(query.condition());
+    }
+    let models = models
+        .filter(structure::Column::Charge.gte(filter.min_charge))
+        .filter(structure::Column::Charge.lte(filter.max_charge));
+    Ok((models, indexed))
+}
+
+/// 分页位置记录上一页最后一个结构的各排序值，以JSON数组的十六进制形式传递，调用方不应解读其内容
+fn encode_cursor(values: &[serde_json::Value]) -> String {
+    serde_json::to_string(values)
+        .expect("排序值应当总能序列化为JSON")
+        .bytes()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 还原分页位置中的各排序值，`None`表示该结构没有这一排序值
+fn decode_cursor(cursor: &str, types: &[KeyType]) -> Result<Vec<Option<Value>>> {
+    let invalid = || ChembankError::InvalidSearch {
+        input: cursor.to_string(),
+        message: "分页位置无效，可能已更换了排序方式".to_string(),
+    };
+    let bytes = (0..cursor.len())
+        .step_by(2)
+        .map(|i| {
+            cursor
+                .get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)?;
+    let values: Vec<serde_json::Value> = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+    if values.len() != types.len() {
+        return Err(invalid());
+    }
+    values
+        .into_iter()
+        .zip(types)
+        .map(|(value, key_type)| match (key_type, value) {
+            (_, serde_json::Value::Null) => Some(None),
+            (KeyType::Integer, value) => value.as_i64().map(|value| Some(value.into())),
+            (KeyType::Real, value) => value.as_f64().map(|value| Some(value.into())),
+            (KeyType::Text, value) => value.as_str().map(|value| Some(value.into())),
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid)
+}
+
+fn read_key(row: &QueryResult, index: usize, key_type: KeyType) -> Result<serde_json::Value> {
+    let column = format!("sort_{}", index);
+    Ok(match key_type {
+        KeyType::Integer => row.try_get::<Option<i64>>("", &column)?.into(),
+        KeyType::Real => row.try_get::<Option<f64>>("", &column)?.into(),
+        KeyType::Text => row.try_get::<Option<String>>("", &column)?.into(),
+    })
+}
+
+/// 排在分页位置之后的条件：按排序键逐个比较，前面的键相等时比较后一个键。
+/// 可为空的键先比较是否为空（非空在前），再比较值
+fn after_cursor(keys: &[(SimpleExpr, bool, bool)], values: Vec<Option<Value>>) -> Condition {
+    let mut after = Condition::any();
+    let mut equal = Condition::all();
+    for ((expr, descending, nullable), value) in keys.iter().cloned().zip(values) {
+        match value {
+            Some(value) => {
+                let mut step = Condition::any();
+                if nullable {
+                    step = step.add(Expr::expr(expr.clone()).is_null());
+                }
+                step = step.add(match descending {
+                    true => Expr::expr(expr.clone()).lt(value.clone()),
+                    false => Expr::expr(expr.clone()).gt(value.clone()),
+                });
+                after = after.add(equal.clone().add(step));
+                equal = equal.add(Expr::expr(expr).eq(value));
+            }
+            // 没有值的结构排在最后，其后只能是同样没有值的结构
+            None => equal = equal.add(Expr::expr(expr).is_null()),
+        }
+    }
+    after
+}
+
+impl Chembank {
+    /// 按关键词、电荷范围、属性数值范围和检索表达式检索结构，每次返回不超过`limit`个结构。
+    /// 关键词在名称、分子式、SMILES、备注和参考文献中检索，使用全文索引时附带摘要；
+    /// 检索表达式的写法见`Query`。
+    ///
+    /// 结果依次按`sort`中的各键排序，没有给出时按相关度（使用全文索引时）和ID倒序排列，
+    /// 最后总以ID倒序区分排序值相同的结构。`cursor`为上一页返回的`next_cursor`，
+    /// 翻页时以上一页最后一个结构为界，其间新增或删除结构不会使结果重复或遗漏
+    #[allow(clippy::too_many_arguments)]
+    pub async fn search_structure(
+        &self,
+        keyword: Option<String>,
+        max_charge: i8,
+        min_charge: i8,
+        ranges: Vec<PropertyRange>,
+        query: Option<String>,
+        sort: Vec<SortKey>,
+        cursor: Option<String>,
+        limit: u32,
+    ) -> Result<SearchPage> {
+        let (mut models, indexed) = filtered(SearchFilter {
+            keyword,
+            max_charge,
+            min_charge,
+            ranges,
+            query,
+        })?;
+        let mut sort = sort;
+        match indexed {
+            true => {
+                QueryTrait::query(&mut models).expr_as(
+                    Expr::cust(format!(
+                        "snippet(structures_fts, -1, char({}), char({}), '…', 16)",
+                        MATCH_START as u32, MATCH_END as u32
+                    )),
+                    Alias::new("snippet"),
+                );
+                if sort.is_empty() {
+                    sort.push(SortKey {
+                        field: SortField::Relevance,
+                        descending: false,
+                    });
+                }
+            }
+            false => {
+                QueryTrait::query(&mut models).expr_as(Expr::cust("NULL"), Alias::new("snippet"));
+                sort.retain(|key| key.field != SortField::Relevance);
+            }
+        }
+        let total = models.clone().count(&self.db).await?;
+
+        sort.push(SortKey {
+            field: SortField::Id,
+            descending: true,
+        });
+        let keys = sort
+            .iter()
+            .map(|key| (key.field.expr(), key.descending, key.field.nullable()))
+            .collect::<Vec<_>>();
+        let types = sort
+            .iter()
+            .map(|key| key.field.key_type())
+            .collect::<Vec<_>>();
+        for (index, (expr, descending, nullable)) in keys.iter().enumerate() {
+            let select = QueryTrait::query(&mut models);
+            if *nullable {
+                select.order_by_expr(Expr::expr(expr.clone()).is_null(), Order::Asc);
+            }
+            select
+                .order_by_expr(
+                    expr.clone(),
+                    if *descending { Order::Desc } else { Order::Asc },
+                )
+                .expr_as(expr.clone(), Alias::new(format!("sort_{}", index)));
+        }
+        if let Some(cursor) = cursor {
+            models = models.filter(after_cursor(&keys, decode_cursor(&cursor, &types)?));
+        }
+        // 多取一个结构以判断是否还有下一页
+        QueryTrait::query(&mut models).limit(limit as u64 + 1);
+        let builder = self.db.get_database_backend();
+        let mut rows = self
+            .db
+            .query_all(builder.build(QueryTrait::query(&mut models)))
+            .await?;
+        let next_cursor = match rows.len() > limit as usize {
+            true => {
+                rows.truncate(limit as usize);
+                let values = match rows.last() {
+                    Some(row) => types
+                        .iter()
+                        .enumerate()
+                        .map(|(index, key_type)| read_key(row, index, *key_type))
+                        .collect::<Result<Vec<_>>>()?,
+                    None => vec![],
+                };
+                Some(encode_cursor(&values)).filter(|_| !values.is_empty())
+            }
+            false => None,
+        };
+        let hits = rows
+            .iter()
+            .map(|row| HitRow::from_query_result(row, "").map(SearchHit::from))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(SearchPage {
+            hits,
+            total: total as u32,
+            next_cursor,
+        })
+    }
+
+    /// 满足检索条件的全部结构ID，按ID升序
+    pub(crate) async fn filter_structure_ids(&self, filter: SearchFilter) -> Result<Vec<u32>> {
+        let (models, _) = filtered(filter)?;
+        Ok(models
+            .select_only()
+            .column(structure::Column::Id)
+            .order_by_asc(structure::Column::Id)
+            .into_tuple::<u32>()
+            .all(&self.db)
+            .await?)
+    }
+}
+
+#[tokio::test]
+async fn test_search_property_range() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    for (formula, density, decomp_temp) in [
+        ("RDX", "1.82 g/cm3", "205"),
+        ("HMX", "1.91", "280 ℃"),
+        ("TNT", "1.65", "295"),
+        ("CL-20", "2.04", "未测定"),
+    ] {
+        let id = db
+            .create_structure(None, formula.to_string(), None, 0)
+            .await
+            .unwrap();
+        db.set_property(property::Model {
+            structure_id: id,
+            density: Some(density.to_string()),
+            decomp_temp: Some(decomp_temp.to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let search = |ranges: Vec<PropertyRange>| {
+        let db = &db;
+        async move {
+            db.search_structure(None, 0, 0, ranges, None, vec![], None, 100)
+                .await
+                .unwrap()
+                .hits
+                .into_iter()
+                .map(|hit| hit.structure.formula)
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        search(vec![
+            "density:1.80..1.95".parse().unwrap(),
+            "decomp_temp:200..".parse().unwrap()
+        ])
+        .await,
+        vec!["HMX", "RDX"]
+    );
+    assert_eq!(
+        search(vec!["decomp_temp:..290".parse().unwrap()]).await,
+        vec!["HMX", "RDX"]
+    );
+    // 数值比较而不是字符串比较，“1.65”在字符串顺序中大于“1.1”但小于“10”
+    assert_eq!(
+        search(vec!["density:1.1..10".parse().unwrap()]).await,
+        vec!["CL-20", "TNT", "HMX", "RDX"]
+    );
+    assert!("density:1.8".parse::<PropertyRange>().is_err());
+    assert!("viscosity:1..2".parse::<PropertyRange>().is_err());
+    assert!("density:a..2".parse::<PropertyRange>().is_err());
+}
+
+#[tokio::test]
+async fn test_search_fulltext() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let nitro = db
+        .create_structure(
+            Some("硝基甲烷".to_string()),
+            "CH3NO2".to_string(),
+            Some("C[N+](=O)[O-]".to_string()),
+            0,
+        )
+        .await
+        .unwrap();
+    let tnt = db
+        .create_structure(Some("TNT".to_string()), "C7H5N3O6".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.set_property(property::Model {
+        structure_id: tnt,
+        remarks: Some("三硝基甲苯，常用的参比炸药".to_string()),
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let search = |keyword: &str| {
+        let db = &db;
+        let keyword = keyword.to_string();
+        async move {
+            db.search_structure(Some(keyword), 0, 0, vec![], None, vec![], None, 100)
+                .await
+                .unwrap()
+                .hits
+        }
+    };
+
+    let hits = search("硝基甲").await;
+    assert_eq!(
+        hits.iter().map(|hit| hit.structure.id).collect::<Vec<_>>(),
+        vec![nitro, tnt]
+    );
+    assert_eq!(
+        hits[0].snippet,
+        vec![
+            SnippetPart {
+                text: "硝基甲".to_string(),
+                matched: true
+            },
+            SnippetPart {
+                text: "烷".to_string(),
+                matched: false
+            }
+        ]
+    );
+    assert!(hits[1]
+        .snippet
+        .iter()
+        .any(|part| part.matched && part.text == "硝基甲"));
+    assert_eq!(search("[N+](=O)").await[0].structure.id, nitro);
+    assert_eq!(search("参比炸").await[0].structure.id, tnt);
+    assert_eq!(search("硝基").await.len(), 2);
+    // 按相关度翻页
+    let first = db
+        .search_structure(
+            Some("硝基甲".to_string()),
+            0,
+            0,
+            vec![],
+            None,
+            vec![],
+            None,
+            1,
+        )
+        .await
+        .unwrap();
+    let second = db
+        .search_structure(
+            Some("硝基甲".to_string()),
+            0,
+            0,
+            vec![],
+            None,
+            vec![],
+            first.next_cursor,
+            1,
+        )
+        .await
+        .unwrap();
+    assert_eq!((first.total, first.hits[0].structure.id), (2, nitro));
+    assert_eq!(second.hits[0].structure.id, tnt);
+    assert_eq!(second.next_cursor, None);
+
+    db.update_structure(
+        nitro,
+        Some("硝基乙烷".to_string()),
+        "C2H5NO2".to_string(),
+        None,
+        0,
+    )
+    .await
+    .unwrap();
+    assert_eq!(search("硝基甲").await.len(), 1);
+    db.remove_structure(tnt).await.unwrap();
+    assert!(search("参比炸").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_search_query() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    for (name, formula, charge, remarks, density) in [
+        ("硝酸铵", "NH4NO3", 0, None, Some("1.72")),
+        ("硝酸根", "NO3", -1, None, None),
+        ("硝酸肼", "N2H5NO3", 0, Some("数据待核实"), Some("1.64")),
+        ("高氯酸铵", "NH4ClO4", 0, Some("常用氧化剂"), Some("1.95")),
+    ] {
+        let id = db
+            .create_structure(Some(name.to_string()), formula.to_string(), None, charge)
+            .await
+            .unwrap();
+        db.set_property(property::Model {
+            structure_id: id,
+            remarks: remarks.map(str::to_string),
+            density: density.map(str::to_string),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let search = |query: &str| {
+        let db = &db;
+        let query = query.to_string();
+        async move {
+            db.search_structure(None, 10, -10, vec![], Some(query), vec![], None, 100)
+                .await
+                .map(|page| {
+                    page.hits
+                        .into_iter()
+                        .map(|hit| hit.structure.name.unwrap())
+                        .collect::<Vec<_>>()
+                })
+        }
+    };
+    assert_eq!(
+        search("name:硝酸 AND charge=0 AND NOT remarks:待核实")
+            .await
+            .unwrap(),
+        vec!["硝酸铵"]
+    );
+    assert_eq!(
+        search("(density>=1.9 OR charge<0) NOT formula=NO3")
+            .await
+            .unwrap(),
+        vec!["高氯酸铵"]
+    );
+    assert_eq!(
+        search("NOT density>1.7").await.unwrap(),
+        vec!["硝酸肼", "硝酸根"]
+    );
+    assert_eq!(
+        search("氧化剂 OR \"NH4NO3\"").await.unwrap(),
+        vec!["高氯酸铵", "硝酸铵"]
+    );
+    assert_eq!(
+        search("name:硝酸 AND").await,
+        Err(ChembankError::QuerySyntax {
+            query: "name:硝酸 AND".to_string(),
+            position: 11,
+            message: "此处应为检索条件".to_string()
+        })
+    );
+}
+
+#[tokio::test]
+async fn test_search_composition() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    for (name, formula, charge) in [
+        ("硝酸钠", "NaNO3", 0),
+        ("硝酸铵", "NH4NO3", 0),
+        ("TNT", "C7H5N3O6", 0),
+        ("硝基甲烷", "CH3NO2", 0),
+        ("RDX", "RDX", 0),
+        ("碳酸根", "CO3(2-)", -2),
+    ] {
+        db.create_structure(Some(name.to_string()), formula.to_string(), None, charge)
+            .await
+            .unwrap();
+    }
+    let search = |query: &str| {
+        let db = &db;
+        let query = query.to_string();
+        async move {
+            db.search_structure(None, 10, -10, vec![], Some(query), vec![], None, 100)
+                .await
+                .unwrap()
+                .hits
+                .into_iter()
+                .map(|hit| hit.structure.name.unwrap())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        search("element:N element:O element!=metal").await,
+        vec!["硝基甲烷", "TNT", "硝酸铵"]
+    );
+    assert_eq!(search("C<=6 C>=1").await, vec!["碳酸根", "硝基甲烷"]);
+    assert_eq!(search("element:metal").await, vec!["硝酸钠"]);
+    assert_eq!(search("H=0 N>0").await, vec!["硝酸钠"]);
+
+    let id = db
+        .search_structure(
+            Some("RDX".to_string()),
+            0,
+            0,
+            vec![],
+            None,
+            vec![],
+            None,
+            100,
+        )
+        .await
+        .unwrap()
+        .hits[0]
+        .structure
+        .id;
+    db.update_structure(id, Some("RDX".to_string()), "C3H6N6O6".to_string(), None, 0)
+        .await
+        .unwrap();
+    assert_eq!(search("C=3").await, vec!["RDX"]);
+    db.remove_structure(id).await.unwrap();
+    assert!(search("C=3").await.is_empty());
+}
+
+#[tokio::test]
+async fn test_search_sort_and_cursor() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    for (formula, charge, density) in [
+        ("A1", 0, Some("1.8")),
+        ("A2", 0, None),
+        ("A3", 1, Some("1.6")),
+        ("A4", 0, Some("1.8")),
+        ("A5", 1, None),
+        ("A6", 0, Some("2.0")),
+    ] {
+        let id = db
+            .create_structure(None, formula.to_string(), None, charge)
+            .await
+            .unwrap();
+        db.set_property(property::Model {
+            structure_id: id,
+            density: density.map(str::to_string),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    }
+    let sort = vec![
+        "charge".parse::<SortKey>().unwrap(),
+        "density:desc".parse::<SortKey>().unwrap(),
+    ];
+    let search = |cursor: Option<String>| {
+        let db = &db;
+        let sort = sort.clone();
+        async move {
+            db.search_structure(None, 10, -10, vec![], None, sort, cursor, 2)
+                .await
+                .unwrap()
+        }
+    };
+    let formulas = |page: &SearchPage| {
+        page.hits
+            .iter()
+            .map(|hit| hit.structure.formula.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let mut pages = vec![];
+    let mut cursor = None;
+    loop {
+        let page = search(cursor).await;
+        assert_eq!(page.total, if pages.is_empty() { 6 } else { 7 });
+        pages.push(formulas(&page));
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+        // 翻页过程中新增的结构排在已经取得的结果之前，不会使后续页面重复或遗漏
+        if pages.len() == 1 {
+            db.create_structure(None, "A0".to_string(), None, -1)
+                .await
+                .unwrap();
+        }
+    }
+    assert_eq!(
+        pages,
+        vec![vec!["A6", "A4"], vec!["A1", "A2"], vec!["A3", "A5"]]
+    );
+
+    let page = db
+        .search_structure(None, 10, -10, vec![], None, vec![], None, 3)
+        .await
+        .unwrap();
+    assert_eq!(page.total, 7);
+    assert_eq!(formulas(&page), vec!["A0", "A6", "A5"]);
+    assert!(matches!(
+        db.search_structure(
+            None,
+            10,
+            -10,
+            vec![],
+            None,
+            vec![],
+            Some("7b".to_string()),
+            3
+        )
+        .await,
+        Err(ChembankError::InvalidSearch { .. })
+    ));
+    assert!("viscosity:desc".parse::<SortKey>().is_err());
+    assert!("name:up".parse::<SortKey>().is_err());
+}
+
+#[tokio::test]
+async fn test_search_components() {
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let mut ids = vec![];
+    for (name, formula, charge) in [
+        ("钠离子", "Na", 1),
+        ("钾离子", "K", 1),
+        ("碳酸根", "CO3", -2),
+        ("硝酸根", "NO3", -1),
+        ("碳酸钠", "Na2CO3", 0),
+        ("硝酸钾", "KNO3", 0),
+        ("混合盐", "Na2CO3·KNO3", 0),
+    ] {
+        ids.push(
+            db.create_structure(Some(name.to_string()), formula.to_string(), None, charge)
+                .await
+                .unwrap(),
+        );
+    }
+    for (structure, component, count) in [
+        (4, 0, 2),
+        (4, 2, 1),
+        (5, 1, 1),
+        (5, 3, 1),
+        (6, 4, 1),
+        (6, 5, 1),
+    ] {
+        db.set_component(ids[structure], ids[component], count)
+            .await
+            .unwrap();
+    }
+    let search = |query: String| {
+        let db = &db;
+        async move {
+            db.search_structure(None, 10, -10, vec![], Some(query), vec![], None, 100)
+                .await
+                .unwrap()
+                .hits
+                .into_iter()
+                .map(|hit| hit.structure.name.unwrap())
+                .collect::<Vec<_>>()
+        }
+    };
+    assert_eq!(
+        search(format!("component:{}", ids[0])).await,
+        vec!["碳酸钠"]
+    );
+    assert_eq!(
+        search("component*:钠离子".to_string()).await,
+        vec!["混合盐", "碳酸钠"]
+    );
+    assert_eq!(
+        search("component:(charge=-2)".to_string()).await,
+        vec!["碳酸钠"]
+    );
+    assert_eq!(
+        search("component*:(charge=-2)".to_string()).await,
+        vec!["混合盐", "碳酸钠"]
+    );
+    assert_eq!(
+        search("component!=钠离子 component:(charge<0 element:N)".to_string()).await,
+        vec!["硝酸钾"]
+    );
+    assert_eq!(
+        search("component:(component:钾离子)".to_string()).await,
+        vec!["混合盐"]
+    );
+}
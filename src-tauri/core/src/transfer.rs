@@ -0,0 +1,1237 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use sea_orm::{
+    prelude::Expr,
+    sea_query::{Alias, SelectStatement},
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseTransaction, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, TransactionTrait,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use skip_bom::{BomType, SkipEncodingBom};
+use specta::Type;
+
+use entities::*;
+
+use crate::{
+    error::Result,
+    merged::{format_summary, parse_summary, Member, MergedRow, MERGED_FILE},
+    progress::{Job, Phase},
+    records::write_composition,
+    Chembank, ChembankError, SearchFilter, Table,
+};
+
+/// 按ID筛选时每条语句携带的ID数量上限，避免超出SQLite的参数个数限制
+const ID_CHUNK: usize = 500;
+
+fn write_bom<T: std::io::Write>(w: &mut T) -> std::io::Result<()> {
+    w.write_all(&[0xEF, 0xBB, 0xBF])
+}
+
+/// 打开带有UTF-8 BOM的CSV表格
+fn open_csv(path: &Path) -> Result<csv::Reader<SkipEncodingBom<'static, File>>> {
+    let file = File::open(path).map_err(|e| ChembankError::io(path, e))?;
+    Ok(csv::Reader::from_reader(SkipEncodingBom::new(
+        &[BomType::UTF8],
+        file,
+    )))
+}
+
+/// 创建CSV表格并写入UTF-8 BOM
+fn create_csv(path: &Path) -> Result<csv::Writer<File>> {
+    let mut file = File::create(path).map_err(|e| ChembankError::io(path, e))?;
+    write_bom(&mut file).map_err(|e| ChembankError::io(path, e))?;
+    Ok(csv::Writer::from_writer(file))
+}
+
+/// 逐行读取CSV表格，行号从1开始并包含表头行。无法解析的行作为错误保留在结果中，
+/// 只有文件无法读取时才整体返回错误
+pub(crate) fn read_csv_rows<T: DeserializeOwned>(path: &Path) -> Result<Vec<Result<(u32, T)>>> {
+    let mut reader = open_csv(path)?;
+    let headers = reader
+        .headers()
+        .cloned()
+        .map_err(|e| ChembankError::csv(path, None, e))?;
+    let mut record = csv::StringRecord::new();
+    let mut rows = vec![];
+    loop {
+        match reader.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => {
+                let line = record
+                    .position()
+                    .map(|pos| pos.line() as u32)
+                    .unwrap_or_default();
+                rows.push(
+                    record
+                        .deserialize(Some(&headers))
+                        .map(|model| (line, model))
+                        .map_err(|e| ChembankError::csv(path, Some(&headers), e)),
+                );
+            }
+            Err(e) if e.is_io_error() => return Err(ChembankError::csv(path, Some(&headers), e)),
+            Err(e) => rows.push(Err(ChembankError::csv(path, Some(&headers), e))),
+        }
+    }
+    Ok(rows)
+}
+
+/// 图片目录下的一个子目录，返回其对应的结构ID和其中图片文件的路径
+pub(crate) fn read_image_folder(structure_folder: &Path) -> Result<(u32, String, PathBuf)> {
+    let structure_id: u32 = structure_folder
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.parse().ok())
+        .ok_or_else(|| ChembankError::InvalidImageFolder {
+            path: structure_folder.to_string_lossy().to_string(),
+            message: "目录名称无法解析为结构ID".to_string(),
+        })?;
+    let filename = fs::read_dir(structure_folder)
+        .map_err(|e| ChembankError::io(structure_folder, e))?
+        .next()
+        .ok_or_else(|| ChembankError::InvalidImageFolder {
+            path: structure_folder.to_string_lossy().to_string(),
+            message: "发现了空的图片文件夹".to_string(),
+        })?
+        .map_err(|e| ChembankError::io(structure_folder, e))?
+        .file_name()
+        .into_string()
+        .map_err(|_| ChembankError::InvalidImageFolder {
+            path: structure_folder.to_string_lossy().to_string(),
+            message: "无法识别的图片文件名".to_string(),
+        })?;
+    let full_image_path = structure_folder.join(&filename);
+    Ok((structure_id, filename, full_image_path))
+}
+
+/// 为写入某条记录时的错误附加文件和行号
+pub(crate) fn at_record(
+    path: &Path,
+    line: Option<u32>,
+) -> impl FnOnce(ChembankError) -> ChembankError + '_ {
+    move |e| ChembankError::ImportRecord {
+        file: path.to_string_lossy().to_string(),
+        line,
+        cause: Box::new(e),
+    }
+}
+
+/// 组成关系中的组成部分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ComponentRef {
+    /// 导入文件中的结构ID，导入时随结构重新编号；不在文件中的ID按已有结构原样使用
+    File(u32),
+    /// 合并表中按名称找到的已有结构
+    Existing(u32),
+}
+
+/// 待导入的一条组成关系，`structure_id`为导入文件中的结构ID
+#[derive(Debug, Clone)]
+pub(crate) struct ComponentRow {
+    pub line: u32,
+    pub structure_id: u32,
+    pub component: ComponentRef,
+    pub count: u32,
+}
+
+/// 导入目录中各表的记录，整个文件无法读取时对应的结果为错误，无法解析的行作为错误保留
+pub(crate) struct ImportRows {
+    pub structure_path: PathBuf,
+    pub structures: Result<Vec<Result<(u32, structure::Model)>>>,
+    pub property_path: PathBuf,
+    pub properties: Result<Vec<Result<(u32, property::Model)>>>,
+    pub component_path: PathBuf,
+    pub components: Result<Vec<Result<ComponentRow>>>,
+}
+
+/// 读取导入目录中的三个CSV表格，目录中有合并表时改为读取合并表。
+/// 合并表的组成摘要中按名称引用的结构先在合并表中查找，再在数据库中查找
+pub(crate) async fn read_import_rows<C: ConnectionTrait>(
+    db: &C,
+    folder_path: &Path,
+) -> Result<ImportRows> {
+    let merged_path = folder_path.join(MERGED_FILE);
+    if !merged_path.exists() {
+        let component_path = folder_path.join("components.csv");
+        let components = read_csv_rows::<component::Model>(&component_path).map(|rows| {
+            rows.into_iter()
+                .map(|row| {
+                    row.map(|(line, model)| ComponentRow {
+                        line,
+                        structure_id: model.structure_id,
+                        component: ComponentRef::File(model.component_id),
+                        count: model.count,
+                    })
+                })
+                .collect()
+        });
+        let structure_path = folder_path.join("structures.csv");
+        let property_path = folder_path.join("properties.csv");
+        return Ok(ImportRows {
+            structures: read_csv_rows(&structure_path),
+            structure_path,
+            properties: read_csv_rows(&property_path),
+            property_path,
+            component_path,
+            components,
+        });
+    }
+    let mut rows = ImportRows {
+        structure_path: merged_path.clone(),
+        structures: Ok(vec![]),
+        property_path: merged_path.clone(),
+        properties: Ok(vec![]),
+        component_path: merged_path.clone(),
+        components: Ok(vec![]),
+    };
+    let merged = match read_csv_rows::<MergedRow>(&merged_path) {
+        Ok(merged) => merged,
+        Err(e) => {
+            rows.structures = Err(e);
+            return Ok(rows);
+        }
+    };
+    let (mut structures, mut properties, mut summaries) = (vec![], vec![], vec![]);
+    for row in merged {
+        let (line, row) = match row {
+            Ok(row) => row,
+            Err(e) => {
+                structures.push(Err(e));
+                continue;
+            }
+        };
+        let (structure, property, summary) = row.split();
+        if let Some(property) = property {
+            properties.push(Ok((line, property)));
+        }
+        if let Some(summary) = summary {
+            summaries.push((line, structure.id, summary));
+        }
+        structures.push(Ok((line, structure)));
+    }
+    let names = structures
+        .iter()
+        .flatten()
+        .filter_map(|(_, model)| Some((model.name.clone()?, model.id)))
+        .collect::<HashMap<_, _>>();
+    let mut components = vec![];
+    for (line, structure_id, summary) in summaries {
+        let error = |message: String| ChembankError::CsvFormat {
+            file: merged_path.to_string_lossy().to_string(),
+            line: Some(line),
+            column: Some("components".to_string()),
+            message,
+        };
+        let members = match parse_summary(&summary) {
+            Ok(members) => members,
+            Err(message) => {
+                components.push(Err(error(message)));
+                continue;
+            }
+        };
+        for (count, member) in members {
+            let component = match member {
+                Member::Id(id) => ComponentRef::File(id),
+                Member::Name(name) => match names.get(&name) {
+                    Some(id) => ComponentRef::File(*id),
+                    None => match structure::Entity::find()
+                        .filter(structure::Column::Name.eq(&name))
+                        .one(db)
+                        .await?
+                    {
+                        Some(model) => ComponentRef::Existing(model.id),
+                        None => {
+                            let message = format!("找不到名称为“{}”的结构", name);
+                            components.push(Err(error(message)));
+                            continue;
+                        }
+                    },
+                },
+            };
+            components.push(Ok(ComponentRow {
+                line,
+                structure_id,
+                component,
+                count,
+            }));
+        }
+    }
+    rows.structures = Ok(structures);
+    rows.properties = Ok(properties);
+    rows.components = Ok(components);
+    Ok(rows)
+}
+
+/// 导出选项
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Type)]
+pub struct ExportOptions {
+    /// 将结构表、属性表和组成关系合并为一个宽表`merged.csv`，组成关系写为摘要，例如`2×钠离子 + 1×碳酸根`
+    #[serde(default)]
+    pub merged: bool,
+    /// 只导出部分结构，省略时导出全部
+    #[serde(default)]
+    pub subset: Option<ExportSubset>,
+}
+
+/// 导出的部分结构。这些结构直接或间接含有的组成部分总是一并导出，使导出的数据可以单独导入
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+pub enum ExportSubset {
+    /// 指定ID的结构，ID不存在时报错
+    Ids(Vec<u32>),
+    /// 满足检索条件的结构
+    Filter(SearchFilter),
+}
+
+/// 导入的结构与已有结构相同时的处理方式
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+pub enum ImportMode {
+    /// 原样使用导入的ID，与已有结构冲突时撤销整个导入
+    #[default]
+    Fail,
+    /// 保留已有结构，忽略导入的结构及其属性、组成和图片
+    Skip,
+    /// 用导入的结构替换已有结构，其属性、组成和图片也一并替换
+    Overwrite,
+    /// 只用导入的内容补充已有结构中空白的信息、属性、组成和图片
+    Merge,
+    /// 全部作为新结构导入
+    Renumber,
+}
+
+/// 判断导入的结构与已有结构相同的依据
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+pub enum MatchBy {
+    #[default]
+    Id,
+    Name,
+    Smiles,
+}
+
+/// 导入选项。除`Fail`以外的方式中，没有对应已有结构的导入结构尽量沿用原ID，
+/// ID已被占用时重新编号，属性、组成和图片中引用的ID随之改变
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+pub struct ImportOptions {
+    #[serde(default)]
+    pub mode: ImportMode,
+    #[serde(default)]
+    pub match_by: MatchBy,
+    /// 导入前先删除全部已有数据。删除和导入在同一个事务中，导入失败时已有数据保持不变
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// 按依赖顺序删除全部结构及其属性、组成、结构式和图片
+async fn clear_tables<C: ConnectionTrait>(db: &C) -> Result<()> {
+    image::Entity::delete_many().exec(db).await?;
+    molfile::Entity::delete_many().exec(db).await?;
+    composition::Entity::delete_many().exec(db).await?;
+    component::Entity::delete_many().exec(db).await?;
+    property::Entity::delete_many().exec(db).await?;
+    structure::Entity::delete_many().exec(db).await?;
+    Ok(())
+}
+
+/// 导入的结构最终的写入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    Inserted,
+    Skipped,
+    Overwritten,
+    Merged,
+}
+
+/// 查找与导入结构相同的已有结构
+async fn find_existing<C: ConnectionTrait>(
+    db: &C,
+    model: &structure::Model,
+    options: ImportOptions,
+) -> Result<Option<structure::Model>> {
+    if matches!(options.mode, ImportMode::Fail | ImportMode::Renumber) {
+        return Ok(None);
+    }
+    let find = structure::Entity::find();
+    let existing = match (options.match_by, &model.name, &model.smiles) {
+        (MatchBy::Id, _, _) => structure::Entity::find_by_id(model.id).one(db).await?,
+        (MatchBy::Name, Some(name), _) => {
+            find.filter(structure::Column::Name.eq(name))
+                .one(db)
+                .await?
+        }
+        (MatchBy::Smiles, _, Some(smiles)) => {
+            find.filter(structure::Column::Smiles.eq(smiles))
+                .one(db)
+                .await?
+        }
+        _ => None,
+    };
+    Ok(existing)
+}
+
+/// 写入一个导入的结构，返回它在数据库中的ID和写入方式
+pub(crate) async fn import_structure<C: ConnectionTrait>(
+    db: &C,
+    model: structure::Model,
+    options: ImportOptions,
+) -> Result<(u32, Target)> {
+    let (name, smiles) = (model.name.clone(), model.smiles.clone());
+    let written = |e| ChembankError::from_structure_write(e, &name, &smiles);
+    let (model, target) = match find_existing(db, &model, options).await? {
+        Some(existing) => match options.mode {
+            ImportMode::Overwrite => {
+                property::Entity::delete_by_id(existing.id).exec(db).await?;
+                image::Entity::delete_by_id(existing.id).exec(db).await?;
+                molfile::Entity::delete_by_id(existing.id).exec(db).await?;
+                component::Entity::delete_many()
+                    .filter(component::Column::StructureId.eq(existing.id))
+                    .exec(db)
+                    .await?;
+                let model = structure::Model {
+                    id: existing.id,
+                    ..model
+                };
+                let model = structure::ActiveModel::from(model).reset_all();
+                (
+                    model.update(db).await.map_err(written)?,
+                    Target::Overwritten,
+                )
+            }
+            ImportMode::Merge => {
+                let model = structure::Model {
+                    id: existing.id,
+                    name: existing.name.or(model.name),
+                    formula: match existing.formula.is_empty() {
+                        true => model.formula,
+                        false => existing.formula,
+                    },
+                    smiles: existing.smiles.or(model.smiles),
+                    charge: existing.charge,
+                };
+                let model = structure::ActiveModel::from(model).reset_all();
+                (model.update(db).await.map_err(written)?, Target::Merged)
+            }
+            _ => return Ok((existing.id, Target::Skipped)),
+        },
+        None => {
+            let occupied = options.mode != ImportMode::Fail
+                && structure::Entity::find_by_id(model.id)
+                    .one(db)
+                    .await?
+                    .is_some();
+            let mut model = structure::ActiveModel::from(model).reset_all();
+            if occupied {
+                model.id = ActiveValue::NotSet;
+            }
+            (model.insert(db).await.map_err(written)?, Target::Inserted)
+        }
+    };
+    write_composition(db, &model).await?;
+    Ok((model.id, target))
+}
+
+/// 用导入的属性补充已有属性中空白的文本
+fn merge_property(existing: property::Model, imported: property::Model) -> property::Model {
+    property::Model {
+        structure_id: existing.structure_id,
+        decomp_temp: existing.decomp_temp.or(imported.decomp_temp),
+        density: existing.density.or(imported.density),
+        diss_temp: existing.diss_temp.or(imported.diss_temp),
+        formation_enthalpy: existing.formation_enthalpy.or(imported.formation_enthalpy),
+        impact_sensitive: existing.impact_sensitive.or(imported.impact_sensitive),
+        friction_sensitivity: existing
+            .friction_sensitivity
+            .or(imported.friction_sensitivity),
+        det_velocity: existing.det_velocity.or(imported.det_velocity),
+        det_pressure: existing.det_pressure.or(imported.det_pressure),
+        n_content: existing.n_content.or(imported.n_content),
+        o_content: existing.o_content.or(imported.o_content),
+        no_content: existing.no_content.or(imported.no_content),
+        references: existing.references.or(imported.references),
+        remarks: existing.remarks.or(imported.remarks),
+        ..existing
+    }
+}
+
+/// 写入一个导入的属性，`target`为其所属结构的写入方式：跳过的结构不写入属性，
+/// 合并的结构只补充已有属性中空白的文本
+pub(crate) async fn import_property<C: ConnectionTrait>(
+    db: &C,
+    model: property::Model,
+    target: Target,
+) -> Result<()> {
+    let existing = match target {
+        Target::Skipped => return Ok(()),
+        Target::Merged => {
+            property::Entity::find_by_id(model.structure_id)
+                .one(db)
+                .await?
+        }
+        _ => None,
+    };
+    let exists = existing.is_some();
+    let mut model = match existing {
+        Some(existing) => merge_property(existing, model),
+        None => model,
+    };
+    model.fill_values();
+    let model = property::ActiveModel::from(model).reset_all();
+    match exists {
+        true => model.update(db).await.map(|_| ()),
+        false => model.insert(db).await.map(|_| ()),
+    }?;
+    Ok(())
+}
+
+/// 在事务中依次写入导入的结构、属性和组成关系，任何一条记录出错时返回带有文件和行号的错误。
+/// 返回导入文件中的结构ID到数据库中的ID和写入方式，不在文件中的ID按已有结构原样使用
+pub(crate) async fn import_tables<C: ConnectionTrait>(
+    db: &C,
+    rows: ImportRows,
+    options: ImportOptions,
+    job: &Job,
+) -> Result<HashMap<u32, (u32, Target)>> {
+    let structures = rows.structures?.into_iter().collect::<Result<Vec<_>>>()?;
+    let properties = rows.properties?.into_iter().collect::<Result<Vec<_>>>()?;
+    let components = rows.components?.into_iter().collect::<Result<Vec<_>>>()?;
+    let (structure_path, property_path, component_path) =
+        (rows.structure_path, rows.property_path, rows.component_path);
+
+    let mut targets = HashMap::new();
+    let total = structures.len() as u32;
+    for (processed, (line, model)) in (0..).zip(structures) {
+        job.step(Phase::Structures, processed, total)?;
+        let id = model.id;
+        let target = import_structure(db, model, options)
+            .await
+            .map_err(at_record(&structure_path, Some(line)))?;
+        targets.insert(id, target);
+    }
+    job.step(Phase::Structures, total, total)?;
+    let resolve = |id: u32| targets.get(&id).copied().unwrap_or((id, Target::Inserted));
+    let total = properties.len() as u32;
+    for (processed, (line, mut model)) in (0..).zip(properties) {
+        job.step(Phase::Properties, processed, total)?;
+        let (id, target) = resolve(model.structure_id);
+        model.structure_id = id;
+        import_property(db, model, target)
+            .await
+            .map_err(at_record(&property_path, Some(line)))?;
+    }
+    job.step(Phase::Properties, total, total)?;
+    let total = components.len() as u32;
+    for (processed, row) in (0..).zip(components) {
+        job.step(Phase::Components, processed, total)?;
+        let (id, target) = resolve(row.structure_id);
+        let component_id = match row.component {
+            ComponentRef::File(id) => resolve(id).0,
+            ComponentRef::Existing(id) => id,
+        };
+        let model = component::Model {
+            structure_id: id,
+            component_id,
+            count: row.count,
+        };
+        let skip = match target {
+            Target::Skipped => true,
+            Target::Merged => component::Entity::find_by_id((id, model.component_id))
+                .one(db)
+                .await?
+                .is_some(),
+            _ => false,
+        };
+        if skip {
+            continue;
+        }
+        component::ActiveModel::from(model)
+            .reset_all()
+            .insert(db)
+            .await
+            .map_err(ChembankError::from)
+            .map_err(at_record(&component_path, Some(row.line)))?;
+    }
+    job.step(Phase::Components, total, total)?;
+    Ok(targets)
+}
+
+impl Chembank {
+    /// 开始导入的事务，选择了`replace`时先在事务中删除全部已有数据。
+    /// 检查导入时也在这样的事务中进行，检查完毕后不提交
+    pub(crate) async fn begin_import(&self, options: ImportOptions) -> Result<DatabaseTransaction> {
+        let txn = self.db.begin().await?;
+        if options.replace {
+            self.ensure_writable()?;
+            clear_tables(&txn).await?;
+        }
+        Ok(txn)
+    }
+
+    /// 从导出目录导入`structures.csv`、`properties.csv`、`components.csv`和`images`目录，
+    /// 目录中有合并表`merged.csv`时以合并表代替三个CSV表格。
+    /// 全部内容在同一个事务中写入，任何一条记录出错都会撤销整个导入，错误中给出出错的文件和行号
+    pub async fn import_from_folder(
+        &self,
+        folder_path: &Path,
+        options: ImportOptions,
+        job: &Job,
+    ) -> Result<()> {
+        self.ensure_writable()?;
+        let txn = self.begin_import(options).await?;
+        let rows = read_import_rows(&txn, folder_path).await?;
+        let targets = import_tables(&txn, rows, options, job).await?;
+        let resolve = |id: u32| targets.get(&id).copied().unwrap_or((id, Target::Inserted));
+        let image_folder = folder_path.join("images");
+        let image_folders = fs::read_dir(&image_folder)
+            .and_then(|items| items.collect::<std::io::Result<Vec<_>>>())
+            .map_err(|e| ChembankError::io(&image_folder, e))?;
+        let total = image_folders.len() as u32;
+        for (processed, item) in (0..).zip(image_folders) {
+            job.step(Phase::Images, processed, total)?;
+            let (structure_id, filename, full_image_path) = read_image_folder(&item.path())?;
+            let (structure_id, target) = resolve(structure_id);
+            let skip = match target {
+                Target::Skipped => true,
+                Target::Merged => image::Entity::find_by_id(structure_id)
+                    .one(&txn)
+                    .await?
+                    .is_some(),
+                _ => false,
+            };
+            if skip {
+                continue;
+            }
+            let mut image_content = vec![];
+            File::open(&full_image_path)
+                .and_then(|mut file| file.read_to_end(&mut image_content))
+                .map_err(|e| ChembankError::io(&full_image_path, e))?;
+            let model = image::ActiveModel {
+                structure_id: ActiveValue::set(structure_id),
+                filename: ActiveValue::set(filename),
+                image: ActiveValue::set(image_content),
+            };
+            model
+                .insert(&txn)
+                .await
+                .map_err(ChembankError::from)
+                .map_err(at_record(&full_image_path, None))?;
+        }
+        job.step(Phase::Images, total, total)?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// 导出范围内的结构ID，加上这些结构直接或间接含有的组成部分
+    async fn subset_ids(&self, subset: ExportSubset) -> Result<HashSet<u32>> {
+        let db = &self.db;
+        let ids = match subset {
+            ExportSubset::Ids(ids) => {
+                for chunk in ids.chunks(ID_CHUNK) {
+                    let found = structure::Entity::find()
+                        .select_only()
+                        .column(structure::Column::Id)
+                        .filter(structure::Column::Id.is_in(chunk.iter().copied()))
+                        .into_tuple::<u32>()
+                        .all(db)
+                        .await?
+                        .into_iter()
+                        .collect::<HashSet<_>>();
+                    if let Some(id) = chunk.iter().find(|id| !found.contains(id)) {
+                        return Err(ChembankError::NotFound {
+                            table: Table::Structure,
+                            key: vec![*id],
+                        });
+                    }
+                }
+                ids
+            }
+            ExportSubset::Filter(filter) => self.filter_structure_ids(filter).await?,
+        };
+        let mut selected = ids.iter().copied().collect::<HashSet<_>>();
+        let closure = Alias::new("component_closure");
+        let builder = db.get_database_backend();
+        for chunk in ids.chunks(ID_CHUNK) {
+            let mut select = SelectStatement::new();
+            select
+                .distinct()
+                .column(Alias::new("component_id"))
+                .from(closure.clone())
+                .and_where(Expr::col(Alias::new("structure_id")).is_in(chunk.iter().copied()));
+            for row in db.query_all(builder.build(&select)).await? {
+                selected.insert(row.try_get::<u32>("", "component_id")?);
+            }
+        }
+        Ok(selected)
+    }
+
+    /// 将全部数据或`options.subset`指定的部分结构导出到目录，返回导出的结构数量。
+    /// 三个CSV文件均带有UTF-8 BOM以便在Excel中打开，取消导出时已经写入的文件不会删除
+    pub async fn export_to_folder(
+        &self,
+        folder_path: &Path,
+        options: ExportOptions,
+        job: &Job,
+    ) -> Result<u32> {
+        let db = &self.db;
+        let selected = match options.subset {
+            Some(subset) => Some(self.subset_ids(subset).await?),
+            None => None,
+        };
+        let _ = fs::create_dir(folder_path);
+        let mut structures = structure::Entity::find().all(db).await?;
+        let mut properties = property::Entity::find().all(db).await?;
+        let mut components = component::Entity::find().all(db).await?;
+        if let Some(selected) = &selected {
+            structures.retain(|model| selected.contains(&model.id));
+            properties.retain(|model| selected.contains(&model.structure_id));
+            components.retain(|model| selected.contains(&model.structure_id));
+        }
+        let exported = structures.len() as u32;
+        if options.merged {
+            let merged_path = folder_path.join(MERGED_FILE);
+            let mut merged_csv = create_csv(&merged_path)?;
+            let names = structures
+                .iter()
+                .map(|model| (model.id, model.name.clone()))
+                .collect::<HashMap<_, _>>();
+            let mut members = HashMap::<u32, Vec<_>>::new();
+            for component in components {
+                let name = names.get(&component.component_id).cloned().flatten();
+                let member = Member::new(component.component_id, name);
+                members
+                    .entry(component.structure_id)
+                    .or_default()
+                    .push((component.count, member));
+            }
+            let mut properties = properties
+                .into_iter()
+                .map(|model| (model.structure_id, model))
+                .collect::<HashMap<_, _>>();
+            let total = structures.len() as u32;
+            for (processed, structure) in (0..).zip(structures) {
+                job.step(Phase::Structures, processed, total)?;
+                let summary = members
+                    .remove(&structure.id)
+                    .map(|members| format_summary(&members));
+                let property = properties.remove(&structure.id);
+                merged_csv
+                    .serialize(MergedRow::new(structure, property, summary))
+                    .map_err(|e| ChembankError::csv(&merged_path, None, e))?;
+            }
+            job.step(Phase::Structures, total, total)?;
+        } else {
+            let structures_path = folder_path.join("structures.csv");
+            let mut structure_csv = create_csv(&structures_path)?;
+            let total = structures.len() as u32;
+            for (processed, structure) in (0..).zip(structures) {
+                job.step(Phase::Structures, processed, total)?;
+                structure_csv
+                    .serialize(structure)
+                    .map_err(|e| ChembankError::csv(&structures_path, None, e))?;
+            }
+            job.step(Phase::Structures, total, total)?;
+            let properties_path = folder_path.join("properties.csv");
+            let mut property_csv = create_csv(&properties_path)?;
+            let total = properties.len() as u32;
+            for (processed, property) in (0..).zip(properties) {
+                job.step(Phase::Properties, processed, total)?;
+                property_csv
+                    .serialize(property)
+                    .map_err(|e| ChembankError::csv(&properties_path, None, e))?;
+            }
+            job.step(Phase::Properties, total, total)?;
+            let components_path = folder_path.join("components.csv");
+            let mut component_csv = create_csv(&components_path)?;
+            let total = components.len() as u32;
+            for (processed, component) in (0..).zip(components) {
+                job.step(Phase::Components, processed, total)?;
+                component_csv
+                    .serialize(component)
+                    .map_err(|e| ChembankError::csv(&components_path, None, e))?;
+            }
+            job.step(Phase::Components, total, total)?;
+        }
+        let image_folder = folder_path.join("images");
+        let _ = fs::create_dir(&image_folder);
+        // 只导出部分结构时按ID分批读取图片，避免读出范围外的图片内容
+        let batches = match &selected {
+            Some(selected) => {
+                let mut ids = selected.iter().copied().collect::<Vec<_>>();
+                ids.sort_unstable();
+                ids.chunks(ID_CHUNK)
+                    .map(|chunk| Some(chunk.to_vec()))
+                    .collect::<Vec<_>>()
+            }
+            None => vec![None],
+        };
+        let mut pages = Vec::new();
+        let mut total = 0;
+        for batch in batches {
+            let mut images = image::Entity::find();
+            if let Some(batch) = batch {
+                images = images.filter(image::Column::StructureId.is_in(batch));
+            }
+            let image_pages = images
+                .order_by_asc(image::Column::StructureId)
+                .paginate(db, 10);
+            total += image_pages.num_items().await? as u32;
+            pages.push(image_pages);
+        }
+        let mut processed = 0;
+        for mut image_pages in pages {
+            while let Some(images) = image_pages.fetch_and_next().await? {
+                for image in images {
+                    job.step(Phase::Images, processed, total)?;
+                    processed += 1;
+                    let image_folder = image_folder.join(image.structure_id.to_string());
+                    let _ = fs::create_dir(&image_folder);
+                    let write_path = image_folder.join(image.filename);
+                    File::create(&write_path)
+                        .and_then(|mut file| file.write_all(&image.image))
+                        .map_err(|e| ChembankError::io(&write_path, e))?;
+                }
+            }
+        }
+        job.step(Phase::Images, total, total)?;
+        Ok(exported)
+    }
+}
+
+#[tokio::test]
+async fn test_import_rollback() {
+    use std::fs::OpenOptions;
+    let folder = std::env::temp_dir().join(format!("chembank-import-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    let mut source = Chembank::connect("sqlite::memory:").await.unwrap();
+    source.migrate().await.unwrap();
+    source
+        .create_structure(
+            Some("钠离子".to_string()),
+            "Na".to_string(),
+            Some("[Na+]".to_string()),
+            1,
+        )
+        .await
+        .unwrap();
+    source
+        .create_structure(
+            Some("氯离子".to_string()),
+            "Cl".to_string(),
+            Some("[Cl-]".to_string()),
+            -1,
+        )
+        .await
+        .unwrap();
+    source
+        .export_to_folder(&folder, ExportOptions::default(), &Job::default())
+        .await
+        .unwrap();
+    // 第4行与第2行的名称重复
+    let structures_path = folder.join("structures.csv");
+    OpenOptions::new()
+        .append(true)
+        .open(&structures_path)
+        .and_then(|mut file| file.write_all("9,钠离子,Na,,1\n".as_bytes()))
+        .unwrap();
+    let mut target = Chembank::connect("sqlite::memory:").await.unwrap();
+    target.migrate().await.unwrap();
+    assert_eq!(
+        target
+            .import_from_folder(&folder, ImportOptions::default(), &Job::default())
+            .await,
+        Err(ChembankError::ImportRecord {
+            file: structures_path.to_string_lossy().to_string(),
+            line: Some(4),
+            cause: Box::new(ChembankError::DuplicateName {
+                name: "钠离子".to_string()
+            })
+        })
+    );
+    assert_eq!(target.structure_count().await.unwrap(), 0);
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_import_modes() {
+    let folder = std::env::temp_dir().join(format!("chembank-modes-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    let mut source = Chembank::connect("sqlite::memory:").await.unwrap();
+    source.migrate().await.unwrap();
+    let mut ids = vec![];
+    for (name, formula, smiles, charge) in [
+        ("钠离子", "Na", "[Na+]", 1),
+        ("氯离子", "Cl", "[Cl-]", -1),
+        ("氯化钠", "NaCl", "[Na+].[Cl-]", 0),
+    ] {
+        let id = source
+            .create_structure(
+                Some(name.to_string()),
+                formula.to_string(),
+                Some(smiles.to_string()),
+                charge,
+            )
+            .await
+            .unwrap();
+        ids.push(id);
+    }
+    source.set_component(ids[2], ids[0], 1).await.unwrap();
+    source.set_component(ids[2], ids[1], 1).await.unwrap();
+    source
+        .set_property(property::Model {
+            structure_id: ids[0],
+            density: Some("0.97".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    source
+        .export_to_folder(&folder, ExportOptions::default(), &Job::default())
+        .await
+        .unwrap();
+
+    // 已有钾离子（ID 1）和没有SMILES的钠离子（ID 2）
+    async fn target() -> Chembank {
+        let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+        db.migrate().await.unwrap();
+        db.create_structure(Some("钾离子".to_string()), "K".to_string(), None, 1)
+            .await
+            .unwrap();
+        let sodium = db
+            .create_structure(Some("钠离子".to_string()), "Na".to_string(), None, 1)
+            .await
+            .unwrap();
+        db.set_property(property::Model {
+            structure_id: sodium,
+            remarks: Some("已有".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db
+    }
+    let options = |mode, match_by| ImportOptions {
+        mode,
+        match_by,
+        ..Default::default()
+    };
+    let components = |db: &Chembank, id: u32| {
+        let db = db.connection().clone();
+        async move {
+            component::Entity::find()
+                .filter(component::Column::StructureId.eq(id))
+                .all(&db)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|model| model.component_id)
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // 钠离子按名称跳过，氯离子和氯化钠的ID已被占用，重新编号为3和4
+    let db = target().await;
+    db.import_from_folder(
+        &folder,
+        options(ImportMode::Skip, MatchBy::Name),
+        &Job::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 4);
+    let (sodium, property, ..) = db.get_structure_detail(2).await.unwrap();
+    assert_eq!(sodium.smiles, None);
+    assert_eq!(property.unwrap().density, None);
+    assert_eq!(components(&db, 4).await, vec![2, 3]);
+
+    let db = target().await;
+    db.import_from_folder(
+        &folder,
+        options(ImportMode::Merge, MatchBy::Name),
+        &Job::default(),
+    )
+    .await
+    .unwrap();
+    let (sodium, property, ..) = db.get_structure_detail(2).await.unwrap();
+    assert_eq!(sodium.smiles.as_deref(), Some("[Na+]"));
+    let property = property.unwrap();
+    assert_eq!(property.remarks.as_deref(), Some("已有"));
+    assert_eq!(property.density_value, Some(0.97));
+
+    // 按ID覆盖时钠离子替换钾离子，名称与ID 2重复，整个导入被撤销
+    let db = target().await;
+    assert!(matches!(
+        db.import_from_folder(
+            &folder,
+            options(ImportMode::Overwrite, MatchBy::Id),
+            &Job::default()
+        )
+        .await,
+        Err(ChembankError::ImportRecord { line: Some(2), .. })
+    ));
+    assert_eq!(db.structure_count().await.unwrap(), 2);
+    let db = target().await;
+    db.remove_structure(2).await.unwrap();
+    db.import_from_folder(
+        &folder,
+        options(ImportMode::Overwrite, MatchBy::Id),
+        &Job::default(),
+    )
+    .await
+    .unwrap();
+    let (sodium, property, ..) = db.get_structure_detail(1).await.unwrap();
+    assert_eq!(sodium.name.as_deref(), Some("钠离子"));
+    assert_eq!(property.unwrap().density_value, Some(0.97));
+    assert_eq!(components(&db, 3).await, vec![1, 2]);
+
+    let db = target().await;
+    db.remove_structure(2).await.unwrap();
+    db.import_from_folder(
+        &folder,
+        options(ImportMode::Renumber, MatchBy::Id),
+        &Job::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 4);
+    assert_eq!(components(&db, 4).await, vec![2, 3]);
+
+    // 清空后导入时只留下导入的结构，原ID不再被占用
+    let db = target().await;
+    let replace = ImportOptions {
+        replace: true,
+        ..Default::default()
+    };
+    db.import_from_folder(&folder, replace, &Job::default())
+        .await
+        .unwrap();
+    assert_eq!(db.structure_count().await.unwrap(), 3);
+    let (sodium, property, ..) = db.get_structure_detail(1).await.unwrap();
+    assert_eq!(sodium.name.as_deref(), Some("钠离子"));
+    assert_eq!(property.unwrap().density_value, Some(0.97));
+    assert_eq!(components(&db, 3).await, vec![1, 2]);
+
+    // 写入属性时取消，已写入的结构随之撤销
+    let db = target().await;
+    let slot = std::sync::Arc::new(std::sync::OnceLock::<Job>::new());
+    let job = Job::new({
+        let slot = slot.clone();
+        move |progress| {
+            if progress.phase == Phase::Properties {
+                slot.get().unwrap().cancel();
+            }
+        }
+    });
+    slot.set(job.clone()).ok();
+    let options = options(ImportMode::Skip, MatchBy::Name);
+    assert_eq!(
+        db.import_from_folder(&folder, options, &job).await,
+        Err(ChembankError::Cancelled)
+    );
+    assert_eq!(db.structure_count().await.unwrap(), 2);
+    // 清空后导入被取消时，已删除的数据随之恢复
+    let db = target().await;
+    assert_eq!(
+        db.import_from_folder(&folder, replace, &job).await,
+        Err(ChembankError::Cancelled)
+    );
+    assert_eq!(db.structure_count().await.unwrap(), 2);
+    let (_, property, ..) = db.get_structure_detail(2).await.unwrap();
+    assert_eq!(property.unwrap().remarks.as_deref(), Some("已有"));
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_merged_table() {
+    let folder = std::env::temp_dir().join(format!("chembank-merged-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    let mut source = Chembank::connect("sqlite::memory:").await.unwrap();
+    source.migrate().await.unwrap();
+    let sodium = source
+        .create_structure(Some("钠离子".to_string()), "Na".to_string(), None, 1)
+        .await
+        .unwrap();
+    let carbonate = source
+        .create_structure(Some("碳酸根".to_string()), "CO3(2-)".to_string(), None, -2)
+        .await
+        .unwrap();
+    let water = source
+        .create_structure(None, "H2O".to_string(), None, 0)
+        .await
+        .unwrap();
+    let salt = source
+        .create_structure(Some("碳酸钠".to_string()), "Na2CO3".to_string(), None, 0)
+        .await
+        .unwrap();
+    source.set_component(salt, sodium, 2).await.unwrap();
+    source.set_component(salt, carbonate, 1).await.unwrap();
+    source.set_component(salt, water, 10).await.unwrap();
+    source
+        .set_property(property::Model {
+            structure_id: salt,
+            density: Some("2.54".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    let options = ExportOptions {
+        merged: true,
+        ..Default::default()
+    };
+    source
+        .export_to_folder(&folder, options, &Job::default())
+        .await
+        .unwrap();
+    assert!(!folder.join("structures.csv").exists());
+    let text = fs::read_to_string(folder.join(MERGED_FILE)).unwrap();
+    assert!(text.contains(&format!("2×钠离子 + 1×碳酸根 + 10×#{}", water)));
+
+    // 目标数据库中已有钠离子，按名称跳过后组成摘要中的钠离子指向已有结构
+    let mut target = Chembank::connect("sqlite::memory:").await.unwrap();
+    target.migrate().await.unwrap();
+    target
+        .create_structure(Some("氯离子".to_string()), "Cl".to_string(), None, -1)
+        .await
+        .unwrap();
+    let existing = target
+        .create_structure(Some("钠离子".to_string()), "Na".to_string(), None, 1)
+        .await
+        .unwrap();
+    let options = ImportOptions {
+        mode: ImportMode::Skip,
+        match_by: MatchBy::Name,
+        ..Default::default()
+    };
+    let report = target.check_import(&folder, options).await.unwrap();
+    assert_eq!(report.errors, vec![]);
+    assert_eq!(
+        (report.structures, report.properties, report.components),
+        (4, 1, 3)
+    );
+    assert!(report.importable());
+    target
+        .import_from_folder(&folder, options, &Job::default())
+        .await
+        .unwrap();
+    assert_eq!(target.structure_count().await.unwrap(), 5);
+    let salt = target.search_structure(
+        Some("碳酸钠".to_string()),
+        i8::MAX,
+        i8::MIN,
+        vec![],
+        None,
+        vec![],
+        None,
+        1,
+    );
+    let salt = salt.await.unwrap().hits[0].structure.id;
+    let (_, property, _, components, _) = target.get_structure_detail(salt).await.unwrap();
+    assert_eq!(property.unwrap().density_value, Some(2.54));
+    let mut components = components
+        .into_iter()
+        .map(|(component, model)| (component.count, model.unwrap().formula))
+        .collect::<Vec<_>>();
+    components.sort();
+    assert_eq!(
+        components,
+        vec![
+            (1, "CO3(2-)".to_string()),
+            (2, "Na".to_string()),
+            (10, "H2O".to_string())
+        ]
+    );
+    let sodium = component::Entity::find_by_id((salt, existing))
+        .one(target.connection())
+        .await
+        .unwrap();
+    assert_eq!(sodium.map(|model| model.count), Some(2));
+    fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn test_export_subset() {
+    let folder = std::env::temp_dir().join(format!("chembank-subset-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    let mut source = Chembank::connect("sqlite::memory:").await.unwrap();
+    source.migrate().await.unwrap();
+    let mut ids = vec![];
+    for (name, formula, charge) in [
+        ("钠离子", "Na", 1),
+        ("钾离子", "K", 1),
+        ("碳酸根", "CO3", -2),
+        ("硝酸根", "NO3", -1),
+        ("碳酸钠", "Na2CO3", 0),
+        ("硝酸钾", "KNO3", 0),
+        ("混合盐", "Na2CO3·KNO3", 0),
+    ] {
+        ids.push(
+            source
+                .create_structure(Some(name.to_string()), formula.to_string(), None, charge)
+                .await
+                .unwrap(),
+        );
+    }
+    for (structure, component) in [(4, 0), (4, 2), (5, 1), (5, 3), (6, 4), (6, 5)] {
+        source
+            .set_component(ids[structure], ids[component], 1)
+            .await
+            .unwrap();
+    }
+    for id in [ids[1], ids[2]] {
+        source
+            .set_image(id, b"<svg/>".to_vec(), "a.svg".to_string())
+            .await
+            .unwrap();
+    }
+    let exported = |subset: ExportSubset| {
+        let (source, folder) = (&source, &folder);
+        async move {
+            let _ = fs::remove_dir_all(folder);
+            let options = ExportOptions {
+                subset: Some(subset),
+                ..Default::default()
+            };
+            source
+                .export_to_folder(folder, options, &Job::default())
+                .await?;
+            let mut ids = open_csv(&folder.join("structures.csv"))?
+                .deserialize::<structure::Model>()
+                .map(|model| model.unwrap().id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            Ok::<_, ChembankError>(ids)
+        }
+    };
+
+    // 碳酸钠连同钠离子和碳酸根一起导出
+    let subset = ExportSubset::Ids(vec![ids[4]]);
+    assert_eq!(
+        exported(subset).await.unwrap(),
+        vec![ids[0], ids[2], ids[4]]
+    );
+    assert!(folder.join("images").join(ids[2].to_string()).exists());
+    assert!(!folder.join("images").join(ids[1].to_string()).exists());
+    // 混合盐间接含有的离子也一并导出
+    let filter = SearchFilter {
+        query: Some("name:混合盐".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(exported(ExportSubset::Filter(filter)).await.unwrap(), ids);
+    let report = source
+        .check_import(&folder, ImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(report.errors, vec![]);
+    // 阳离子不含组成部分，只导出其本身
+    let filter = SearchFilter {
+        min_charge: 1,
+        ..Default::default()
+    };
+    let subset = ExportSubset::Filter(filter);
+    assert_eq!(exported(subset).await.unwrap(), vec![ids[0], ids[1]]);
+    assert!(matches!(
+        exported(ExportSubset::Ids(vec![ids[0], 99])).await,
+        Err(ChembankError::NotFound { key, .. }) if key == vec![99]
+    ));
+    let _ = fs::remove_dir_all(&folder);
+}
@@ -0,0 +1,660 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fs,
+    hash::Hash,
+    path::Path,
+};
+
+use sea_orm::{EntityTrait, QuerySelect};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use entities::*;
+
+use crate::{
+    error::Result,
+    transfer::{
+        read_image_folder, read_import_rows, ComponentRef, ImportMode, ImportOptions, MatchBy,
+    },
+    Chembank, ChembankError, Table,
+};
+
+/// 导入前检查发现的一处错误，行号从1开始并包含表头行；图片和整个文件的错误没有行号
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+pub struct ImportIssue {
+    pub file: String,
+    pub line: Option<u32>,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+impl From<ChembankError> for ImportIssue {
+    fn from(e: ChembankError) -> Self {
+        match e {
+            ChembankError::CsvFormat {
+                file,
+                line,
+                column,
+                message,
+            } => Self {
+                file,
+                line,
+                column,
+                message,
+            },
+            ChembankError::Io { path, message } => Self {
+                file: path.unwrap_or_default(),
+                line: None,
+                column: None,
+                message,
+            },
+            ChembankError::InvalidImageFolder { path, message } => Self {
+                file: path,
+                line: None,
+                column: None,
+                message,
+            },
+            e => Self {
+                file: String::new(),
+                line: None,
+                column: None,
+                message: e.to_string(),
+            },
+        }
+    }
+}
+
+/// 按选择的导入方式，冲突的记录在导入时的处理结果
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub enum Resolution {
+    /// 导入失败
+    Error,
+    /// 忽略导入的记录，保留已有记录
+    Skipped,
+    /// 用导入的记录替换已有记录
+    Overwritten,
+    /// 只用导入的记录补充已有记录中空白的内容
+    Merged,
+    /// 导入的结构改用新的ID
+    Renumbered,
+}
+
+impl Resolution {
+    /// 导入的结构与已有结构相同时的处理结果
+    fn of(mode: ImportMode) -> Self {
+        match mode {
+            ImportMode::Fail => Self::Error,
+            ImportMode::Skip => Self::Skipped,
+            ImportMode::Overwrite => Self::Overwritten,
+            ImportMode::Merge => Self::Merged,
+            ImportMode::Renumber => Self::Renumbered,
+        }
+    }
+}
+
+/// 待导入的记录与数据库中已有记录的冲突，`field`为冲突的列，`existing_id`为已有记录所属结构的ID
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Type)]
+pub struct ImportConflict {
+    pub file: String,
+    pub line: Option<u32>,
+    pub table: Table,
+    pub field: String,
+    pub value: String,
+    pub existing_id: u32,
+    pub resolution: Resolution,
+}
+
+/// 导入前的检查结果，各表的数量为文件中可以解析的记录数。
+/// 没有错误且冲突都不会导致导入失败时，导入会按选择的方式写入这些记录
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Type)]
+pub struct ImportReport {
+    pub structures: u32,
+    pub properties: u32,
+    pub components: u32,
+    pub images: u32,
+    pub conflicts: Vec<ImportConflict>,
+    pub errors: Vec<ImportIssue>,
+}
+
+impl ImportReport {
+    /// 按检查时的导入方式能否成功导入
+    pub fn importable(&self) -> bool {
+        self.errors.is_empty()
+            && self
+                .conflicts
+                .iter()
+                .all(|conflict| conflict.resolution != Resolution::Error)
+    }
+
+    fn error(&mut self, file: &Path, line: Option<u32>, column: &str, message: String) {
+        self.errors.push(ImportIssue {
+            file: file.to_string_lossy().to_string(),
+            line,
+            column: Some(column.to_string()),
+            message,
+        });
+    }
+
+    /// 记录一处冲突，最后一个参数为已有记录所属结构的ID和冲突的处理结果
+    fn conflict(
+        &mut self,
+        file: &Path,
+        line: Option<u32>,
+        table: Table,
+        field: &str,
+        value: impl ToString,
+        (existing_id, resolution): (u32, Resolution),
+    ) {
+        self.conflicts.push(ImportConflict {
+            file: file.to_string_lossy().to_string(),
+            line,
+            table,
+            field: field.to_string(),
+            value: value.to_string(),
+            existing_id,
+            resolution,
+        });
+    }
+}
+
+/// 记录某个值第一次出现的行号，再次出现时返回第一次的行号
+fn first_seen<K: Hash + Eq>(seen: &mut HashMap<K, u32>, key: K, line: u32) -> Option<u32> {
+    match seen.entry(key) {
+        Entry::Occupied(entry) => Some(*entry.get()),
+        Entry::Vacant(entry) => {
+            entry.insert(line);
+            None
+        }
+    }
+}
+
+impl Chembank {
+    /// 按`options`检查导出目录能否导入当前数据库，不写入任何内容。
+    /// 检查表格格式、文件内的重复、与已有结构的名称/SMILES/ID冲突，以及属性、组成和图片引用的结构是否存在，
+    /// 每处冲突按选择的导入方式给出处理结果。选择了`replace`时按清空后的数据库检查
+    pub async fn check_import(
+        &self,
+        folder_path: &Path,
+        options: ImportOptions,
+    ) -> Result<ImportReport> {
+        // 与导入时相同的事务中检查，不提交
+        let txn = self.begin_import(options).await?;
+        let db = &txn;
+        let mode = options.mode;
+        let mut report = ImportReport::default();
+        let existing = structure::Entity::find()
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|model| (model.id, model))
+            .collect::<HashMap<_, _>>();
+        let mut existing_names = HashMap::new();
+        let mut existing_smiles = HashMap::new();
+        for model in existing.values() {
+            if let Some(name) = &model.name {
+                existing_names.insert(name.clone(), model.id);
+            }
+            if let Some(smiles) = &model.smiles {
+                existing_smiles.insert(smiles.clone(), model.id);
+            }
+        }
+        let mut known_ids = existing.keys().copied().collect::<HashSet<_>>();
+        // 导入文件中的结构在数据库中的ID及其属性、组成和图片与已有记录冲突时的处理结果，
+        // 改用新ID的结构为空
+        let mut placements = HashMap::new();
+
+        let rows = read_import_rows(db, folder_path).await?;
+        let structure_path = rows.structure_path;
+        match rows.structures {
+            Err(e) => report.errors.push(e.into()),
+            Ok(rows) => {
+                let (mut ids, mut names, mut smileses) =
+                    (HashMap::new(), HashMap::new(), HashMap::new());
+                for row in rows {
+                    let (line, model) = match row {
+                        Ok(row) => row,
+                        Err(e) => {
+                            report.errors.push(e.into());
+                            continue;
+                        }
+                    };
+                    report.structures += 1;
+                    // 与导入结构相同的已有结构，与`find_existing`的判断一致
+                    let matched = match (mode, options.match_by) {
+                        (ImportMode::Fail | ImportMode::Renumber, _) => None,
+                        (_, MatchBy::Id) => existing.get(&model.id),
+                        (_, MatchBy::Name) => model
+                            .name
+                            .as_ref()
+                            .and_then(|name| existing_names.get(name))
+                            .map(|id| &existing[id]),
+                        (_, MatchBy::Smiles) => model
+                            .smiles
+                            .as_ref()
+                            .and_then(|smiles| existing_smiles.get(smiles))
+                            .map(|id| &existing[id]),
+                    };
+                    let occupied = existing.contains_key(&model.id);
+                    let placement = match matched {
+                        Some(matched) => Some((matched.id, Resolution::of(mode))),
+                        None if occupied && mode != ImportMode::Fail => None,
+                        None => Some((model.id, Resolution::Error)),
+                    };
+                    // 名称和SMILES不能与其他已有结构相同，合并时只有已有结构中空白的值会被导入的值代替
+                    let unique = |id: u32, kept: bool| match matched {
+                        Some(matched) if matched.id == id => Resolution::of(mode),
+                        Some(_)
+                            if mode == ImportMode::Skip || (mode == ImportMode::Merge && kept) =>
+                        {
+                            Resolution::of(mode)
+                        }
+                        _ => Resolution::Error,
+                    };
+                    if let Some(first) = first_seen(&mut ids, model.id, line) {
+                        let message = format!("ID {}与第{}行重复", model.id, first);
+                        report.error(&structure_path, Some(line), "id", message);
+                    } else {
+                        placements.insert(model.id, placement);
+                        if occupied {
+                            let id = model.id;
+                            let resolution = match (matched, mode) {
+                                (Some(_), _) => Resolution::of(mode),
+                                (None, ImportMode::Fail) => Resolution::Error,
+                                (None, _) => Resolution::Renumbered,
+                            };
+                            report.conflict(
+                                &structure_path,
+                                Some(line),
+                                Table::Structure,
+                                "id",
+                                id,
+                                (id, resolution),
+                            );
+                        }
+                    }
+                    if let Some(name) = &model.name {
+                        if let Some(first) = first_seen(&mut names, name.clone(), line) {
+                            let message = format!("名称“{}”与第{}行重复", name, first);
+                            report.error(&structure_path, Some(line), "name", message);
+                        } else if let Some(id) = existing_names.get(name) {
+                            let table = Table::Structure;
+                            let kept = matched.is_some_and(|matched| matched.name.is_some());
+                            report.conflict(
+                                &structure_path,
+                                Some(line),
+                                table,
+                                "name",
+                                name,
+                                (*id, unique(*id, kept)),
+                            );
+                        }
+                    }
+                    if let Some(smiles) = &model.smiles {
+                        if let Some(first) = first_seen(&mut smileses, smiles.clone(), line) {
+                            let message = format!("SMILES“{}”与第{}行重复", smiles, first);
+                            report.error(&structure_path, Some(line), "smiles", message);
+                        } else if let Some(id) = existing_smiles.get(smiles) {
+                            let table = Table::Structure;
+                            let kept = matched.is_some_and(|matched| matched.smiles.is_some());
+                            report.conflict(
+                                &structure_path,
+                                Some(line),
+                                table,
+                                "smiles",
+                                smiles,
+                                (*id, unique(*id, kept)),
+                            );
+                        }
+                    }
+                }
+                known_ids.extend(ids.into_keys());
+            }
+        }
+        // 不在导入文件中的结构原样使用，其属性、组成和图片与已有记录冲突时导入失败
+        let placement = |id: u32| {
+            placements
+                .get(&id)
+                .copied()
+                .unwrap_or(Some((id, Resolution::Error)))
+        };
+        let missing = |id: u32| format!("结构{}既不在数据库中，也不在导入的结构中", id);
+
+        let property_path = rows.property_path;
+        let existing_properties = property::Entity::find()
+            .select_only()
+            .column(property::Column::StructureId)
+            .into_tuple::<u32>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        match rows.properties {
+            Err(e) => report.errors.push(e.into()),
+            Ok(rows) => {
+                let mut ids = HashMap::new();
+                for row in rows {
+                    let (line, model) = match row {
+                        Ok(row) => row,
+                        Err(e) => {
+                            report.errors.push(e.into());
+                            continue;
+                        }
+                    };
+                    report.properties += 1;
+                    let id = model.structure_id;
+                    if !known_ids.contains(&id) {
+                        report.error(&property_path, Some(line), "structure_id", missing(id));
+                    } else if let Some(first) = first_seen(&mut ids, id, line) {
+                        let message = format!("结构{}的属性与第{}行重复", id, first);
+                        report.error(&property_path, Some(line), "structure_id", message);
+                    } else if let Some((id, resolution)) = placement(id) {
+                        if existing_properties.contains(&id) {
+                            let table = Table::Property;
+                            report.conflict(
+                                &property_path,
+                                Some(line),
+                                table,
+                                "structure_id",
+                                id,
+                                (id, resolution),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let component_path = rows.component_path;
+        // 合并表中的组成关系都在组成摘要一列中
+        let merged = component_path == structure_path;
+        let column = |name| match merged {
+            true => "components",
+            false => name,
+        };
+        let existing_components = component::Entity::find()
+            .select_only()
+            .columns([
+                component::Column::StructureId,
+                component::Column::ComponentId,
+            ])
+            .into_tuple::<(u32, u32)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        match rows.components {
+            Err(e) => report.errors.push(e.into()),
+            Ok(rows) => {
+                let mut pairs = HashMap::new();
+                for row in rows {
+                    let row = match row {
+                        Ok(row) => row,
+                        Err(e) => {
+                            report.errors.push(e.into());
+                            continue;
+                        }
+                    };
+                    report.components += 1;
+                    let line = row.line;
+                    let (id, known) = match row.component {
+                        ComponentRef::File(id) => (id, known_ids.contains(&id)),
+                        ComponentRef::Existing(id) => (id, true),
+                    };
+                    let pair = (row.structure_id, id);
+                    if !known_ids.contains(&pair.0) {
+                        let message = missing(pair.0);
+                        report.error(&component_path, Some(line), column("structure_id"), message);
+                    } else if !known {
+                        let message = missing(pair.1);
+                        report.error(&component_path, Some(line), column("component_id"), message);
+                    } else if let Some(first) = first_seen(&mut pairs, pair, line) {
+                        let message =
+                            format!("结构{}的组成部分{}与第{}行重复", pair.0, pair.1, first);
+                        report.error(&component_path, Some(line), column("component_id"), message);
+                    } else if let Some((id, resolution)) = placement(pair.0) {
+                        let component_id = match row.component {
+                            ComponentRef::File(id) => placement(id).map(|(id, _)| id),
+                            ComponentRef::Existing(id) => Some(id),
+                        };
+                        if let Some(component_id) = component_id.filter(|component_id| {
+                            existing_components.contains(&(id, *component_id))
+                        }) {
+                            report.conflict(
+                                &component_path,
+                                Some(line),
+                                Table::Component,
+                                column("component_id"),
+                                component_id,
+                                (id, resolution),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        let image_folder = folder_path.join("images");
+        let existing_images = image::Entity::find()
+            .select_only()
+            .column(image::Column::StructureId)
+            .into_tuple::<u32>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect::<HashSet<_>>();
+        match fs::read_dir(&image_folder) {
+            Err(e) => report
+                .errors
+                .push(ChembankError::io(&image_folder, e).into()),
+            Ok(items) => {
+                let mut ids = HashSet::new();
+                for item in items {
+                    let structure_folder = match item {
+                        Ok(item) => item.path(),
+                        Err(e) => {
+                            report
+                                .errors
+                                .push(ChembankError::io(&image_folder, e).into());
+                            continue;
+                        }
+                    };
+                    let (id, _, image_path) = match read_image_folder(&structure_folder) {
+                        Ok(image) => image,
+                        Err(e) => {
+                            report.errors.push(e.into());
+                            continue;
+                        }
+                    };
+                    report.images += 1;
+                    let issue = |message: String| ImportIssue {
+                        file: image_path.to_string_lossy().to_string(),
+                        line: None,
+                        column: None,
+                        message,
+                    };
+                    if !image_path.is_file() {
+                        report
+                            .errors
+                            .push(issue("不是可以读取的图片文件".to_string()));
+                    } else if !known_ids.contains(&id) {
+                        report.errors.push(issue(missing(id)));
+                    } else if !ids.insert(id) {
+                        report
+                            .errors
+                            .push(issue(format!("结构{}有多个图片目录", id)));
+                    } else if let Some((id, resolution)) = placement(id) {
+                        if existing_images.contains(&id) {
+                            let table = Table::Image;
+                            report.conflict(
+                                &image_path,
+                                None,
+                                table,
+                                "structure_id",
+                                id,
+                                (id, resolution),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        txn.rollback().await?;
+        Ok(report)
+    }
+}
+
+#[tokio::test]
+async fn test_check_import() {
+    use std::io::Write;
+    let folder = std::env::temp_dir().join(format!("chembank-check-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    let mut db = Chembank::connect("sqlite::memory:").await.unwrap();
+    db.migrate().await.unwrap();
+    let sodium = db
+        .create_structure(
+            Some("钠离子".to_string()),
+            "Na".to_string(),
+            Some("[Na+]".to_string()),
+            1,
+        )
+        .await
+        .unwrap();
+    let salt = db
+        .create_structure(Some("氯化钠".to_string()), "NaCl".to_string(), None, 0)
+        .await
+        .unwrap();
+    db.set_component(salt, sodium, 1).await.unwrap();
+    db.export_to_folder(
+        &folder,
+        crate::ExportOptions::default(),
+        &crate::Job::default(),
+    )
+    .await
+    .unwrap();
+    let append = |file: &str, text: &str| {
+        fs::OpenOptions::new()
+            .append(true)
+            .open(folder.join(file))
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .unwrap();
+    };
+    append("structures.csv", "3,溴离子,Br,[Br-],abc\n4,溴离子,Br,,-1\n");
+    append("components.csv", "4,9,1\n");
+
+    let report = db
+        .check_import(&folder, ImportOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        (
+            report.structures,
+            report.properties,
+            report.components,
+            report.images
+        ),
+        (3, 0, 2, 0)
+    );
+    let conflicts = |report: &ImportReport| {
+        report
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                (
+                    conflict.table,
+                    conflict.field.clone(),
+                    conflict.existing_id,
+                    conflict.resolution,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    let conflict = |table, field: &str, id, resolution| (table, field.to_string(), id, resolution);
+    use Resolution::*;
+    assert_eq!(
+        conflicts(&report),
+        vec![
+            conflict(Table::Structure, "id", sodium, Error),
+            conflict(Table::Structure, "name", sodium, Error),
+            conflict(Table::Structure, "smiles", sodium, Error),
+            conflict(Table::Structure, "id", salt, Error),
+            conflict(Table::Structure, "name", salt, Error),
+            conflict(Table::Component, "component_id", salt, Error),
+        ]
+    );
+    assert!(!report.importable());
+
+    // 冲突的处理结果随导入方式改变
+    let options = |mode, match_by| ImportOptions {
+        mode,
+        match_by,
+        ..Default::default()
+    };
+    let cases = [
+        (
+            options(ImportMode::Skip, MatchBy::Name),
+            vec![
+                conflict(Table::Structure, "id", sodium, Skipped),
+                conflict(Table::Structure, "name", sodium, Skipped),
+                conflict(Table::Structure, "smiles", sodium, Skipped),
+                conflict(Table::Structure, "id", salt, Skipped),
+                conflict(Table::Structure, "name", salt, Skipped),
+                conflict(Table::Component, "component_id", salt, Skipped),
+            ],
+        ),
+        (
+            options(ImportMode::Overwrite, MatchBy::Id),
+            vec![
+                conflict(Table::Structure, "id", sodium, Overwritten),
+                conflict(Table::Structure, "name", sodium, Overwritten),
+                conflict(Table::Structure, "smiles", sodium, Overwritten),
+                conflict(Table::Structure, "id", salt, Overwritten),
+                conflict(Table::Structure, "name", salt, Overwritten),
+                conflict(Table::Component, "component_id", salt, Overwritten),
+            ],
+        ),
+        // 氯化钠没有SMILES，作为新结构导入时名称与已有结构相同
+        (
+            options(ImportMode::Merge, MatchBy::Smiles),
+            vec![
+                conflict(Table::Structure, "id", sodium, Merged),
+                conflict(Table::Structure, "name", sodium, Merged),
+                conflict(Table::Structure, "smiles", sodium, Merged),
+                conflict(Table::Structure, "id", salt, Renumbered),
+                conflict(Table::Structure, "name", salt, Error),
+            ],
+        ),
+        (
+            options(ImportMode::Renumber, MatchBy::Id),
+            vec![
+                conflict(Table::Structure, "id", sodium, Renumbered),
+                conflict(Table::Structure, "name", sodium, Error),
+                conflict(Table::Structure, "smiles", sodium, Error),
+                conflict(Table::Structure, "id", salt, Renumbered),
+                conflict(Table::Structure, "name", salt, Error),
+            ],
+        ),
+        // 清空数据库后导入时没有冲突
+        (
+            ImportOptions {
+                replace: true,
+                ..Default::default()
+            },
+            vec![],
+        ),
+    ];
+    for (options, expected) in cases {
+        let report = db.check_import(&folder, options).await.unwrap();
+        assert_eq!(conflicts(&report), expected, "{:?}", options);
+    }
+    let errors = report
+        .errors
+        .iter()
+        .map(|issue| (issue.line, issue.column.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![(Some(4), Some("charge")), (Some(3), Some("component_id"))]
+    );
+    assert_eq!(db.structure_count().await.unwrap(), 2);
+    fs::remove_dir_all(&folder).unwrap();
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use calamine::{open_workbook, Data, Reader, Xlsx};
+use resvg::{tiny_skia, usvg};
+use rust_xlsxwriter::{Image, Workbook, Worksheet, XlsxError};
+use sea_orm::{EntityTrait, PaginatorTrait, QueryOrder};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use specta::Type;
+
+use entities::*;
+
+use crate::{
+    error::Result,
+    progress::{Job, Phase},
+    transfer::{import_tables, ComponentRef, ComponentRow, ImportOptions, ImportRows},
+    Chembank, ChembankError,
+};
+
+const STRUCTURE_SHEET: &str = "structures";
+const PROPERTY_SHEET: &str = "properties";
+const COMPONENT_SHEET: &str = "components";
+/// 结构表中图片所在的列，位于结构的各个字段之后
+const IMAGE_COLUMN: u16 = 5;
+/// 插入图片的行高，单位为磅
+const IMAGE_ROW_HEIGHT: f64 = 80.0;
+/// 图片列的列宽，单位为字符宽度
+const IMAGE_COLUMN_WIDTH: f64 = 20.0;
+/// SVG图片栅格化时的放大倍数，使插入的图片在缩放后仍然清晰
+const SVG_SCALE: f32 = 2.0;
+
+/// Excel导出选项
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, Type)]
+pub struct XlsxOptions {
+    /// 在结构表最后的`image`列插入结构图片，图片仅供查看，导入时忽略
+    #[serde(default)]
+    pub images: bool,
+}
+
+/// 工作表在错误信息中的名称，例如`chembank.xlsx#structures`
+fn sheet_path(path: &Path, sheet: &str) -> PathBuf {
+    PathBuf::from(format!("{}#{}", path.to_string_lossy(), sheet))
+}
+
+fn workbook_error(path: &Path, message: impl Display) -> ChembankError {
+    ChembankError::XlsxFormat {
+        file: path.to_string_lossy().to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn write_error(path: &Path, e: XlsxError) -> ChembankError {
+    match e {
+        XlsxError::IoError(e) => ChembankError::io(path, e),
+        e => workbook_error(path, e),
+    }
+}
+
+/// 将SVG图片栅格化为PNG，Excel不支持直接插入SVG图片
+fn rasterize_svg(svg: &[u8], options: &usvg::Options) -> Option<Vec<u8>> {
+    let tree = usvg::Tree::from_data(svg, options).ok()?;
+    let size = tree.size().to_int_size().scale_by(SVG_SCALE)?;
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height())?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+    let transform = tiny_skia::Transform::from_scale(SVG_SCALE, SVG_SCALE);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    pixmap.encode_png().ok()
+}
+
+/// 单元格按显示的文本组成一行记录，数值单元格写为不带多余小数位的数字
+fn text_record(cells: &[Data]) -> csv::StringRecord {
+    cells.iter().map(|cell| cell.to_string()).collect()
+}
+
+/// 逐行读取工作表，第一行为表头，行号与Excel中的行号相同。单元格先转换为文本，
+/// 再按与CSV表格相同的规则解析，无法解析的行作为错误保留在结果中，空行被忽略
+fn read_sheet_rows<T: DeserializeOwned>(
+    workbook: &mut Xlsx<BufReader<File>>,
+    path: &Path,
+    sheet: &str,
+) -> Result<Vec<Result<(u32, T)>>> {
+    if !workbook.sheet_names().iter().any(|name| name == sheet) {
+        return Err(workbook_error(path, format!("缺少工作表{}", sheet)));
+    }
+    let range = workbook
+        .worksheet_range(sheet)
+        .map_err(|e| workbook_error(path, e))?;
+    let file = sheet_path(path, sheet);
+    let first_row = range.start().map_or(0, |(row, _)| row);
+    let mut cells = range.rows();
+    let headers = match cells.next() {
+        Some(header_cells) => text_record(header_cells),
+        None => return Ok(vec![]),
+    };
+    let rows = (first_row + 2..)
+        .zip(cells)
+        .filter(|(_, cells)| cells.iter().any(|cell| *cell != Data::Empty))
+        .map(|(line, cells)| {
+            let mut record = text_record(cells);
+            let mut position = csv::Position::new();
+            position.set_line(line as u64);
+            record.set_position(Some(position));
+            record
+                .deserialize(Some(&headers))
+                .map(|model| (line, model))
+                .map_err(|e| ChembankError::csv(&file, Some(&headers), e))
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// 读取工作簿中的结构、属性和组成关系三个工作表，表头与导出目录中的CSV表格相同
+fn read_xlsx_rows(path: &Path) -> Result<ImportRows> {
+    let mut workbook: Xlsx<_> = open_workbook(path).map_err(|e| workbook_error(path, e))?;
+    let components = read_sheet_rows::<component::Model>(&mut workbook, path, COMPONENT_SHEET)?
+        .into_iter()
+        .map(|row| {
+            row.map(|(line, model)| ComponentRow {
+                line,
+                structure_id: model.structure_id,
+                component: ComponentRef::File(model.component_id),
+                count: model.count,
+            })
+        })
+        .collect();
+    Ok(ImportRows {
+        structures: Ok(read_sheet_rows(&mut workbook, path, STRUCTURE_SHEET)?),
+        structure_path: sheet_path(path, STRUCTURE_SHEET),
+        properties: Ok(read_sheet_rows(&mut workbook, path, PROPERTY_SHEET)?),
+        property_path: sheet_path(path, PROPERTY_SHEET),
+        components: Ok(components),
+        component_path: sheet_path(path, COMPONENT_SHEET),
+    })
+}
+
+/// 写入一个表的全部记录，第一行为表头，表为空时不写表头，与CSV表格相同
+fn write_sheet_rows<T: Serialize>(
+    worksheet: &mut Worksheet,
+    path: &Path,
+    phase: Phase,
+    rows: &[T],
+    job: &Job,
+) -> Result<()> {
+    let Some(first) = rows.first() else {
+        return Ok(());
+    };
+    worksheet
+        .serialize_headers(0, 0, first)
+        .and_then(|worksheet| worksheet.set_freeze_panes(1, 0))
+        .map_err(|e| write_error(path, e))?;
+    let total = rows.len() as u32;
+    for (processed, row) in (0..).zip(rows) {
+        job.step(phase, processed, total)?;
+        worksheet.serialize(row).map_err(|e| write_error(path, e))?;
+    }
+    job.step(phase, total, total)
+}
+
+impl Chembank {
+    /// 从Excel工作簿导入，工作簿中的`structures`、`properties`和`components`三个工作表
+    /// 与导出目录中的三个CSV表格格式相同。全部内容在同一个事务中写入，错误中给出出错的工作表和行号
+    pub async fn import_xlsx(&self, path: &Path, options: ImportOptions, job: &Job) -> Result<()> {
+        self.ensure_writable()?;
+        let rows = read_xlsx_rows(path)?;
+        let txn = self.begin_import(options).await?;
+        import_tables(&txn, rows, options, job).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// 将全部结构、属性和组成关系导出为Excel工作簿，每个表一个工作表，
+    /// 可以选择在结构表中插入结构图片。SVG图片栅格化后插入，其他Excel无法识别的图片格式只写出文件名
+    pub async fn export_xlsx(&self, path: &Path, options: XlsxOptions, job: &Job) -> Result<()> {
+        let db = &self.db;
+        let structures = structure::Entity::find()
+            .order_by_asc(structure::Column::Id)
+            .all(db)
+            .await?;
+        let properties = property::Entity::find().all(db).await?;
+        let components = component::Entity::find().all(db).await?;
+        let mut workbook = Workbook::new();
+        let worksheet = workbook
+            .add_worksheet()
+            .set_name(STRUCTURE_SHEET)
+            .map_err(|e| write_error(path, e))?;
+        write_sheet_rows(worksheet, path, Phase::Structures, &structures, job)?;
+        if options.images && !structures.is_empty() {
+            worksheet
+                .write_string(0, IMAGE_COLUMN, "image")
+                .and_then(|worksheet| worksheet.set_column_width(IMAGE_COLUMN, IMAGE_COLUMN_WIDTH))
+                .map_err(|e| write_error(path, e))?;
+            // 结构按ID升序写出，第一行为表头
+            let rows = structures
+                .iter()
+                .zip(1..)
+                .map(|(model, row)| (model.id, row))
+                .collect::<HashMap<_, _>>();
+            let mut svg_options = usvg::Options::default();
+            svg_options.fontdb_mut().load_system_fonts();
+            let mut image_pages = image::Entity::find()
+                .order_by_asc(image::Column::StructureId)
+                .paginate(db, 10);
+            let total = image_pages.num_items().await? as u32;
+            let mut processed = 0;
+            while let Some(images) = image_pages.fetch_and_next().await? {
+                for image in images {
+                    job.step(Phase::Images, processed, total)?;
+                    processed += 1;
+                    let Some(&row) = rows.get(&image.structure_id) else {
+                        continue;
+                    };
+                    let picture = Image::new_from_buffer(&image.image).or_else(|e| {
+                        rasterize_svg(&image.image, &svg_options)
+                            .ok_or(e)
+                            .and_then(|png| Image::new_from_buffer(&png))
+                    });
+                    let written =
+                        match picture {
+                            Ok(picture) => worksheet
+                                .set_row_height(row, IMAGE_ROW_HEIGHT)
+                                .and_then(|worksheet| {
+                                    worksheet.insert_image_fit_to_cell(
+                                        row,
+                                        IMAGE_COLUMN,
+                                        &picture,
+                                        false,
+                                    )
+                                }),
+                            Err(_) => worksheet.write_string(row, IMAGE_COLUMN, &image.filename),
+                        };
+                    written.map_err(|e| write_error(path, e))?;
+                }
+            }
+            job.step(Phase::Images, total, total)?;
+        }
+        let worksheet = workbook
+            .add_worksheet()
+            .set_name(PROPERTY_SHEET)
+            .map_err(|e| write_error(path, e))?;
+        write_sheet_rows(worksheet, path, Phase::Properties, &properties, job)?;
+        let worksheet = workbook
+            .add_worksheet()
+            .set_name(COMPONENT_SHEET)
+            .map_err(|e| write_error(path, e))?;
+        write_sheet_rows(worksheet, path, Phase::Components, &components, job)?;
+        workbook.save(path).map_err(|e| write_error(path, e))?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_xlsx_import_export() {
+    use std::fs;
+
+    let folder = std::env::temp_dir().join(format!("chembank-xlsx-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    let example = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example/export");
+    let mut source = Chembank::connect("sqlite::memory:").await.unwrap();
+    source.migrate().await.unwrap();
+    source
+        .import_from_folder(&example, ImportOptions::default(), &Job::default())
+        .await
+        .unwrap();
+    let export_path = folder.join("export.xlsx");
+    source
+        .export_xlsx(&export_path, XlsxOptions { images: true }, &Job::default())
+        .await
+        .unwrap();
+
+    let mut target = Chembank::connect("sqlite::memory:").await.unwrap();
+    target.migrate().await.unwrap();
+    target
+        .import_xlsx(&export_path, ImportOptions::default(), &Job::default())
+        .await
+        .unwrap();
+    let summary = |models: Vec<structure::Model>| {
+        models
+            .into_iter()
+            .map(|model| {
+                (
+                    model.id,
+                    model.name,
+                    model.formula,
+                    model.smiles,
+                    model.charge,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        summary(structure::Entity::find().all(&target.db).await.unwrap()),
+        summary(structure::Entity::find().all(&source.db).await.unwrap())
+    );
+    assert_eq!(
+        component::Entity::find().count(&target.db).await.unwrap(),
+        component::Entity::find().count(&source.db).await.unwrap()
+    );
+    assert_eq!(
+        property::Entity::find().count(&target.db).await.unwrap(),
+        property::Entity::find().count(&source.db).await.unwrap()
+    );
+
+    // 手工编辑的工作簿中数字保存为数值单元格，空行被忽略
+    let edited_path = folder.join("edited.xlsx");
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet().set_name(STRUCTURE_SHEET).unwrap();
+    for (column, header) in ["id", "name", "formula", "smiles", "charge"]
+        .into_iter()
+        .enumerate()
+    {
+        worksheet.write_string(0, column as u16, header).unwrap();
+    }
+    worksheet.write_number(1, 0, 10).unwrap();
+    worksheet.write_string(1, 1, "水").unwrap();
+    worksheet.write_string(1, 2, "H2O").unwrap();
+    worksheet.write_number(1, 4, 0).unwrap();
+    worksheet.write_string(3, 0, "x").unwrap();
+    worksheet.write_string(3, 2, "H2").unwrap();
+    worksheet.write_number(3, 4, 0).unwrap();
+    workbook.add_worksheet().set_name(PROPERTY_SHEET).unwrap();
+    workbook.save(&edited_path).unwrap();
+    assert_eq!(
+        target
+            .import_xlsx(&edited_path, ImportOptions::default(), &Job::default())
+            .await,
+        Err(ChembankError::XlsxFormat {
+            file: edited_path.to_string_lossy().to_string(),
+            message: "缺少工作表components".to_string()
+        })
+    );
+    workbook.add_worksheet().set_name(COMPONENT_SHEET).unwrap();
+    workbook.save(&edited_path).unwrap();
+    let error = target
+        .import_xlsx(&edited_path, ImportOptions::default(), &Job::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ChembankError::CsvFormat { file, line: Some(4), column: Some(column), .. }
+            if file.ends_with("edited.xlsx#structures") && column == "id"
+    ));
+    let rows = read_xlsx_rows(&edited_path).unwrap();
+    let (line, model) = rows.structures.unwrap().remove(0).unwrap();
+    assert_eq!((line, model.id, model.smiles), (2, 10, None));
+    let _ = fs::remove_dir_all(&folder);
+}
@@ -7,11 +7,11 @@ use specta::Type;
 #[serde(rename = "Component")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    structure_id: u32,
+    pub structure_id: u32,
     #[sea_orm(primary_key)]
-    component_id: u32,
+    pub component_id: u32,
     #[sea_orm(default = 1)]
-    count: u32
+    pub count: u32
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use specta::Type;
+
+/// 由分子式解析得到的元素组成，每种元素一行，随结构的新建、修改和导入自动更新
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, DeriveEntityModel, Type)]
+#[sea_orm(table_name = "compositions")]
+#[serde(rename = "Composition")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub structure_id: u32,
+    #[sea_orm(primary_key)]
+    pub element: String,
+    pub count: u32
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::structure::Entity",
+        from = "Column::StructureId",
+        to = "super::structure::Column::Id"
+    )]
+    Structure
+}
+
+impl Related<super::structure::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Structure.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+/// 全部元素符号，按原子序数排列
+pub const ELEMENTS: [&str; 118] = [
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl",
+    "Ar", "K", "Ca", "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As",
+    "Se", "Br", "Kr", "Rb", "Sr", "Y", "Zr", "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In",
+    "Sn", "Sb", "Te", "I", "Xe", "Cs", "Ba", "La", "Ce", "Pr", "Nd", "Pm", "Sm", "Eu", "Gd", "Tb",
+    "Dy", "Ho", "Er", "Tm", "Yb", "Lu", "Hf", "Ta", "W", "Re", "Os", "Ir", "Pt", "Au", "Hg", "Tl",
+    "Pb", "Bi", "Po", "At", "Rn", "Fr", "Ra", "Ac", "Th", "Pa", "U", "Np", "Pu", "Am", "Cm", "Bk",
+    "Cf", "Es", "Fm", "Md", "No", "Lr", "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds", "Rg", "Cn", "Nh",
+    "Fl", "Mc", "Lv", "Ts", "Og",
+];
+
+/// 非金属元素和准金属，其余元素均视为金属
+const NON_METALS: [&str; 24] = [
+    "H", "He", "B", "C", "N", "O", "F", "Ne", "Si", "P", "S", "Cl", "Ar", "Ge", "As", "Se", "Br",
+    "Kr", "Sb", "Te", "I", "Xe", "At", "Rn",
+];
+
+/// 检索时代表全部金属元素的名称
+pub const METAL: &str = "metal";
+
+pub fn element(symbol: &str) -> Option<&'static str> {
+    ELEMENTS.into_iter().find(|element| *element == symbol)
+}
+
+pub fn is_metal(symbol: &str) -> bool {
+    element(symbol).is_some() && !NON_METALS.contains(&symbol)
+}
+
+/// 去掉分子式末尾的电荷标记，例如`CO3(2-)`、`SO4^2-`、`NH4+`、`Ca2+`。
+/// 末尾没有括号或`^`分隔时，符号前的数字只有与`charge`的绝对值一致时才视为电荷，
+/// 因此`NO3-`中的3仍是原子个数
+fn strip_charge(formula: &str, charge: i8) -> &str {
+    let formula = formula.trim_end_matches(|c: char| "⁺⁻⁰¹²³⁴⁵⁶⁷⁸⁹".contains(c));
+    if let Some((rest, _)) = formula.rsplit_once('^') {
+        return rest;
+    }
+    let is_charge = |text: &str| {
+        let digits = text.trim_end_matches(['+', '-']);
+        text.len() > digits.len() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        if let Some(inner) = formula.strip_suffix(close) {
+            if let Some((rest, inner)) = inner.rsplit_once(open) {
+                if is_charge(inner) {
+                    return rest;
+                }
+            }
+        }
+    }
+    let rest = formula.trim_end_matches(['+', '-']);
+    if rest.len() == formula.len() {
+        return formula;
+    }
+    let digits = rest.len() - rest.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    match rest[rest.len() - digits..].parse::<u32>() {
+        Ok(count) if count == charge.unsigned_abs() as u32 && count > 1 => {
+            &rest[..rest.len() - digits]
+        }
+        _ => rest,
+    }
+}
+
+/// 将分子式解析为各元素的原子个数，支持括号、结晶水（`CuSO4·5H2O`）和末尾的电荷标记。
+/// 分子式中含有无法识别的内容（如代号`RDX`）时返回`None`
+pub fn parse(formula: &str, charge: i8) -> Option<BTreeMap<&'static str, u32>> {
+    let formula = strip_charge(formula.trim(), charge);
+    let mut total = BTreeMap::new();
+    for part in formula.split(['·', '•', '.', '*']) {
+        let chars = part.trim().chars().collect::<Vec<_>>();
+        let mut i = 0;
+        let number = |i: &mut usize| {
+            let start = *i;
+            while *i < chars.len() && chars[*i].is_ascii_digit() {
+                *i += 1;
+            }
+            match *i > start {
+                true => chars[start..*i]
+                    .iter()
+                    .collect::<String>()
+                    .parse::<u32>()
+                    .ok(),
+                false => Some(1),
+            }
+        };
+        let coefficient = number(&mut i)?;
+        // 每层括号对应一个计数表，遇到右括号时乘以其后的个数并合并到上一层
+        let mut stack = vec![BTreeMap::<&'static str, u32>::new()];
+        while i < chars.len() {
+            match chars[i] {
+                '(' | '[' | '{' => {
+                    stack.push(BTreeMap::new());
+                    i += 1;
+                }
+                ')' | ']' | '}' => {
+                    i += 1;
+                    let group = stack.pop()?;
+                    let count = number(&mut i)?;
+                    let outer = stack.last_mut()?;
+                    for (element, n) in group {
+                        *outer.entry(element).or_default() += n.saturating_mul(count);
+                    }
+                }
+                c if c.is_ascii_uppercase() => {
+                    let start = i;
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_lowercase() {
+                        i += 1;
+                    }
+                    let element = element(&chars[start..i].iter().collect::<String>())?;
+                    let count = number(&mut i)?;
+                    *stack.last_mut()?.entry(element).or_default() += count;
+                }
+                _ => return None,
+            }
+        }
+        if stack.len() != 1 {
+            return None;
+        }
+        for (element, n) in stack.pop()? {
+            *total.entry(element).or_default() += n.saturating_mul(coefficient);
+        }
+    }
+    match total.is_empty() {
+        true => None,
+        false => Some(total),
+    }
+}
+
+/// 按Hill规则写出分子式：含碳时碳、氢在前，其余元素按符号的字母顺序排列
+pub fn hill(counts: &BTreeMap<&str, u32>) -> String {
+    let mut order = counts.keys().copied().collect::<Vec<_>>();
+    if counts.contains_key("C") {
+        order.retain(|element| !["C", "H"].contains(element));
+        order.splice(
+            0..0,
+            ["C", "H"].into_iter().filter(|e| counts.contains_key(e)),
+        );
+    }
+    order
+        .into_iter()
+        .filter(|element| counts[element] > 0)
+        .map(|element| match counts[element] {
+            1 => element.to_string(),
+            count => format!("{}{}", element, count),
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_formula() {
+    let parse = |formula: &str, charge: i8| {
+        parse(formula, charge).map(|counts| counts.into_iter().collect::<Vec<_>>())
+    };
+    assert_eq!(
+        parse("C7H5N3O6", 0),
+        Some(vec![("C", 7), ("H", 5), ("N", 3), ("O", 6)])
+    );
+    assert_eq!(
+        parse("(NH4)2SO4", 0),
+        Some(vec![("H", 8), ("N", 2), ("O", 4), ("S", 1)])
+    );
+    assert_eq!(
+        parse("CuSO4·5H2O", 0),
+        Some(vec![("Cu", 1), ("H", 10), ("O", 9), ("S", 1)])
+    );
+    assert_eq!(parse("CO3(2-)", -2), Some(vec![("C", 1), ("O", 3)]));
+    assert_eq!(parse("SO4^2-", -2), Some(vec![("O", 4), ("S", 1)]));
+    assert_eq!(parse("NO3-", -1), Some(vec![("N", 1), ("O", 3)]));
+    assert_eq!(parse("Ca2+", 2), Some(vec![("Ca", 1)]));
+    assert_eq!(parse("Na", 1), Some(vec![("Na", 1)]));
+    assert_eq!(parse("RDX", 0), None);
+    assert_eq!(parse("CL-20", 0), None);
+    assert_eq!(parse("(NH4", 1), None);
+    assert_eq!(parse("", 0), None);
+    assert!(is_metal("Na") && is_metal("Fe") && !is_metal("N") && !is_metal("Xx"));
+    let hill = |formula: &str| hill(&crate::formula::parse(formula, 0).unwrap());
+    assert_eq!(hill("C7H5N3O6"), "C7H5N3O6");
+    assert_eq!(hill("CH3Cl"), "CH3Cl");
+    assert_eq!(hill("NaCl"), "ClNa");
+    assert_eq!(hill("H2SO4"), "H2O4S");
+}
@@ -3,3 +3,7 @@ pub mod component;
 pub mod property;
 pub mod image;
 pub mod links;
+pub mod quantity;
+pub mod composition;
+pub mod formula;
+pub mod molfile;
\ No newline at end of file
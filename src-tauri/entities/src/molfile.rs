@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use sea_orm::entity::prelude::*;
+use specta::Type;
+
+/// 从SD文件导入的结构式（MOL块），保留原始坐标以便原样导出。修改结构的SMILES时删除
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, DeriveEntityModel, Type)]
+#[sea_orm(table_name = "molfiles")]
+#[serde(rename = "Molfile")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub structure_id: u32,
+    #[sea_orm(not_null)]
+    pub molblock: String
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::structure::Entity",
+        from = "Column::StructureId",
+        to = "super::structure::Column::Id"
+    )]
+    Structure
+}
+
+impl Related<super::structure::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Structure.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -2,25 +2,51 @@ use serde::{Deserialize, Serialize};
 use sea_orm::entity::prelude::*;
 use specta::Type;
 
-#[derive(Serialize, Deserialize, Debug, Clone, DeriveEntityModel, Type)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, DeriveEntityModel, Type)]
 #[sea_orm(table_name = "property")]
 #[serde(rename = "Property")]
 pub struct Model {
     #[sea_orm(primary_key)]
     pub structure_id: u32,
-    decomp_temp: Option<String>,
-    density: Option<String>,
-    diss_temp: Option<String>,
-    formation_enthalpy: Option<String>,
-    impact_sensitive: Option<String>,
-    friction_sensitivity: Option<String>,
-    det_velocity: Option<String>,
-    det_pressure: Option<String>,
-    n_content: Option<String>,
-    o_content: Option<String>,
-    no_content: Option<String>,
-    references: Option<String>,
-    remarks: Option<String>,
+    pub decomp_temp: Option<String>,
+    pub density: Option<String>,
+    pub diss_temp: Option<String>,
+    pub formation_enthalpy: Option<String>,
+    pub impact_sensitive: Option<String>,
+    pub friction_sensitivity: Option<String>,
+    pub det_velocity: Option<String>,
+    pub det_pressure: Option<String>,
+    pub n_content: Option<String>,
+    pub o_content: Option<String>,
+    pub no_content: Option<String>,
+    pub references: Option<String>,
+    pub remarks: Option<String>,
+    /// 以下数值列由对应的文本列解析得到，单位见`Quantity::unit`
+    pub decomp_temp_value: Option<f64>,
+    pub density_value: Option<f64>,
+    pub diss_temp_value: Option<f64>,
+    pub formation_enthalpy_value: Option<f64>,
+    pub impact_sensitive_value: Option<f64>,
+    pub friction_sensitivity_value: Option<f64>,
+    pub det_velocity_value: Option<f64>,
+    pub det_pressure_value: Option<f64>,
+    pub n_content_value: Option<f64>,
+    pub o_content_value: Option<f64>,
+    pub no_content_value: Option<f64>,
+    /// 无法识别为数值的原始内容
+    pub value_note: Option<String>,
+    /// 以下单位列为对应数值列的单位，即`Quantity::unit`，没有数值时为空
+    pub decomp_temp_unit: Option<String>,
+    pub density_unit: Option<String>,
+    pub diss_temp_unit: Option<String>,
+    pub formation_enthalpy_unit: Option<String>,
+    pub impact_sensitive_unit: Option<String>,
+    pub friction_sensitivity_unit: Option<String>,
+    pub det_velocity_unit: Option<String>,
+    pub det_pressure_unit: Option<String>,
+    pub n_content_unit: Option<String>,
+    pub o_content_unit: Option<String>,
+    pub no_content_unit: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -0,0 +1,393 @@
+use sea_orm::IdenStatic;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use crate::property;
+
+/// 可以按数值检索的属性。文本列保留用户输入的原始内容，数值列保存换算为`unit`后的数值
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Type)]
+pub enum Quantity {
+    DecompTemp,
+    Density,
+    DissTemp,
+    FormationEnthalpy,
+    ImpactSensitive,
+    FrictionSensitivity,
+    DetVelocity,
+    DetPressure,
+    NContent,
+    OContent,
+    NoContent,
+}
+
+impl Quantity {
+    pub const ALL: [Quantity; 11] = [
+        Quantity::DecompTemp,
+        Quantity::Density,
+        Quantity::DissTemp,
+        Quantity::FormationEnthalpy,
+        Quantity::ImpactSensitive,
+        Quantity::FrictionSensitivity,
+        Quantity::DetVelocity,
+        Quantity::DetPressure,
+        Quantity::NContent,
+        Quantity::OContent,
+        Quantity::NoContent,
+    ];
+
+    /// 按文本列的列名查找属性，例如`density`、`decomp_temp`
+    pub fn from_name(name: &str) -> Option<Quantity> {
+        Quantity::ALL
+            .into_iter()
+            .find(|quantity| quantity.text_column().as_str() == name)
+    }
+
+    /// 保存原始文本的列
+    pub fn text_column(self) -> property::Column {
+        match self {
+            Quantity::DecompTemp => property::Column::DecompTemp,
+            Quantity::Density => property::Column::Density,
+            Quantity::DissTemp => property::Column::DissTemp,
+            Quantity::FormationEnthalpy => property::Column::FormationEnthalpy,
+            Quantity::ImpactSensitive => property::Column::ImpactSensitive,
+            Quantity::FrictionSensitivity => property::Column::FrictionSensitivity,
+            Quantity::DetVelocity => property::Column::DetVelocity,
+            Quantity::DetPressure => property::Column::DetPressure,
+            Quantity::NContent => property::Column::NContent,
+            Quantity::OContent => property::Column::OContent,
+            Quantity::NoContent => property::Column::NoContent,
+        }
+    }
+
+    /// 保存数值的列
+    pub fn value_column(self) -> property::Column {
+        match self {
+            Quantity::DecompTemp => property::Column::DecompTempValue,
+            Quantity::Density => property::Column::DensityValue,
+            Quantity::DissTemp => property::Column::DissTempValue,
+            Quantity::FormationEnthalpy => property::Column::FormationEnthalpyValue,
+            Quantity::ImpactSensitive => property::Column::ImpactSensitiveValue,
+            Quantity::FrictionSensitivity => property::Column::FrictionSensitivityValue,
+            Quantity::DetVelocity => property::Column::DetVelocityValue,
+            Quantity::DetPressure => property::Column::DetPressureValue,
+            Quantity::NContent => property::Column::NContentValue,
+            Quantity::OContent => property::Column::OContentValue,
+            Quantity::NoContent => property::Column::NoContentValue,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Quantity::DecompTemp => "分解温度",
+            Quantity::Density => "密度",
+            Quantity::DissTemp => "热熔解温度",
+            Quantity::FormationEnthalpy => "生成焓",
+            Quantity::ImpactSensitive => "撞击感度",
+            Quantity::FrictionSensitivity => "摩擦感度",
+            Quantity::DetVelocity => "爆速",
+            Quantity::DetPressure => "爆压",
+            Quantity::NContent => "氮含量",
+            Quantity::OContent => "氧含量",
+            Quantity::NoContent => "氮氧含量",
+        }
+    }
+
+    /// 数值列使用的单位，与界面上标注的单位一致
+    pub fn unit(self) -> &'static str {
+        match self {
+            Quantity::DecompTemp | Quantity::DissTemp => "℃",
+            Quantity::Density => "g·cm-3",
+            Quantity::FormationEnthalpy => "kJ·mol-1",
+            Quantity::ImpactSensitive => "J",
+            Quantity::FrictionSensitivity => "N",
+            Quantity::DetVelocity => "m·s-1",
+            Quantity::DetPressure => "GPa",
+            Quantity::NContent | Quantity::OContent | Quantity::NoContent => "%",
+        }
+    }
+
+    /// 可以识别的单位写法（经过`normalize_unit`处理），以及换算到`unit`的系数和偏移量
+    fn units(self) -> &'static [(&'static str, f64, f64)] {
+        match self {
+            Quantity::DecompTemp | Quantity::DissTemp => &[
+                ("°c", 1.0, 0.0),
+                ("oc", 1.0, 0.0),
+                ("c", 1.0, 0.0),
+                ("k", 1.0, -273.15),
+            ],
+            Quantity::Density => &[
+                ("g.cm-3", 1.0, 0.0),
+                ("gcm-3", 1.0, 0.0),
+                ("g/cm3", 1.0, 0.0),
+                ("g/ml", 1.0, 0.0),
+                ("kg.m-3", 0.001, 0.0),
+                ("kgm-3", 0.001, 0.0),
+                ("kg/m3", 0.001, 0.0),
+            ],
+            Quantity::FormationEnthalpy => &[
+                ("kj.mol-1", 1.0, 0.0),
+                ("kjmol-1", 1.0, 0.0),
+                ("kj/mol", 1.0, 0.0),
+                ("kcal.mol-1", 4.184, 0.0),
+                ("kcalmol-1", 4.184, 0.0),
+                ("kcal/mol", 4.184, 0.0),
+                ("j/mol", 0.001, 0.0),
+            ],
+            Quantity::ImpactSensitive => &[("j", 1.0, 0.0), ("n.m", 1.0, 0.0), ("nm", 1.0, 0.0)],
+            Quantity::FrictionSensitivity => &[("n", 1.0, 0.0), ("kn", 1000.0, 0.0)],
+            Quantity::DetVelocity => &[
+                ("m.s-1", 1.0, 0.0),
+                ("ms-1", 1.0, 0.0),
+                ("m/s", 1.0, 0.0),
+                ("km.s-1", 1000.0, 0.0),
+                ("kms-1", 1000.0, 0.0),
+                ("km/s", 1000.0, 0.0),
+            ],
+            Quantity::DetPressure => &[("gpa", 1.0, 0.0), ("mpa", 0.001, 0.0), ("kbar", 0.1, 0.0)],
+            Quantity::NContent | Quantity::OContent | Quantity::NoContent => {
+                &[("%", 1.0, 0.0), ("wt%", 1.0, 0.0), ("wt.%", 1.0, 0.0)]
+            }
+        }
+    }
+
+    /// 文本是不带单位的数值时在其后写明`unit`，其他文本原样返回
+    pub fn with_unit(self, text: &str) -> String {
+        match split_number(text.trim()) {
+            Some((_, unit)) if normalize_unit(unit).is_empty() => {
+                format!("{} {}", text.trim(), self.unit())
+            }
+            _ => text.to_string(),
+        }
+    }
+
+    /// 将文本解析为数值，文本可以带有单位，例如`1.82 g/cm3`、`8,500 m/s`、`-120 kJ/mol`。
+    /// 没有单位时认为使用的是`unit`；范围、近似值和无法识别的单位返回`None`
+    pub fn parse(self, text: &str) -> Option<f64> {
+        let text = text.trim().replace(['−', '–'], "-");
+        let (number, unit) = split_number(&text)?;
+        let unit = normalize_unit(unit);
+        if unit.is_empty() {
+            return Some(number);
+        }
+        self.units()
+            .iter()
+            .find(|(alias, _, _)| *alias == unit)
+            .map(|(_, factor, offset)| number * factor + offset)
+    }
+}
+
+/// 逗号后恰好有三位数字时视为千位分隔符
+fn is_thousands_separator(bytes: &[u8], index: usize) -> bool {
+    index > 0
+        && bytes[index - 1].is_ascii_digit()
+        && bytes.len() >= index + 4
+        && bytes[index + 1..index + 4].iter().all(u8::is_ascii_digit)
+        && !bytes.get(index + 4).is_some_and(u8::is_ascii_digit)
+}
+
+/// 拆分开头的数值和其后的单位
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        end = 1;
+    }
+    let start = end;
+    while end < bytes.len()
+        && (bytes[end].is_ascii_digit()
+            || bytes[end] == b'.'
+            || (bytes[end] == b',' && is_thousands_separator(bytes, end)))
+    {
+        end += 1;
+    }
+    if !bytes[start..end].iter().any(u8::is_ascii_digit) {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let mut exponent = end + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            while bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                exponent += 1;
+            }
+            end = exponent;
+        }
+    }
+    let number = text[..end].replace(',', "").parse().ok()?;
+    Some((number, &text[end..]))
+}
+
+/// 统一单位的写法：小写，去掉空白，上标改为普通字符，各种乘号改为`.`
+fn normalize_unit(unit: &str) -> String {
+    unit.chars()
+        .filter(|c| !c.is_whitespace() && *c != '^')
+        .map(|c| match c {
+            '℃' => "°c".to_string(),
+            '·' | '⋅' | '*' | '×' => ".".to_string(),
+            '³' => "3".to_string(),
+            '¹' => "1".to_string(),
+            '⁻' => "-".to_string(),
+            c => c.to_lowercase().to_string(),
+        })
+        .collect()
+}
+
+/// 解析各属性的文本，返回每个属性的数值，以及无法识别为数值的原始内容汇总
+pub fn parse_texts<'a>(
+    texts: impl IntoIterator<Item = (Quantity, Option<&'a str>)>,
+) -> (Vec<(Quantity, Option<f64>)>, Option<String>) {
+    let mut values = vec![];
+    let mut unparsed = vec![];
+    for (quantity, text) in texts {
+        let text = text.map(str::trim).filter(|text| !text.is_empty());
+        let value = text.and_then(|text| quantity.parse(text));
+        if let (Some(text), None) = (text, value) {
+            unparsed.push(format!("{}：{}", quantity.label(), text));
+        }
+        values.push((quantity, value));
+    }
+    let note = (!unparsed.is_empty()).then(|| unparsed.join("；"));
+    (values, note)
+}
+
+impl property::Model {
+    pub fn text(&self, quantity: Quantity) -> Option<&str> {
+        match quantity {
+            Quantity::DecompTemp => self.decomp_temp.as_deref(),
+            Quantity::Density => self.density.as_deref(),
+            Quantity::DissTemp => self.diss_temp.as_deref(),
+            Quantity::FormationEnthalpy => self.formation_enthalpy.as_deref(),
+            Quantity::ImpactSensitive => self.impact_sensitive.as_deref(),
+            Quantity::FrictionSensitivity => self.friction_sensitivity.as_deref(),
+            Quantity::DetVelocity => self.det_velocity.as_deref(),
+            Quantity::DetPressure => self.det_pressure.as_deref(),
+            Quantity::NContent => self.n_content.as_deref(),
+            Quantity::OContent => self.o_content.as_deref(),
+            Quantity::NoContent => self.no_content.as_deref(),
+        }
+    }
+
+    pub fn text_mut(&mut self, quantity: Quantity) -> &mut Option<String> {
+        match quantity {
+            Quantity::DecompTemp => &mut self.decomp_temp,
+            Quantity::Density => &mut self.density,
+            Quantity::DissTemp => &mut self.diss_temp,
+            Quantity::FormationEnthalpy => &mut self.formation_enthalpy,
+            Quantity::ImpactSensitive => &mut self.impact_sensitive,
+            Quantity::FrictionSensitivity => &mut self.friction_sensitivity,
+            Quantity::DetVelocity => &mut self.det_velocity,
+            Quantity::DetPressure => &mut self.det_pressure,
+            Quantity::NContent => &mut self.n_content,
+            Quantity::OContent => &mut self.o_content,
+            Quantity::NoContent => &mut self.no_content,
+        }
+    }
+
+    pub fn value(&self, quantity: Quantity) -> Option<f64> {
+        match quantity {
+            Quantity::DecompTemp => self.decomp_temp_value,
+            Quantity::Density => self.density_value,
+            Quantity::DissTemp => self.diss_temp_value,
+            Quantity::FormationEnthalpy => self.formation_enthalpy_value,
+            Quantity::ImpactSensitive => self.impact_sensitive_value,
+            Quantity::FrictionSensitivity => self.friction_sensitivity_value,
+            Quantity::DetVelocity => self.det_velocity_value,
+            Quantity::DetPressure => self.det_pressure_value,
+            Quantity::NContent => self.n_content_value,
+            Quantity::OContent => self.o_content_value,
+            Quantity::NoContent => self.no_content_value,
+        }
+    }
+
+    fn value_mut(&mut self, quantity: Quantity) -> &mut Option<f64> {
+        match quantity {
+            Quantity::DecompTemp => &mut self.decomp_temp_value,
+            Quantity::Density => &mut self.density_value,
+            Quantity::DissTemp => &mut self.diss_temp_value,
+            Quantity::FormationEnthalpy => &mut self.formation_enthalpy_value,
+            Quantity::ImpactSensitive => &mut self.impact_sensitive_value,
+            Quantity::FrictionSensitivity => &mut self.friction_sensitivity_value,
+            Quantity::DetVelocity => &mut self.det_velocity_value,
+            Quantity::DetPressure => &mut self.det_pressure_value,
+            Quantity::NContent => &mut self.n_content_value,
+            Quantity::OContent => &mut self.o_content_value,
+            Quantity::NoContent => &mut self.no_content_value,
+        }
+    }
+
+    fn unit_mut(&mut self, quantity: Quantity) -> &mut Option<String> {
+        match quantity {
+            Quantity::DecompTemp => &mut self.decomp_temp_unit,
+            Quantity::Density => &mut self.density_unit,
+            Quantity::DissTemp => &mut self.diss_temp_unit,
+            Quantity::FormationEnthalpy => &mut self.formation_enthalpy_unit,
+            Quantity::ImpactSensitive => &mut self.impact_sensitive_unit,
+            Quantity::FrictionSensitivity => &mut self.friction_sensitivity_unit,
+            Quantity::DetVelocity => &mut self.det_velocity_unit,
+            Quantity::DetPressure => &mut self.det_pressure_unit,
+            Quantity::NContent => &mut self.n_content_unit,
+            Quantity::OContent => &mut self.o_content_unit,
+            Quantity::NoContent => &mut self.no_content_unit,
+        }
+    }
+
+    /// 根据文本列重新计算全部数值列、单位列和`value_note`，写入属性前调用
+    pub fn fill_values(&mut self) {
+        let (values, note) = parse_texts(
+            Quantity::ALL
+                .into_iter()
+                .map(|quantity| (quantity, self.text(quantity)))
+                .collect::<Vec<_>>(),
+        );
+        for (quantity, value) in values {
+            *self.value_mut(quantity) = value;
+            *self.unit_mut(quantity) = value.map(|_| quantity.unit().to_string());
+        }
+        self.value_note = note;
+    }
+}
+
+#[test]
+fn test_parse_quantity() {
+    assert_eq!(Quantity::Density.parse("1.82"), Some(1.82));
+    assert_eq!(Quantity::Density.parse(" 1.82 g/cm³ "), Some(1.82));
+    assert_eq!(Quantity::Density.parse("1820 kg·m-3"), Some(1.82));
+    assert_eq!(Quantity::DetVelocity.parse("8,500 m/s"), Some(8500.0));
+    assert_eq!(Quantity::DetVelocity.parse("8.5km/s"), Some(8500.0));
+    assert_eq!(
+        Quantity::FormationEnthalpy.parse("−120.5 kJ mol-1"),
+        Some(-120.5)
+    );
+    assert_eq!(Quantity::DecompTemp.parse("235℃"), Some(235.0));
+    assert_eq!(Quantity::DecompTemp.parse("500 K"), Some(500.0 - 273.15));
+    assert_eq!(Quantity::NContent.parse("3.5e1%"), Some(35.0));
+    assert_eq!(Quantity::DecompTemp.parse("235 (dec.)"), None);
+    assert_eq!(Quantity::DecompTemp.parse("230-235"), None);
+    assert_eq!(Quantity::Density.parse("约1.8"), None);
+    assert_eq!(Quantity::Density.parse("1.8 GPa"), None);
+    assert_eq!(Quantity::DetPressure.parse("1,5"), None);
+    for quantity in Quantity::ALL {
+        let text = quantity.with_unit("12.5");
+        assert_eq!(text, format!("12.5 {}", quantity.unit()));
+        assert_eq!(quantity.parse(&text), Some(12.5));
+    }
+    assert_eq!(Quantity::Density.with_unit("1820 kg/m3"), "1820 kg/m3");
+    assert_eq!(Quantity::Density.with_unit("约1.8"), "约1.8");
+
+    let (values, note) = parse_texts([
+        (Quantity::Density, Some("1.9")),
+        (Quantity::DetVelocity, Some("未测定")),
+        (Quantity::DetPressure, Some("  ")),
+    ]);
+    assert_eq!(
+        values,
+        vec![
+            (Quantity::Density, Some(1.9)),
+            (Quantity::DetVelocity, None),
+            (Quantity::DetPressure, None)
+        ]
+    );
+    assert_eq!(note, Some("爆速：未测定".to_string()));
+}
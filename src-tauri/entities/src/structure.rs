@@ -9,12 +9,12 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = true)]
     pub id: u32,
     #[sea_orm(unique)]
-    name: Option<String>,
-    formula: String,
+    pub name: Option<String>,
+    pub formula: String,
     #[sea_orm(unique)]
-    smiles: Option<String>,
+    pub smiles: Option<String>,
     #[sea_orm(not_null, default)]
-    charge: i8,
+    pub charge: i8,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
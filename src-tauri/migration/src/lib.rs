@@ -0,0 +1,29 @@
+pub use sea_orm_migration::{prelude::*, seaql_migrations};
+
+mod m20261018_000001_create_tables;
+mod m20261018_000002_property_values;
+mod m20261018_000003_structure_search_index;
+mod m20261018_000004_compositions;
+mod m20261018_000005_component_closure;
+mod m20261018_000006_molfiles;
+mod m20261018_000007_property_units;
+
+/// 数据库结构迁移，按顺序执行并记录在`seaql_migrations`表中。
+/// 修改`entities`中的模型时，需要在此追加新的迁移，不能修改已有的迁移。
+/// 迁移中用到的列名和解析规则也要在迁移中写明，不能引用`entities`，以免已有迁移的结果随程序版本变化
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![
+            Box::new(m20261018_000001_create_tables::Migration),
+            Box::new(m20261018_000002_property_values::Migration),
+            Box::new(m20261018_000003_structure_search_index::Migration),
+            Box::new(m20261018_000004_compositions::Migration),
+            Box::new(m20261018_000005_component_closure::Migration),
+            Box::new(m20261018_000006_molfiles::Migration),
+            Box::new(m20261018_000007_property_units::Migration),
+        ]
+    }
+}
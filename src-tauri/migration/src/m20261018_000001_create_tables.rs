@@ -0,0 +1,177 @@
+use sea_orm_migration::{prelude::*, sea_orm::sea_query::StringLen};
+
+/// 初始的四张表，与早期版本中`init_db`创建的表结构一致。
+/// 使用`if_not_exists`使得由早期版本创建的数据库可以直接纳入迁移管理
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Structures::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Structures::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Structures::Name).string().unique_key())
+                    .col(ColumnDef::new(Structures::Formula).string().not_null())
+                    .col(ColumnDef::new(Structures::Smiles).string().unique_key())
+                    .col(ColumnDef::new(Structures::Charge).tiny_integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(Components::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Components::StructureId).integer().not_null())
+                    .col(ColumnDef::new(Components::ComponentId).integer().not_null())
+                    .col(ColumnDef::new(Components::Count).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .name("pk-components")
+                            .col(Components::StructureId)
+                            .col(Components::ComponentId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Components::Table, Components::StructureId)
+                            .to(Structures::Table, Structures::Id),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Components::Table, Components::ComponentId)
+                            .to(Structures::Table, Structures::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        let mut property = Table::create();
+        property.table(Property::Table).if_not_exists().col(
+            ColumnDef::new(Property::StructureId)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        );
+        for column in [
+            Property::DecompTemp,
+            Property::Density,
+            Property::DissTemp,
+            Property::FormationEnthalpy,
+            Property::ImpactSensitive,
+            Property::FrictionSensitivity,
+            Property::DetVelocity,
+            Property::DetPressure,
+            Property::NContent,
+            Property::OContent,
+            Property::NoContent,
+            Property::References,
+            Property::Remarks,
+        ] {
+            property.col(ColumnDef::new(column).string());
+        }
+        property.foreign_key(
+            ForeignKey::create()
+                .from(Property::Table, Property::StructureId)
+                .to(Structures::Table, Structures::Id),
+        );
+        manager.create_table(property).await?;
+        manager
+            .create_table(
+                Table::create()
+                    .table(Images::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Images::StructureId)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Images::Filename).string().not_null())
+                    .col(
+                        ColumnDef::new_with_type(
+                            Images::Image,
+                            ColumnType::VarBinary(StringLen::None),
+                        )
+                        .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Images::Table, Images::StructureId)
+                            .to(Structures::Table, Structures::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for table in [
+            Images::Table.into_iden(),
+            Property::Table.into_iden(),
+            Components::Table.into_iden(),
+            Structures::Table.into_iden(),
+        ] {
+            manager
+                .drop_table(Table::drop().table(table).to_owned())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Structures {
+    Table,
+    Id,
+    Name,
+    Formula,
+    Smiles,
+    Charge,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Components {
+    Table,
+    StructureId,
+    ComponentId,
+    Count,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Property {
+    Table,
+    StructureId,
+    DecompTemp,
+    Density,
+    DissTemp,
+    FormationEnthalpy,
+    ImpactSensitive,
+    FrictionSensitivity,
+    DetVelocity,
+    DetPressure,
+    NContent,
+    OContent,
+    NoContent,
+    References,
+    Remarks,
+}
+
+#[derive(DeriveIden)]
+pub(crate) enum Images {
+    Table,
+    StructureId,
+    Filename,
+    Image,
+}
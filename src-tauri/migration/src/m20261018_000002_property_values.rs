@@ -0,0 +1,244 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::m20261018_000001_create_tables::Property;
+
+/// 换算到数值列单位的系数和偏移量，键为经过`normalize_unit`处理的单位写法
+type Units = &'static [(&'static str, f64, f64)];
+
+const TEMPERATURE: Units = &[
+    ("°c", 1.0, 0.0),
+    ("oc", 1.0, 0.0),
+    ("c", 1.0, 0.0),
+    ("k", 1.0, -273.15),
+];
+const CONTENT: Units = &[("%", 1.0, 0.0), ("wt%", 1.0, 0.0), ("wt.%", 1.0, 0.0)];
+
+/// 编写本迁移时可以按数值检索的属性：文本列、数值列、名称和可以识别的单位
+const QUANTITIES: [(&str, &str, &str, Units); 11] = [
+    ("decomp_temp", "decomp_temp_value", "分解温度", TEMPERATURE),
+    (
+        "density",
+        "density_value",
+        "密度",
+        &[
+            ("g.cm-3", 1.0, 0.0),
+            ("gcm-3", 1.0, 0.0),
+            ("g/cm3", 1.0, 0.0),
+            ("g/ml", 1.0, 0.0),
+            ("kg.m-3", 0.001, 0.0),
+            ("kgm-3", 0.001, 0.0),
+            ("kg/m3", 0.001, 0.0),
+        ],
+    ),
+    ("diss_temp", "diss_temp_value", "热熔解温度", TEMPERATURE),
+    (
+        "formation_enthalpy",
+        "formation_enthalpy_value",
+        "生成焓",
+        &[
+            ("kj.mol-1", 1.0, 0.0),
+            ("kjmol-1", 1.0, 0.0),
+            ("kj/mol", 1.0, 0.0),
+            ("kcal.mol-1", 4.184, 0.0),
+            ("kcalmol-1", 4.184, 0.0),
+            ("kcal/mol", 4.184, 0.0),
+            ("j/mol", 0.001, 0.0),
+        ],
+    ),
+    (
+        "impact_sensitive",
+        "impact_sensitive_value",
+        "撞击感度",
+        &[("j", 1.0, 0.0), ("n.m", 1.0, 0.0), ("nm", 1.0, 0.0)],
+    ),
+    (
+        "friction_sensitivity",
+        "friction_sensitivity_value",
+        "摩擦感度",
+        &[("n", 1.0, 0.0), ("kn", 1000.0, 0.0)],
+    ),
+    (
+        "det_velocity",
+        "det_velocity_value",
+        "爆速",
+        &[
+            ("m.s-1", 1.0, 0.0),
+            ("ms-1", 1.0, 0.0),
+            ("m/s", 1.0, 0.0),
+            ("km.s-1", 1000.0, 0.0),
+            ("kms-1", 1000.0, 0.0),
+            ("km/s", 1000.0, 0.0),
+        ],
+    ),
+    (
+        "det_pressure",
+        "det_pressure_value",
+        "爆压",
+        &[("gpa", 1.0, 0.0), ("mpa", 0.001, 0.0), ("kbar", 0.1, 0.0)],
+    ),
+    ("n_content", "n_content_value", "氮含量", CONTENT),
+    ("o_content", "o_content_value", "氧含量", CONTENT),
+    ("no_content", "no_content_value", "氮氧含量", CONTENT),
+];
+
+/// 为可以按数值检索的属性增加数值列，并解析已有的文本填入。
+/// 文本列保持不变，无法识别为数值的内容汇总在`value_note`中
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite每条ALTER TABLE语句只能增加一列
+        for (_, column, _, _) in QUANTITIES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Property::Table)
+                        .add_column(ColumnDef::new(Alias::new(column)).double())
+                        .to_owned(),
+                )
+                .await?;
+        }
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Property::Table)
+                    .add_column(ColumnDef::new(PropertyValues::ValueNote).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let rows = db
+            .query_all(
+                builder.build(
+                    Query::select()
+                        .column(Property::StructureId)
+                        .columns(QUANTITIES.map(|(column, _, _, _)| Alias::new(column)))
+                        .from(Property::Table),
+                ),
+            )
+            .await?;
+        for row in rows {
+            let structure_id: u32 = row.try_get("", "structure_id")?;
+            let mut update = Query::update();
+            update.table(Property::Table);
+            let mut unparsed = vec![];
+            for (text_column, value_column, label, units) in QUANTITIES {
+                let text: Option<String> = row.try_get("", text_column)?;
+                let text = text
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty());
+                let value = text.and_then(|text| parse(units, text));
+                if let (Some(text), None) = (text, value) {
+                    unparsed.push(format!("{}：{}", label, text));
+                }
+                update.value(Alias::new(value_column), value);
+            }
+            let note = (!unparsed.is_empty()).then(|| unparsed.join("；"));
+            update
+                .value(PropertyValues::ValueNote, note)
+                .and_where(Expr::col(Property::StructureId).eq(structure_id));
+            db.execute(builder.build(&update)).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let columns = QUANTITIES
+            .iter()
+            .map(|(_, column, _, _)| *column)
+            .chain(["value_note"]);
+        for column in columns {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Property::Table)
+                        .drop_column(Alias::new(column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum PropertyValues {
+    ValueNote,
+}
+
+/// 将文本解析为数值，没有单位时认为使用的是数值列的单位；范围、近似值和无法识别的单位返回`None`
+fn parse(units: Units, text: &str) -> Option<f64> {
+    let text = text.trim().replace(['−', '–'], "-");
+    let (number, unit) = split_number(&text)?;
+    let unit = normalize_unit(unit);
+    if unit.is_empty() {
+        return Some(number);
+    }
+    units
+        .iter()
+        .find(|(alias, _, _)| *alias == unit)
+        .map(|(_, factor, offset)| number * factor + offset)
+}
+
+/// 逗号后恰好有三位数字时视为千位分隔符
+fn is_thousands_separator(bytes: &[u8], index: usize) -> bool {
+    index > 0
+        && bytes[index - 1].is_ascii_digit()
+        && bytes.len() >= index + 4
+        && bytes[index + 1..index + 4].iter().all(u8::is_ascii_digit)
+        && !bytes.get(index + 4).is_some_and(u8::is_ascii_digit)
+}
+
+/// 拆分开头的数值和其后的单位
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let bytes = text.as_bytes();
+    let mut end = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        end = 1;
+    }
+    let start = end;
+    while end < bytes.len()
+        && (bytes[end].is_ascii_digit()
+            || bytes[end] == b'.'
+            || (bytes[end] == b',' && is_thousands_separator(bytes, end)))
+    {
+        end += 1;
+    }
+    if !bytes[start..end].iter().any(u8::is_ascii_digit) {
+        return None;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let mut exponent = end + 1;
+        if matches!(bytes.get(exponent), Some(b'+' | b'-')) {
+            exponent += 1;
+        }
+        if bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+            while bytes.get(exponent).is_some_and(u8::is_ascii_digit) {
+                exponent += 1;
+            }
+            end = exponent;
+        }
+    }
+    let number = text[..end].replace(',', "").parse().ok()?;
+    Some((number, &text[end..]))
+}
+
+/// 统一单位的写法：小写，去掉空白，上标改为普通字符，各种乘号改为`.`
+fn normalize_unit(unit: &str) -> String {
+    unit.chars()
+        .filter(|c| !c.is_whitespace() && *c != '^')
+        .map(|c| match c {
+            '℃' => "°c".to_string(),
+            '·' | '⋅' | '*' | '×' => ".".to_string(),
+            '³' => "3".to_string(),
+            '¹' => "1".to_string(),
+            '⁻' => "-".to_string(),
+            c => c.to_lowercase().to_string(),
+        })
+        .collect()
+}
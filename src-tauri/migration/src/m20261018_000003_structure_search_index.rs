@@ -0,0 +1,71 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+/// 为名称、分子式、SMILES、备注和参考文献建立FTS5全文索引。
+/// 使用trigram分词器，中文和SMILES也可以按任意不少于三个字符的片段检索；
+/// 索引通过触发器与结构表和属性表保持同步，导入数据时无需额外处理
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// 重新写入某个结构的索引行，`id`为结构ID的SQL表达式
+fn reindex(id: &str) -> String {
+    format!(
+        r#"DELETE FROM structures_fts WHERE rowid = {id};
+        INSERT INTO structures_fts (rowid, name, formula, smiles, remarks, "references")
+        SELECT s.id, s.name, s.formula, s.smiles, p.remarks, p."references"
+        FROM structures s LEFT JOIN property p ON p.structure_id = s.id
+        WHERE s.id = {id};"#
+    )
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"CREATE VIRTUAL TABLE IF NOT EXISTS structures_fts
+            USING fts5(name, formula, smiles, remarks, "references", tokenize = 'trigram')"#,
+        )
+        .await?;
+        db.execute_unprepared(&format!(
+            r#"CREATE TRIGGER IF NOT EXISTS structures_fts_insert AFTER INSERT ON structures BEGIN {} END;
+            CREATE TRIGGER IF NOT EXISTS structures_fts_update AFTER UPDATE ON structures BEGIN
+                DELETE FROM structures_fts WHERE rowid = old.id; {} END;
+            CREATE TRIGGER IF NOT EXISTS structures_fts_delete AFTER DELETE ON structures BEGIN
+                DELETE FROM structures_fts WHERE rowid = old.id; END;
+            CREATE TRIGGER IF NOT EXISTS property_fts_insert AFTER INSERT ON property BEGIN {} END;
+            CREATE TRIGGER IF NOT EXISTS property_fts_update AFTER UPDATE ON property BEGIN
+                DELETE FROM structures_fts WHERE rowid = old.structure_id; {} END;
+            CREATE TRIGGER IF NOT EXISTS property_fts_delete AFTER DELETE ON property BEGIN {} END;"#,
+            reindex("new.id"),
+            reindex("new.id"),
+            reindex("new.structure_id"),
+            reindex("new.structure_id"),
+            reindex("old.structure_id"),
+        ))
+        .await?;
+        db.execute_unprepared(
+            r#"DELETE FROM structures_fts;
+            INSERT INTO structures_fts (rowid, name, formula, smiles, remarks, "references")
+            SELECT s.id, s.name, s.formula, s.smiles, p.remarks, p."references"
+            FROM structures s LEFT JOIN property p ON p.structure_id = s.id;"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "DROP TRIGGER IF EXISTS structures_fts_insert;
+                DROP TRIGGER IF EXISTS structures_fts_update;
+                DROP TRIGGER IF EXISTS structures_fts_delete;
+                DROP TRIGGER IF EXISTS property_fts_insert;
+                DROP TRIGGER IF EXISTS property_fts_update;
+                DROP TRIGGER IF EXISTS property_fts_delete;
+                DROP TABLE IF EXISTS structures_fts;",
+            )
+            .await?;
+        Ok(())
+    }
+}
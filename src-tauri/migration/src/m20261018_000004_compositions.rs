@@ -0,0 +1,211 @@
+use std::collections::BTreeMap;
+
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::m20261018_000001_create_tables::Structures;
+
+/// 编写本迁移时`entities::formula`可以识别的元素符号，按原子序数排列
+const ELEMENTS: [&str; 118] = [
+    "H", "He", "Li", "Be", "B", "C", "N", "O", "F", "Ne", "Na", "Mg", "Al", "Si", "P", "S", "Cl",
+    "Ar", "K", "Ca", "Sc", "Ti", "V", "Cr", "Mn", "Fe", "Co", "Ni", "Cu", "Zn", "Ga", "Ge", "As",
+    "Se", "Br", "Kr", "Rb", "Sr", "Y", "Zr", "Nb", "Mo", "Tc", "Ru", "Rh", "Pd", "Ag", "Cd", "In",
+    "Sn", "Sb", "Te", "I", "Xe", "Cs", "Ba", "La", "Ce", "Pr", "Nd", "Pm", "Sm", "Eu", "Gd", "Tb",
+    "Dy", "Ho", "Er", "Tm", "Yb", "Lu", "Hf", "Ta", "W", "Re", "Os", "Ir", "Pt", "Au", "Hg", "Tl",
+    "Pb", "Bi", "Po", "At", "Rn", "Fr", "Ra", "Ac", "Th", "Pa", "U", "Np", "Pu", "Am", "Cm", "Bk",
+    "Cf", "Es", "Fm", "Md", "No", "Lr", "Rf", "Db", "Sg", "Bh", "Hs", "Mt", "Ds", "Rg", "Cn", "Nh",
+    "Fl", "Mc", "Lv", "Ts", "Og",
+];
+
+/// 增加元素组成表，并解析已有结构的分子式填入。无法解析的分子式不产生任何行
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Compositions::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Compositions::StructureId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Compositions::Element).string().not_null())
+                    .col(ColumnDef::new(Compositions::Count).integer().not_null())
+                    .primary_key(
+                        Index::create()
+                            .name("pk-compositions")
+                            .col(Compositions::StructureId)
+                            .col(Compositions::Element),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Compositions::Table, Compositions::StructureId)
+                            .to(Structures::Table, Structures::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx-compositions-element")
+                    .if_not_exists()
+                    .table(Compositions::Table)
+                    .col(Compositions::Element)
+                    .col(Compositions::Count)
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        let rows = db
+            .query_all(
+                builder.build(
+                    Query::select()
+                        .columns([Structures::Id, Structures::Formula, Structures::Charge])
+                        .from(Structures::Table),
+                ),
+            )
+            .await?;
+        for row in rows {
+            let id: u32 = row.try_get("", "id")?;
+            let formula: String = row.try_get("", "formula")?;
+            let charge: i8 = row.try_get("", "charge")?;
+            let Some(counts) = parse(&formula, charge) else {
+                continue;
+            };
+            let mut insert = Query::insert();
+            insert.into_table(Compositions::Table).columns([
+                Compositions::StructureId,
+                Compositions::Element,
+                Compositions::Count,
+            ]);
+            for (element, count) in counts {
+                insert.values_panic([id.into(), element.into(), count.into()]);
+            }
+            db.execute(builder.build(&insert)).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Compositions::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Compositions {
+    Table,
+    StructureId,
+    Element,
+    Count,
+}
+
+/// 去掉分子式末尾的电荷标记，例如`CO3(2-)`、`SO4^2-`、`NH4+`、`Ca2+`。
+/// 末尾没有括号或`^`分隔时，符号前的数字只有与`charge`的绝对值一致时才视为电荷，
+/// 因此`NO3-`中的3仍是原子个数
+fn strip_charge(formula: &str, charge: i8) -> &str {
+    let formula = formula.trim_end_matches(|c: char| "⁺⁻⁰¹²³⁴⁵⁶⁷⁸⁹".contains(c));
+    if let Some((rest, _)) = formula.rsplit_once('^') {
+        return rest;
+    }
+    let is_charge = |text: &str| {
+        let digits = text.trim_end_matches(['+', '-']);
+        text.len() > digits.len() && digits.chars().all(|c| c.is_ascii_digit())
+    };
+    for (open, close) in [('(', ')'), ('[', ']')] {
+        if let Some(inner) = formula.strip_suffix(close) {
+            if let Some((rest, inner)) = inner.rsplit_once(open) {
+                if is_charge(inner) {
+                    return rest;
+                }
+            }
+        }
+    }
+    let rest = formula.trim_end_matches(['+', '-']);
+    if rest.len() == formula.len() {
+        return formula;
+    }
+    let digits = rest.len() - rest.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    match rest[rest.len() - digits..].parse::<u32>() {
+        Ok(count) if count == charge.unsigned_abs() as u32 && count > 1 => {
+            &rest[..rest.len() - digits]
+        }
+        _ => rest,
+    }
+}
+
+/// 将分子式解析为各元素的原子个数，支持括号、结晶水（`CuSO4·5H2O`）和末尾的电荷标记。
+/// 分子式中含有无法识别的内容（如代号`RDX`）时返回`None`
+fn parse(formula: &str, charge: i8) -> Option<BTreeMap<&'static str, u32>> {
+    let formula = strip_charge(formula.trim(), charge);
+    let mut total = BTreeMap::new();
+    for part in formula.split(['·', '•', '.', '*']) {
+        let chars = part.trim().chars().collect::<Vec<_>>();
+        let mut i = 0;
+        let number = |i: &mut usize| {
+            let start = *i;
+            while *i < chars.len() && chars[*i].is_ascii_digit() {
+                *i += 1;
+            }
+            match *i > start {
+                true => chars[start..*i]
+                    .iter()
+                    .collect::<String>()
+                    .parse::<u32>()
+                    .ok(),
+                false => Some(1),
+            }
+        };
+        let coefficient = number(&mut i)?;
+        // 每层括号对应一个计数表，遇到右括号时乘以其后的个数并合并到上一层
+        let mut stack = vec![BTreeMap::<&'static str, u32>::new()];
+        while i < chars.len() {
+            match chars[i] {
+                '(' | '[' | '{' => {
+                    stack.push(BTreeMap::new());
+                    i += 1;
+                }
+                ')' | ']' | '}' => {
+                    i += 1;
+                    let group = stack.pop()?;
+                    let count = number(&mut i)?;
+                    let outer = stack.last_mut()?;
+                    for (element, n) in group {
+                        *outer.entry(element).or_default() += n.saturating_mul(count);
+                    }
+                }
+                c if c.is_ascii_uppercase() => {
+                    let start = i;
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_lowercase() {
+                        i += 1;
+                    }
+                    let symbol = chars[start..i].iter().collect::<String>();
+                    let element = ELEMENTS.into_iter().find(|element| *element == symbol)?;
+                    let count = number(&mut i)?;
+                    *stack.last_mut()?.entry(element).or_default() += count;
+                }
+                _ => return None,
+            }
+        }
+        if stack.len() != 1 {
+            return None;
+        }
+        for (element, n) in stack.pop()? {
+            *total.entry(element).or_default() += n.saturating_mul(coefficient);
+        }
+    }
+    match total.is_empty() {
+        true => None,
+        false => Some(total),
+    }
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+/// 组成关系的传递闭包视图，每行表示`structure_id`直接或间接含有`component_id`。
+/// 使用`UNION`去重，即使组成关系中存在环也能终止
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE VIEW IF NOT EXISTS component_closure (structure_id, component_id) AS
+                WITH RECURSIVE closure (structure_id, component_id) AS (
+                    SELECT structure_id, component_id FROM components
+                    UNION
+                    SELECT closure.structure_id, components.component_id
+                    FROM closure JOIN components ON components.structure_id = closure.component_id
+                )
+                SELECT structure_id, component_id FROM closure",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP VIEW IF EXISTS component_closure")
+            .await?;
+        Ok(())
+    }
+}
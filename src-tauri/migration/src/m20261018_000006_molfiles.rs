@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20261018_000001_create_tables::Structures;
+
+/// 增加结构式表，保存从SD文件导入的MOL块
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Molfiles::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Molfiles::StructureId)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Molfiles::Molblock).text().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Molfiles::Table, Molfiles::StructureId)
+                            .to(Structures::Table, Structures::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Molfiles::Table).to_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Molfiles {
+    Table,
+    StructureId,
+    Molblock,
+}
@@ -0,0 +1,69 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+use crate::m20261018_000001_create_tables::Property;
+
+/// 本迁移执行时各数值列的单位列和单位
+const UNITS: [(&str, &str, &str); 11] = [
+    ("decomp_temp_value", "decomp_temp_unit", "℃"),
+    ("density_value", "density_unit", "g·cm-3"),
+    ("diss_temp_value", "diss_temp_unit", "℃"),
+    (
+        "formation_enthalpy_value",
+        "formation_enthalpy_unit",
+        "kJ·mol-1",
+    ),
+    ("impact_sensitive_value", "impact_sensitive_unit", "J"),
+    (
+        "friction_sensitivity_value",
+        "friction_sensitivity_unit",
+        "N",
+    ),
+    ("det_velocity_value", "det_velocity_unit", "m·s-1"),
+    ("det_pressure_value", "det_pressure_unit", "GPa"),
+    ("n_content_value", "n_content_unit", "%"),
+    ("o_content_value", "o_content_unit", "%"),
+    ("no_content_value", "no_content_unit", "%"),
+];
+
+/// 为每个数值列增加单位列，已有数值的记录填入数值列的单位
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = db.get_database_backend();
+        for (value_column, unit_column, unit) in UNITS {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Property::Table)
+                        .add_column(ColumnDef::new(Alias::new(unit_column)).string())
+                        .to_owned(),
+                )
+                .await?;
+            let update = Query::update()
+                .table(Property::Table)
+                .value(Alias::new(unit_column), unit)
+                .and_where(Expr::col(Alias::new(value_column)).is_not_null())
+                .to_owned();
+            db.execute(builder.build(&update)).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for (_, unit_column, _) in UNITS {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Property::Table)
+                        .drop_column(Alias::new(unit_column))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
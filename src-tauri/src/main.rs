@@ -1,38 +1,124 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::{
-    fs::{self, File},
-    io::{Read, Write},
-    path::PathBuf,
-};
+use std::path::{Path, PathBuf};
 
-use sea_orm::{
-    prelude::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, Database, DatabaseConnection, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, Schema, TransactionTrait
+use chembank_core::{
+    ArchiveReport, Chembank, ChembankError, DatabaseStatus, ExportOptions, ImportOptions,
+    ImportReport, Job, PropertyRange, RecentDatabases, Recovery, SchemaReport, SdfOptions,
+    SearchPage, Settings, SortKey, StructureDetail, XlsxOptions,
+};
+use tauri::{
+    api::path::{app_config_dir, app_data_dir},
+    AppHandle, Config, Manager, State,
 };
-use skip_bom::{BomType, SkipEncodingBom};
-use tauri::State;
 use tokio::sync::Mutex;
 
 use entities::*;
 
 struct AppState {
-    db: Mutex<Option<DatabaseConnection>>,
+    db: Mutex<Option<Chembank>>,
+    /// 最近一次无法打开的数据库路径及原因，成功打开其他数据库后清除
+    failure: Mutex<Option<(Option<PathBuf>, ChembankError)>>,
+    /// 正在运行的导入导出任务，用于取消
+    job: Mutex<Option<Job>>,
+}
+
+/// 导入导出进度事件的名称
+const PROGRESS_EVENT: &str = "transfer-progress";
+
+/// 创建向前端发送进度事件的任务，并记录下来以便取消
+async fn start_job(app: &AppHandle, state: &AppState) -> Job {
+    let app = app.clone();
+    let job = Job::new(move |progress| {
+        let _ = app.emit_all(PROGRESS_EVENT, progress);
+    });
+    *state.job.lock().await = Some(job.clone());
+    job
+}
+
+fn connected(db: &Option<Chembank>) -> Result<&Chembank, ChembankError> {
+    db.as_ref().ok_or(ChembankError::DbUnavailable {
+        message: "没有打开的数据库".to_string(),
+    })
+}
+
+fn recent_file(app: &AppHandle) -> Result<PathBuf, ChembankError> {
+    app.path_resolver()
+        .app_config_dir()
+        .map(|dir| dir.join("recent.json"))
+        .ok_or(ChembankError::Io {
+            path: None,
+            message: "无法确定配置文件目录".to_string(),
+        })
+}
+
+/// 用新打开的数据库替换当前数据库，并记录到最近打开的列表中
+async fn switch_database(
+    app: &AppHandle,
+    state: &AppState,
+    new_db: Chembank,
+) -> Result<(), ChembankError> {
+    let recent_file = recent_file(app)?;
+    let mut recent = RecentDatabases::load(&recent_file);
+    if let Some(path) = new_db.path() {
+        recent.push(path);
+    }
+    let mut db = state.db.lock().await;
+    if let Some(db) = db.replace(new_db) {
+        db.close().await?;
+    };
+    *state.failure.lock().await = None;
+    recent.save(&recent_file)
+}
+
+/// 按环境变量、配置文件和数据目录的顺序确定并打开默认数据库，失败时返回数据库路径（如果已经确定）和原因
+async fn open_default_database(
+    config: &Config,
+) -> Result<Chembank, (Option<PathBuf>, ChembankError)> {
+    let no_directory = |message: &str| {
+        (
+            None,
+            ChembankError::Io {
+                path: None,
+                message: message.to_string(),
+            },
+        )
+    };
+    let config_dir = app_config_dir(config).ok_or_else(|| no_directory("无法确定配置文件目录"))?;
+    let data_dir = app_data_dir(config).ok_or_else(|| no_directory("无法确定数据目录"))?;
+    let database = Settings::load(&config_dir.join("settings.json"))
+        .and_then(|settings| settings.default_database(&data_dir))
+        .map_err(|e| (None, e))?;
+    Chembank::open_or_create(&database)
+        .await
+        .map_err(|e| (Some(database), e))
 }
 
 #[tokio::main]
 async fn main() {
-    let db = Database::connect("sqlite:chembank.db?mode=rwc")
-        .await
-        .unwrap();
-    let _ = init_db(&db).await;
+    let context = tauri::generate_context!();
+    let (db, failure) = match open_default_database(context.config()).await {
+        Ok(db) => (Some(db), None),
+        Err(failure) => (None, Some(failure)),
+    };
     tauri::Builder::default()
         .manage(AppState {
-            db: Mutex::new(Some(db)),
+            db: Mutex::new(db),
+            failure: Mutex::new(failure),
+            job: Mutex::new(None),
         })
         .invoke_handler(tauri::generate_handler![
+            database_status,
+            recover_database,
+            open_database,
+            create_database,
+            close_database,
+            current_database,
+            recent_databases,
             structure_count,
             reset_database,
+            schema_report,
             create_structure,
             update_structure,
             remove_structure,
@@ -44,46 +130,135 @@ async fn main() {
             get_structure_detail,
             export_to_folder,
             import_from_folder,
+            check_import,
+            cancel_transfer,
+            import_sdf,
+            export_sdf,
+            import_xlsx,
+            export_xlsx,
+            import_archive,
+            check_archive,
+            export_archive,
         ])
-        .run(tauri::generate_context!())
+        .run(context)
         .expect("error while running tauri application");
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn reset_database(state: State<'_, AppState>) -> Result<(), String> {
+async fn database_status(state: State<'_, AppState>) -> Result<DatabaseStatus, ChembankError> {
+    let db = state.db.lock().await;
+    if let Some(db) = db.as_ref() {
+        return Ok(DatabaseStatus::Open {
+            path: db.path().map(Path::to_path_buf),
+        });
+    }
+    Ok(match state.failure.lock().await.clone() {
+        Some((path, error)) => DatabaseStatus::Failed { path, error },
+        None => DatabaseStatus::Closed,
+    })
+}
+
+/// 对启动时无法打开的数据库执行恢复操作，原文件会被重命名保留
+#[tauri::command]
+#[specta::specta]
+async fn recover_database(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    recovery: Recovery,
+) -> Result<(), ChembankError> {
+    let path = state
+        .failure
+        .lock()
+        .await
+        .as_ref()
+        .and_then(|(path, _)| path.clone())
+        .ok_or(ChembankError::DbUnavailable {
+            message: "没有需要恢复的数据库文件".to_string(),
+        })?;
+    let new_db = match recovery {
+        Recovery::RestoreBackup { backup } => Chembank::restore(&path, &backup).await?,
+        Recovery::CreateNew => Chembank::recreate(&path).await?,
+    };
+    switch_database(&app, &state, new_db).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn open_database(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+) -> Result<(), ChembankError> {
+    match Chembank::open(&path).await {
+        Ok(new_db) => switch_database(&app, &state, new_db).await,
+        Err(e) => {
+            // 无法打开的文件不再保留在最近打开的列表中
+            let recent_file = recent_file(&app)?;
+            let mut recent = RecentDatabases::load(&recent_file);
+            recent.remove(&path);
+            recent.save(&recent_file)?;
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn create_database(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+) -> Result<(), ChembankError> {
+    let new_db = Chembank::create(&path).await?;
+    switch_database(&app, &state, new_db).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn close_database(state: State<'_, AppState>) -> Result<(), ChembankError> {
     let mut db = state.db.lock().await;
     if let Some(db) = db.take() {
-        db.close().await.map_err(|e| format!("无法关闭当前数据库，请确认磁盘空间充足，或强制重启程序，但可能导致最近的部分操作丢失, 详细信息：{:#?}", e))?;
+        db.close().await?;
     };
-    tokio::fs::remove_file("chembank.db")
-        .await
-        .map_err(|e| format!("无法删除旧的数据库，原因如下：\n{:#?}", e))?;
-    let new_db = Database::connect("sqlite:chembank.db?mode=rwc")
-        .await
-        .map_err(|e| {
-            format!(
-                "无法创建目标数据库，这可能是由于权限问题或文件损坏导致的，详细信息：{:#?}",
-                e
-            )
-        })?;
-    init_db(&new_db).await?;
-    *db = Some(new_db);
     Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn structure_count(state: State<'_, AppState>) -> Result<u32, String> {
+async fn current_database(state: State<'_, AppState>) -> Result<Option<PathBuf>, ChembankError> {
     let db = state.db.lock().await;
-    let db = db
-        .as_ref()
-        .ok_or("无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员".to_string())?;
-    structure::Entity::find()
-        .count(db)
-        .await
-        .map(|value| value as u32)
-        .map_err(|e| format!("数据库故障，原因：{:#?}", e))
+    Ok(db.as_ref().and_then(|db| db.path()).map(Path::to_path_buf))
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn recent_databases(app: AppHandle) -> Result<Vec<PathBuf>, ChembankError> {
+    Ok(RecentDatabases::load(&recent_file(&app)?).paths)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn reset_database(state: State<'_, AppState>) -> Result<(), ChembankError> {
+    let mut db = state.db.lock().await;
+    match db.as_mut() {
+        Some(db) => db.reset().await,
+        None => connected(&db).map(|_| ()),
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn schema_report(state: State<'_, AppState>) -> Result<SchemaReport, ChembankError> {
+    let db = state.db.lock().await;
+    connected(&db)?.schema_report().await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn structure_count(state: State<'_, AppState>) -> Result<u32, ChembankError> {
+    let db = state.db.lock().await;
+    connected(&db)?.structure_count().await
 }
 
 #[tauri::command]
@@ -94,25 +269,11 @@ async fn create_structure(
     formula: String,
     smiles: Option<String>,
     charge: i8,
-) -> Result<u32, String> {
+) -> Result<u32, ChembankError> {
     let db = state.db.lock().await;
-    let db = db
-        .as_ref()
-        .ok_or("无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员".to_string())?;
-    let model = structure::ActiveModel {
-        id: ActiveValue::not_set(),
-        name: ActiveValue::set(name),
-        formula: ActiveValue::set(formula),
-        smiles: ActiveValue::set(smiles),
-        charge: ActiveValue::set(charge),
-    };
-    let model = model.insert(db).await.map_err(|e| {
-        format!(
-            "无法添加结构，请检查是否有重复的名称或SMILES，详细信息\n{:#?}",
-            e
-        )
-    })?;
-    Ok(model.id)
+    connected(&db)?
+        .create_structure(name, formula, smiles, charge)
+        .await
 }
 
 #[tauri::command]
@@ -124,84 +285,18 @@ async fn update_structure(
     formula: String,
     smiles: Option<String>,
     charge: i8,
-) -> Result<(), String> {
+) -> Result<(), ChembankError> {
     let db = state.db.lock().await;
-    let db = db
-        .as_ref()
-        .ok_or("无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员".to_string())?;
-
-    let model = structure::Entity::find_by_id(id)
-        .one(db)
+    connected(&db)?
+        .update_structure(id, name, formula, smiles, charge)
         .await
-        .map_err(|e| format!("数据库故障 ，详细信息\n{:#?}", e))?
-        .ok_or("没有找到对应的结构记录，可能已经删除或未添加")?;
-    let mut model: structure::ActiveModel = model.into();
-    model.name = ActiveValue::set(name);
-    model.formula = ActiveValue::set(formula);
-    model.smiles = ActiveValue::set(smiles);
-    model.charge = ActiveValue::set(charge);
-    model.update(db).await.map_err(|e| {
-        format!(
-            "无法更新结构，请检查是否有重复的名称或SMILES，详细信息\n{:#?}",
-            e
-        )
-    })?;
-    Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn remove_structure(state: State<'_, AppState>, id: u32) -> Result<(), String> {
+async fn remove_structure(state: State<'_, AppState>, id: u32) -> Result<(), ChembankError> {
     let db = state.db.lock().await;
-    let db = db
-        .as_ref()
-        .ok_or("无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员".to_string())?;
-    let txn = db.begin().await.map_err(|e| {
-        format!(
-            "无法开始事务，可能是由于数据库损坏或权限问题，详细信息\n{:#?}",
-            e
-        )
-    })?;
-    let component_of = component::Entity::find()
-        .filter(component::Column::ComponentId.eq(id))
-        .count(&txn)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    if component_of > 0 {
-        Err(format!(
-            "该结构仍被作为其他结构的组成部分存在，请检查删除相应结构后再删除此结构"
-        ))?;
-    };
-    component::Entity::delete_many()
-        .filter(component::Column::StructureId.eq(id))
-        .exec(&txn)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    image::Entity::delete_many()
-        .filter(image::Column::StructureId.eq(id))
-        .exec(&txn)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    property::Entity::delete_many()
-        .filter(property::Column::StructureId.eq(id))
-        .exec(&txn)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    structure::Entity::find_by_id(id)
-        .one(&txn)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?
-        .ok_or("未找到对应结构，可能已经删除或未添加".to_string())?
-        .delete(&txn)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    txn.commit().await.map_err(|e| {
-        format!(
-            "无法提交事务，可能是由于数据库损坏或权限问题，详细信息\n{:#?}",
-            e
-        )
-    })?;
-    Ok(())
+    connected(&db)?.remove_structure(id).await
 }
 
 #[tauri::command]
@@ -211,33 +306,11 @@ async fn set_component(
     structure_id: u32,
     component_id: u32,
     count: u32,
-) -> Result<(), String> {
+) -> Result<(), ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let model = component::ActiveModel {
-        structure_id: ActiveValue::set(structure_id),
-        component_id: ActiveValue::set(component_id),
-        count: ActiveValue::Set(count),
-    };
-    if component::Entity::find_by_id((structure_id, component_id))
-        .one(db)
+    connected(&db)?
+        .set_component(structure_id, component_id, count)
         .await
-        .map_err(|e| format!("数据库错误，详细信息：\n{:#?}", e))?
-        .is_some()
-    {
-        model
-            .update(db)
-            .await
-            .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    } else {
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    }
-    Ok(())
 }
 
 #[tauri::command]
@@ -246,21 +319,11 @@ async fn delete_component(
     state: State<'_, AppState>,
     structure_id: u32,
     component_id: u32,
-) -> Result<(), String> {
+) -> Result<(), ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let model = component::Entity::find_by_id((structure_id, component_id))
-        .one(db)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?
-        .ok_or("未找到对应组件信息，可能尚未创建？".to_string())?;
-    model
-        .delete(db)
+    connected(&db)?
+        .delete_component(structure_id, component_id)
         .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    Ok(())
 }
 
 #[tauri::command]
@@ -270,63 +333,18 @@ async fn set_image(
     structure_id: u32,
     image: Vec<u8>,
     filename: String,
-) -> Result<(), String> {
+) -> Result<(), ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let model = image::ActiveModel {
-        structure_id: ActiveValue::set(structure_id),
-        image: ActiveValue::set(image),
-        filename: ActiveValue::set(filename),
-    };
-    if image::Entity::find_by_id(structure_id)
-        .one(db)
+    connected(&db)?
+        .set_image(structure_id, image, filename)
         .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?
-        .is_some()
-    {
-        model
-            .update(db)
-            .await
-            .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    } else {
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    };
-
-    Ok(())
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn set_property(state: State<'_, AppState>, model: property::Model) -> Result<(), String> {
+async fn set_property(state: State<'_, AppState>, model: property::Model) -> Result<(), ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let structure_id = model.structure_id;
-    let model: property::ActiveModel = model.into();
-    let model = model.reset_all();
-    if property::Entity::find_by_id(structure_id)
-        .one(db)
-        .await
-        .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?
-        .is_some()
-    {
-        model
-            .update(db)
-            .await
-            .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    } else {
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("数据库故障，详细信息\n{:#?}", e))?;
-    }
-    Ok(())
+    connected(&db)?.set_property(model).await
 }
 
 #[tauri::command]
@@ -334,272 +352,187 @@ async fn set_property(state: State<'_, AppState>, model: property::Model) -> Res
 async fn get_structure_detail(
     state: State<'_, AppState>,
     id: u32,
-) -> Result<
-    (
-        structure::Model,
-        Option<property::Model>,
-        Option<image::Model>,
-        Vec<(component::Model, Option<structure::Model>)>,
-        Vec<(component::Model, Option<structure::Model>)>,
-    ),
-    String,
-> {
+) -> Result<StructureDetail, ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let model = structure::Entity::find_by_id(id)
-        .one(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?
-        .ok_or("没有找到对应记录")?;
-    let property_model = model
-        .find_related(property::Entity)
-        .one(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?;
-    let image_model = model
-        .find_related(image::Entity)
-        .one(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?;
-    let components = component::Entity::find()
-        .filter(component::Column::StructureId.eq(model.id))
-        .find_also_linked(links::ComponentStructure)
-        .all(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))
-        .unwrap();
-    let relateds = component::Entity::find()
-        .filter(component::Column::ComponentId.eq(model.id))
-        .find_also_linked(links::StructureComponent)
-        .all(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?;
-    Ok((model, property_model, image_model, components, relateds))
+    connected(&db)?.get_structure_detail(id).await
 }
 
 #[tauri::command]
 #[specta::specta]
+#[allow(clippy::too_many_arguments)]
 async fn search_structure(
     state: State<'_, AppState>,
-    page_size: u32,
-    page_number: u32,
     keyword: Option<String>,
     max_charge: i8,
     min_charge: i8,
-) -> Result<(Vec<structure::Model>, u32), String> {
+    ranges: Vec<PropertyRange>,
+    query: Option<String>,
+    sort: Vec<SortKey>,
+    cursor: Option<String>,
+    limit: u32,
+) -> Result<SearchPage, ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let mut models = structure::Entity::find().left_join(property::Entity);
-    if let Some(keyword) = keyword {
-        let keyword = format!("%{}%", keyword);
-        models = models.filter(
-            Expr::col((structure::Entity, structure::Column::Name))
-            .like(&keyword)
-            .or(Expr::col((structure::Entity, structure::Column::Formula)).like(&keyword))
-            .or(Expr::col((structure::Entity, structure::Column::Smiles)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::Remarks)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::References)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::DecompTemp)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::DetPressure)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::DetVelocity)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::DissTemp)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::Density)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::FormationEnthalpy)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::FrictionSensitivity)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::ImpactSensitive)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::NContent)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::NoContent)).like(&keyword))
-            .or(Expr::col((property::Entity, property::Column::OContent)).like(&keyword))
-        );
-    }
-    let models = models
-        .filter(structure::Column::Charge.gte(min_charge))
-        .filter(structure::Column::Charge.lte(max_charge))
-        .order_by_desc(structure::Column::Id)
-        .paginate(db, page_size as u64);
-    let pages = models.num_pages().await.map_err(|e| {
-        format!(
-            "查询错误，可能是由于数据库损坏或权限问题，详细信息\n{:#?}",
-            e
-        )
-    })?;
-    let models = models.fetch_page(page_number as u64).await.map_err(|e| {
-        format!(
-            "查询错误，可能是由于数据库损坏或权限问题，详细信息\n{:#?}",
-            e
-        )
-    })?;
-    Ok((models, pages as u32))
+    connected(&db)?
+        .search_structure(keyword, max_charge, min_charge, ranges, query, sort, cursor, limit)
+        .await
 }
 
-fn write_bom<T: std::io::Write>(w: &mut T) -> std::io::Result<()> {
-    w.write_all(&[0xEF, 0xBB, 0xBF])
+#[tauri::command]
+#[specta::specta]
+async fn import_from_folder(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    folder_path: PathBuf,
+    options: ImportOptions,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.import_from_folder(&folder_path, options, &job).await;
+    state.job.lock().await.take();
+    result
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn import_from_folder(
+async fn check_import(
     state: State<'_, AppState>,
     folder_path: PathBuf,
-) -> Result<(), String> {
+    options: ImportOptions,
+) -> Result<ImportReport, ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let structure_csv = folder_path.join("structures.csv");
-    let structure_csv = File::open(structure_csv).map_err(|e| format!("无法打开表格，{:#?}", e))?;
-    let structure_csv = SkipEncodingBom::new(&[BomType::UTF8], structure_csv);
-    let mut structure_csv = csv::Reader::from_reader(structure_csv);
-    for model in structure_csv.deserialize() {
-        let model: structure::Model =
-            model.map_err(|e| format!("structure表格式不正确：\n{:#?}", e))?;
-        let model: structure::ActiveModel = model.into();
-        let model = model.reset_all();
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("写入失败，原因：\n{:#?}", e))?;
-    }
-    let property_csv = folder_path.join("properties.csv");
-    let property_csv = File::open(property_csv).map_err(|e| format!("无法打开表格，{:#?}", e))?;
-    let property_csv = SkipEncodingBom::new(&[BomType::UTF8], property_csv);
-    let mut property_csv = csv::Reader::from_reader(property_csv);
-    for model in property_csv.deserialize() {
-        let model: property::Model =
-            model.map_err(|e| format!("property表格式不正确：\n{:#?}", e))?;
-        let model: property::ActiveModel = model.into();
-        let model = model.reset_all();
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("写入失败，原因：\n{:#?}", e))?;
-    }
-    let component_csv = folder_path.join("components.csv");
-    let component_csv = File::open(component_csv).map_err(|e| format!("无法打开表格，{:#?}", e))?;
-    let component_csv = SkipEncodingBom::new(&[BomType::UTF8], component_csv);
-    let mut component_csv = csv::Reader::from_reader(component_csv);
-    for model in component_csv.deserialize() {
-        let model: component::Model =
-            model.map_err(|e| format!("component表格式不正确：\n{:#?}", e))?;
-        let model: component::ActiveModel = model.into();
-        let model = model.reset_all();
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("写入失败，原因：\n{:#?}", e))?;
-    }
-    let image_folder = folder_path.join("images");
-    let image_folders =
-        fs::read_dir(&image_folder).map_err(|e| format!("无法读取图片目录\n{:#?}", e))?;
-    for item in image_folders {
-        let item = item.map_err(|e| format!("无法读取的路径\n{:#?}", e))?;
-        let structure_id: u32 = item
-            .file_name()
-            .into_string()
-            .map_err(|e| format!("无法识别的路径\n{:?}", e))?
-            .parse()
-            .map_err(|e| format!("无法将名称解析为结构ID：{:#?}", e))?;
-        let filename = fs::read_dir(image_folder.join(structure_id.to_string()))
-            .map_err(|e| format!("无法读取图片文件夹\n{:#?}", e))?
-            .next()
-            .ok_or("发现了空的图片文件夹")?
-            .map_err(|e| format!("无法读取的路径\n{:#?}", e))?
-            .file_name()
-            .into_string()
-            .map_err(|e| format!("无法识别的路径\n{:?}", e))?;
-        let full_image_path = image_folder.join(structure_id.to_string()).join(&filename);
-        let mut image_content = vec![];
-        File::open(full_image_path)
-            .map_err(|e| format!("无法打开文件\n{:#?}", e))?
-            .read_to_end(&mut image_content)
-            .map_err(|e| format!("无法读取文件\n{:#?}", e))?;
-        let model = image::ActiveModel {
-            structure_id: ActiveValue::set(structure_id),
-            filename: ActiveValue::set(filename),
-            image: ActiveValue::set(image_content),
-        };
-        model
-            .insert(db)
-            .await
-            .map_err(|e| format!("未能添加记录，原因：\n{:#?}", e))?;
-    }
-    Ok(())
+    connected(&db)?.check_import(&folder_path, options).await
 }
 
 #[tauri::command]
 #[specta::specta]
-async fn export_to_folder(state: State<'_, AppState>, folder_path: PathBuf) -> Result<(), String> {
+async fn export_to_folder(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    folder_path: PathBuf,
+    options: ExportOptions,
+) -> Result<u32, ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.export_to_folder(&folder_path, options, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn import_sdf(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+    sdf: SdfOptions,
+    options: ImportOptions,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.import_sdf(&path, &sdf, options, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn export_sdf(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+    sdf: SdfOptions,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.export_sdf(&path, &sdf, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn import_xlsx(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+    options: ImportOptions,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.import_xlsx(&path, options, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn export_xlsx(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+    xlsx: XlsxOptions,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.export_xlsx(&path, xlsx, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn import_archive(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+    options: ImportOptions,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.import_archive(&path, options, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn check_archive(
+    state: State<'_, AppState>,
+    path: PathBuf,
+    options: ImportOptions,
+) -> Result<ArchiveReport, ChembankError> {
     let db = state.db.lock().await;
-    let db = db.as_ref().ok_or(format!(
-        "无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员"
-    ))?;
-    let _ = fs::create_dir(&folder_path);
-    let structures_csv = folder_path.join("structures.csv");
-    let mut structures_csv =
-        File::create(structures_csv).map_err(|e| format!("无法创建表格：\n{:#?}", e))?;
-    write_bom(&mut structures_csv).map_err(|e| format!("无法写入文件：\n{:#?}", e))?;
-    let mut structure_csv = csv::Writer::from_writer(structures_csv);
-    let structures = structure::Entity::find()
-        .all(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?;
-    for structure in structures {
-        structure_csv
-            .serialize(structure)
-            .map_err(|e| format!("写入错误，原因为：{:#?}", e))?;
-    }
-    let properties_csv = folder_path.join("properties.csv");
-    let mut properties_csv =
-        File::create(properties_csv).map_err(|e| format!("无法创建表格：\n{:#?}", e))?;
-    write_bom(&mut properties_csv).map_err(|e| format!("无法写入文件：\n{:#?}", e))?;
-    let mut property_csv = csv::Writer::from_writer(properties_csv);
-    let properties = property::Entity::find()
-        .all(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?;
-    for property in properties {
-        property_csv
-            .serialize(property)
-            .map_err(|e| format!("写入错误，原因为：{:#?}", e))?;
-    }
-    let components_csv = folder_path.join("components.csv");
-    let mut components_csv =
-        File::create(components_csv).map_err(|e| format!("无法创建表格：\n{:#?}", e))?;
-    write_bom(&mut components_csv).map_err(|e| format!("无法写入文件：\n{:#?}", e))?;
-    let mut component_csv = csv::Writer::from_writer(components_csv);
-    let components = component::Entity::find()
-        .all(db)
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?;
-    for component in components {
-        component_csv
-            .serialize(component)
-            .map_err(|e| format!("写入错误，原因为：\n{:#?}", e))?;
-    }
-    let image_folder = folder_path.join("images");
-    let _ = fs::create_dir(&image_folder);
-    let mut image_pages = image::Entity::find()
-        .order_by_asc(image::Column::StructureId)
-        .paginate(db, 10);
-    while let Some(images) = image_pages
-        .fetch_and_next()
-        .await
-        .map_err(|e| format!("查询错误，详细信息\n{:#?}", e))?
-    {
-        for image in images {
-            let image_folder = image_folder.join(image.structure_id.to_string());
-            let _ = fs::create_dir(&image_folder);
-            let write_path = image_folder.join(image.filename);
-            let mut write_file =
-                File::create(write_path).map_err(|e| format!("无法创建图片：\n{:#?}", e))?;
-            write_file
-                .write_all(&image.image)
-                .map_err(|e| format!("无法写入图片：\n{:#?}", e))?;
-        }
+    connected(&db)?.check_archive(&path, options).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn export_archive(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: PathBuf,
+) -> Result<(), ChembankError> {
+    let guard = state.db.lock().await;
+    let db = connected(&guard)?;
+    let job = start_job(&app, &state).await;
+    let result = db.export_archive(&path, &job).await;
+    state.job.lock().await.take();
+    result
+}
+
+/// 取消正在运行的导入导出，没有任务运行时不做任何事
+#[tauri::command]
+#[specta::specta]
+async fn cancel_transfer(state: State<'_, AppState>) -> Result<(), ChembankError> {
+    if let Some(job) = state.job.lock().await.as_ref() {
+        job.cancel();
     }
     Ok(())
 }
@@ -607,11 +540,20 @@ async fn export_to_folder(state: State<'_, AppState>, folder_path: PathBuf) -> R
 #[test]
 fn export_bindings() {
     use specta::collect_types;
+    use std::io::Write;
     use tauri_specta::ts;
 
     ts::export(
         collect_types![
+            database_status,
+            recover_database,
+            open_database,
+            create_database,
+            close_database,
+            current_database,
+            recent_databases,
             reset_database,
+            schema_report,
             create_structure,
             update_structure,
             remove_structure,
@@ -623,39 +565,50 @@ fn export_bindings() {
             get_structure_detail,
             export_to_folder,
             import_from_folder,
+            check_import,
+            cancel_transfer,
+            import_sdf,
+            export_sdf,
+            import_xlsx,
+            export_xlsx,
+            import_archive,
+            check_archive,
+            export_archive,
         ],
         "../src/bindings.ts",
     )
     .unwrap();
-}
 
-async fn init_db(db: &DatabaseConnection) -> Result<(), String> {
-    let builder = db.get_database_backend();
-    let structure_stmt = Schema::new(builder).create_table_from_entity(structure::Entity);
-    let component_stmt = Schema::new(builder).create_table_from_entity(component::Entity);
-    let property_stmt = Schema::new(builder).create_table_from_entity(property::Entity);
-    let image_stmt = Schema::new(builder).create_table_from_entity(image::Entity);
-    for stmt in vec![structure_stmt, component_stmt, property_stmt, image_stmt] {
-        let stmt = builder.build(&stmt);
-        db.execute(stmt)
-            .await
-            .map_err(|e| format!("未能完成初始化，详细信息：\n{:#?}", e))?;
+    // 命令的错误类型不会被collect_types!收集，需要单独追加到绑定文件中
+    let config = Default::default();
+    let mut bindings = std::fs::OpenOptions::new()
+        .append(true)
+        .open("../src/bindings.ts")
+        .unwrap();
+    for ty in [
+        specta::ts::export::<chembank_core::ChembankError>(&config).unwrap(),
+        specta::ts::export::<chembank_core::Table>(&config).unwrap(),
+        specta::ts::export::<chembank_core::Progress>(&config).unwrap(),
+        specta::ts::export::<chembank_core::Phase>(&config).unwrap(),
+    ] {
+        writeln!(bindings, "{}", ty).unwrap();
     }
-    Ok(())
 }
 
 #[tokio::test]
 async fn test_create_db() {
-    let db = Database::connect("sqlite://./chembank.db?mode=rwc")
+    let mut db = Chembank::connect("sqlite://./chembank.db?mode=rwc")
         .await
         .unwrap();
-    init_db(&db).await.unwrap();
+    db.migrate().await.unwrap();
     db.close().await.unwrap();
 }
 
 #[tokio::test]
 async fn write_to_csv() {
+    use sea_orm::{Database, EntityTrait};
     use std::fs::{create_dir, File};
+    use std::io::Write;
     use std::path::Path;
 
     let db = Database::connect("sqlite:chembank.db").await.unwrap();
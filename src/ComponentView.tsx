@@ -1,7 +1,7 @@
 import { Box, Button, ButtonGroup, Checkbox, FormControlLabel, Grid2, Slider, TextField, Typography } from "@mui/material";
 import { useEffect, useState } from "react";
 import { useNavigate, useSearchParams } from "react-router";
-import { createStructure, searchStructure, setComponent } from "./bindings";
+import { createStructure, SearchPage, searchStructure, setComponent } from "./bindings";
 import useFetch from "./useFetch";
 
 
@@ -9,18 +9,19 @@ import useFetch from "./useFetch";
 export default function ComponentView() {
     const navigate = useNavigate();
     const [searchParams] = useSearchParams();
-    const page = Number(searchParams.get("page") ?? "0");
     const componentOf = Number(searchParams.get("component_of")!);
     const [componentCount, setComponentCount] = useState(1);
     const [keyword, setKeyword] = useState<string | null>(null)
     const [[minCharge, maxCharge], setChargeRange] = useState<[number, number]>([-10, 10])
-    const [[structures, count]] = useFetch(() => searchStructure(100, page, keyword, maxCharge, minCharge), [[], 0], [page, keyword, minCharge, maxCharge]);
+    // 已经访问过的各页的分页位置，最后一个为当前页
+    const [cursors, setCursors] = useState<(string | null)[]>([null]);
+    const cursor = cursors[cursors.length - 1];
+    const emptyPage: SearchPage = { hits: [], total: 0, next_cursor: null };
+    const [{ hits, total, next_cursor }] = useFetch(() => searchStructure(keyword, maxCharge, minCharge, [], null, [], cursor, 100), emptyPage, [cursor, keyword, minCharge, maxCharge]);
     const [selected, setSelected] = useState<number | null>(null);
     useEffect(() => {
-        if (page > count) {
-            navigate(`/?page=${count - 1}`)
-        }
-    }, [page, count])
+        setCursors([null])
+    }, [keyword, minCharge, maxCharge])
     return <Grid2 display={"flex"} flexDirection={"column"} gap={1}>
         <Grid2 container spacing={2}>
             <Grid2 spacing={1} container alignItems={"center"} justifyContent={"start"} size={12}>
@@ -70,7 +71,7 @@ export default function ComponentView() {
         </Grid2>
         <Grid2 display={"flex"} gap={1} flexDirection={"column"} justifyContent={"space-around"}>
             {
-                structures.map((structure, idx) => <Grid2 container justifyContent={"center"} key={idx}>
+                hits.map(({ structure }, idx) => <Grid2 container justifyContent={"center"} key={idx}>
                     <Grid2 size={1}>{structure.id}</Grid2>
                     <Grid2 size={2}>{structure.name}</Grid2>
                     <Grid2 size={3}>{structure.formula}</Grid2>
@@ -88,16 +89,12 @@ export default function ComponentView() {
                 </Grid2>)
             }
         </Grid2>
-        <Grid2>
+        <Grid2 container alignItems={"center"} spacing={2}>
             <ButtonGroup variant="contained">
-                {
-                    new Array(count).fill(0).map((_, k) => k).map(key => <Button color={key === page ? "info" : "primary"} key={key} onClick={() => {
-                        navigate(`/?page=${key}&component_of=${componentOf}`)
-                    }}>
-                        {key + 1}
-                    </Button>)
-                }
+                <Button disabled={cursors.length === 1} onClick={() => setCursors(cursors.slice(0, -1))}>上一页</Button>
+                <Button disabled={next_cursor === null} onClick={() => setCursors([...cursors, next_cursor])}>下一页</Button>
             </ButtonGroup>
+            <Typography>共{total}个结构，第{cursors.length}页</Typography>
         </Grid2>
     </Grid2>
 }
\ No newline at end of file
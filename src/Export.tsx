@@ -1,23 +1,35 @@
 import { Box, Button, Typography } from "@mui/material";
 import { useEffect, useState } from "react";
 import { useNavigate, useSearchParams } from "react-router";
-import { exportToFolder } from "./bindings";
+import { exportArchive, exportSdf, exportToFolder, exportXlsx } from "./bindings";
+import { describeError } from "./errors";
+import TransferProgress from "./TransferProgress";
 
 export default function ExportView() {
     const [finished, setFinished] = useState<number | string>(0);
+    const [count, setCount] = useState<number | null>(null);
     const [searchParams] = useSearchParams();
+    // 导出到目录时为目录，导出SD文件、Excel工作簿或归档时为文件路径
     const folder = searchParams.get("folder")!;
+    const merged = searchParams.get("merged") === "true";
+    const images = searchParams.get("images") === "true";
+    const format = searchParams.get("format");
+    // 只导出部分结构时为JSON形式的`ExportSubset`
+    const subset = searchParams.get("subset");
     const navigate = useNavigate();
 
     useEffect(() => {
-        exportToFolder(folder).then(() => setFinished(1)).catch((e) => setFinished(String(e)))
+        const exported = format === "sdf" ? exportSdf(folder, {}) :
+            format === "xlsx" ? exportXlsx(folder, { images }) :
+                format === "archive" ? exportArchive(folder) : exportToFolder(folder, { merged, subset: subset === null ? null : JSON.parse(subset) }).then(setCount);
+        exported.then(() => setFinished(1)).catch((e) => setFinished(describeError(e)))
     }, [])
 
     return <Box>
         {
-            finished === 0 ? <Typography>正在导出数据到{folder}</Typography> :
+            finished === 0 ? <TransferProgress title={`正在导出数据到${folder}`} /> :
                 finished === 1 ? <>
-                    <Typography>数据导出完成</Typography>
+                    <Typography>{count === null ? "数据导出完成" : `已导出${count}个结构`}</Typography>
                     <Button variant="contained" color="success" onClick={() => navigate("/")}>返回首页</Button>
                 </> :
                     <>
@@ -1,34 +1,115 @@
-import { Button, ButtonGroup, Checkbox, FormControlLabel, FormGroup, Grid2, TextField, Typography } from "@mui/material";
+import { Button, ButtonGroup, Checkbox, FormControlLabel, FormGroup, Grid2, MenuItem, TextField, Typography } from "@mui/material";
 import { confirm, message, open, save } from "@tauri-apps/api/dialog";
 import { useEffect, useState } from "react";
-import { useNavigate, useSearchParams } from "react-router";
-import { createStructure, importFromFolder, removeStructure, resetDatabase, searchStructure } from "./bindings";
+import { useNavigate } from "react-router";
+import { ChembankError, createStructure, databaseStatus, ExportSubset, PropertyRange, Quantity, removeStructure, resetDatabase, SearchPage, searchStructure, SortField, SortKey } from "./bindings";
+import { describeError } from "./errors";
 import useFetch from "./useFetch";
 import rdkitModule from "./rdkit";
+import { quantities } from "./quantities";
+
+/**
+ * 数值输入框的内容，空白表示不限
+ */
+function parseBound(value: string): number | null {
+    return value.trim() === "" || isNaN(Number(value)) ? null : Number(value)
+}
+
+const sortFields: [string, string][] = [
+    ["Id", "序号"],
+    ["Name", "名称"],
+    ["Formula", "分子式"],
+    ["Smiles", "SMILES"],
+    ["Charge", "电荷数"],
+    ...Object.entries(quantities).map(([quantity, { label }]): [string, string] => [`Property:${quantity}`, label]),
+]
+
+/**
+ * 排序下拉框的值，属性写作`Property:数量名`
+ */
+function sortFieldValue(field: SortField): string {
+    return typeof field === "string" ? field : `Property:${field.Property}`
+}
+
+function parseSortField(value: string): SortField {
+    return value.startsWith("Property:") ? { Property: value.slice("Property:".length) as Quantity } : value as SortField
+}
+
+const emptyPage: SearchPage = { hits: [], total: 0, next_cursor: null }
+
+/**
+ * 关键词能解析为SMILES时换为规范形式再检索
+ */
+function processKeyword(keyword: string | null): Promise<string | null> {
+    return rdkitModule.then(
+        rdkit => keyword !== null ? rdkit.get_mol(keyword) : null
+    ).then(
+        mol => mol?.get_smiles() ?? keyword
+    )
+}
+
+/**
+ * 选择导出数据的目录
+ */
+function chooseExportFolder(): Promise<string | null> {
+    return save({
+        filters: [
+            {
+                name: "保存到目录",
+                extensions: []
+            }
+        ]
+    });
+}
 
 
 
 export default function Home() {
     const navigate = useNavigate();
-    const [searchParams] = useSearchParams();
-    const page = Number(searchParams.get("page") ?? "0");
     const [keyword, setKeyword] = useState<string | null>(null)
     const [expandMode, setExpandMode] = useState(false);
     const minCharge = expandMode ? -10 : 0
     const maxCharge = expandMode ? 10 : 0
-    const [[structures, count], refreshList] = useFetch(async () => {
-        const processedKeyword = await rdkitModule.then(
-            rdkit => keyword !== null ? rdkit.get_mol(keyword) : null
-        ).then(
-            mol => mol?.get_smiles() ?? keyword
-        )
-        return searchStructure(100, page, processedKeyword, maxCharge, minCharge)
-    }, [[], 0], [page, keyword, minCharge, maxCharge]);
-    useEffect(() => {
-        if (page >= count) {
-            navigate(`/?page=${Math.max(0, count - 1)}`)
+    const [ranges, setRanges] = useState<PropertyRange[]>([]);
+    const [query, setQuery] = useState<string | null>(null);
+    const [queryError, setQueryError] = useState<string | null>(null);
+    const [sort, setSort] = useState<SortKey | null>(null);
+    // 已经访问过的各页的分页位置，最后一个为当前页
+    const [cursors, setCursors] = useState<(string | null)[]>([null]);
+    const cursor = cursors[cursors.length - 1];
+    // 勾选的结构ID，用于只导出选中的结构
+    const [selected, setSelected] = useState<number[]>([]);
+    const [{ hits, total, next_cursor }, refreshList] = useFetch(async () => {
+        const processedKeyword = await processKeyword(keyword)
+        return searchStructure(processedKeyword, maxCharge, minCharge, ranges, query, sort === null ? [] : [sort], cursor, 100).then(result => {
+            setQueryError(null)
+            return result
+        }, (e: ChembankError) => {
+            if (e.kind !== "QuerySyntax") {
+                throw e
+            }
+            setQueryError(describeError(e))
+            return emptyPage
+        })
+    }, emptyPage, [cursor, keyword, minCharge, maxCharge, ranges, query, sort]);
+    const exportSubset = async (subset: ExportSubset) => {
+        const folder = await chooseExportFolder()
+        if (folder !== null) {
+            const merged = await confirm("是否将结构表、属性表和组成关系合并为一个表格导出？点击确定合并导出，点击取消分别导出为三个表格。")
+            navigate(`/export?folder=${encodeURIComponent(folder)}&merged=${merged}&subset=${encodeURIComponent(JSON.stringify(subset))}`)
         }
-    }, [page, count])
+    }
+    const updateRange = (index: number, range: PropertyRange) => setRanges(ranges.map((item, idx) => idx === index ? range : item));
+    useEffect(() => {
+        databaseStatus().then(status => {
+            if (status.state === "Failed") {
+                navigate("/recovery")
+            }
+        })
+    }, [])
+    useEffect(() => {
+        setCursors([null])
+    }, [keyword, minCharge, maxCharge, ranges, query, sort])
     return <Grid2 display={"flex"} flexDirection={"column"} gap={1}>
         <Grid2 container spacing={2}>
             <Grid2 spacing={1} container alignItems={"center"} justifyContent={"start"} size={12}>
@@ -42,45 +123,117 @@ export default function Home() {
                         ]
                     })
                     if (folder !== null) {
-                        const resetDB = await confirm("导入数据前需要清空数据库。点击确定清空数据库，点击取消取消导入。")
-                        if (resetDB) {
-                            navigate("/import")
-                            await resetDatabase()
-                            importFromFolder(folder as string)
-                                .then(() => {
-                                    message("导入成功")
-                                    navigate("/")
-                                })
-                                .then(refreshList)
-                                .catch((e) => message(`导入失败，原因为：${e}`))
-                        }
-
+                        navigate(`/import?folder=${encodeURIComponent(folder as string)}`)
                     }
                 }}>导入数据</Button>
                 <Button variant="contained" color="secondary" onClick={async () => {
-                    const folder = await save({
+                    const folder = await chooseExportFolder();
+                    if (folder !== null) {
+                        const merged = await confirm("是否将结构表、属性表和组成关系合并为一个表格导出？点击确定合并导出，点击取消分别导出为三个表格。")
+                        navigate(`/export?folder=${folder}&merged=${merged}`)
+                    }
+                }}>导出数据</Button>
+                <Button variant="contained" color="secondary" onClick={async () => exportSubset({
+                    Filter: { keyword: await processKeyword(keyword), max_charge: maxCharge, min_charge: minCharge, ranges, query }
+                })}>导出检索结果</Button>
+                <Button variant="contained" color="secondary" disabled={selected.length === 0} onClick={() => exportSubset({ Ids: selected })}>导出选中结构（{selected.length}）</Button>
+                <Button variant="contained" color="primary" onClick={async () => {
+                    const file = await open({
                         filters: [
-                            {
-                                name: "保存到目录",
-                                extensions: []
-                            }
+                            { name: "SD文件", extensions: ["sdf", "sd"] }
+                        ]
+                    })
+                    if (file !== null) {
+                        navigate(`/import-file?file=${encodeURIComponent(file as string)}&format=sdf`)
+                    }
+                }}>导入SD文件</Button>
+                <Button variant="contained" color="secondary" onClick={async () => {
+                    const file = await save({
+                        filters: [
+                            { name: "SD文件", extensions: ["sdf"] }
                         ]
                     });
-                    if (folder !== null) {
-                        navigate(`/export?folder=${folder}`)
+                    if (file !== null) {
+                        navigate(`/export?folder=${encodeURIComponent(file)}&format=sdf`)
                     }
-                }}>导出数据</Button>
+                }}>导出SD文件</Button>
+                <Button variant="contained" color="primary" onClick={async () => {
+                    const file = await open({
+                        filters: [
+                            { name: "Excel工作簿", extensions: ["xlsx"] }
+                        ]
+                    })
+                    if (file !== null) {
+                        navigate(`/import-file?file=${encodeURIComponent(file as string)}&format=xlsx`)
+                    }
+                }}>导入Excel工作簿</Button>
+                <Button variant="contained" color="secondary" onClick={async () => {
+                    const file = await save({
+                        filters: [
+                            { name: "Excel工作簿", extensions: ["xlsx"] }
+                        ]
+                    });
+                    if (file !== null) {
+                        const images = await confirm("是否在结构表中插入结构图片？图片较多时导出较慢，导入时图片会被忽略。")
+                        navigate(`/export?folder=${encodeURIComponent(file)}&format=xlsx&images=${images}`)
+                    }
+                }}>导出Excel工作簿</Button>
+                <Button variant="contained" color="primary" onClick={async () => {
+                    const file = await open({
+                        filters: [
+                            { name: "ChemBank归档", extensions: ["chembank"] }
+                        ]
+                    })
+                    if (file !== null) {
+                        navigate(`/import?archive=${encodeURIComponent(file as string)}`)
+                    }
+                }}>导入归档</Button>
+                <Button variant="contained" color="secondary" onClick={async () => {
+                    const file = await save({
+                        filters: [
+                            { name: "ChemBank归档", extensions: ["chembank"] }
+                        ]
+                    });
+                    if (file !== null) {
+                        navigate(`/export?folder=${encodeURIComponent(file)}&format=archive`)
+                    }
+                }}>导出归档</Button>
                 <Button variant="contained" color="error" onClick={async () => {
                     await resetDatabase();
                     refreshList()
                 }}>清空数据</Button>
             </Grid2>
             <Grid2 container alignItems={"center"} flexDirection={"row"} size={12} spacing={2}>
-                <TextField sx={{ width: 512 }} placeholder="输入名称、分子式或SMILES查询" label="关键词（名称/分子式/SMILES）" value={keyword ?? ""} onChange={(e) => { if (e.target.value === "") { setKeyword(null) } else { setKeyword(e.target.value) } }}></TextField>
+                <TextField sx={{ width: 512 }} placeholder="输入名称、分子式或SMILES查询" label="关键词（名称/分子式/SMILES/备注/参考文献）" value={keyword ?? ""} onChange={(e) => { if (e.target.value === "") { setKeyword(null) } else { setKeyword(e.target.value) } }}></TextField>
                 <FormGroup>
                     <FormControlLabel label="显示离子" control={<Checkbox checked={expandMode} onClick={() => setExpandMode(!expandMode)}></Checkbox>}></FormControlLabel>
                 </FormGroup>
+                <Button variant="outlined" onClick={() => setRanges([...ranges, { quantity: "Density", min: null, max: null }])}>添加数值条件</Button>
+                <TextField select sx={{ width: 200 }} label="排序" value={sort === null ? "" : sortFieldValue(sort.field)} onChange={(e) => setSort(e.target.value === "" ? null : { field: parseSortField(e.target.value), descending: sort?.descending ?? false })}>
+                    <MenuItem value="">默认</MenuItem>
+                    {
+                        sortFields.map(([value, label]) => <MenuItem key={value} value={value}>{label}</MenuItem>)
+                    }
+                </TextField>
+                <FormGroup>
+                    <FormControlLabel label="倒序" control={<Checkbox disabled={sort === null} checked={sort?.descending ?? false} onClick={() => sort !== null && setSort({ ...sort, descending: !sort.descending })}></Checkbox>}></FormControlLabel>
+                </FormGroup>
+            </Grid2>
+            <Grid2 container alignItems={"center"} flexDirection={"row"} size={12} spacing={2}>
+                <TextField sx={{ width: 768 }} placeholder={"例如 name:硝酸 AND charge=0 AND NOT remarks:待核实，或 element:N C<=6 element!=metal，或 component*:(charge=-2)"} label="检索表达式" value={query ?? ""} error={queryError !== null} helperText={queryError} onChange={(e) => { if (e.target.value.trim() === "") { setQuery(null) } else { setQuery(e.target.value) } }}></TextField>
             </Grid2>
+            {
+                ranges.map((range, idx) => <Grid2 container alignItems={"center"} flexDirection={"row"} size={12} spacing={2} key={idx}>
+                    <TextField select sx={{ width: 200 }} label="属性" value={range.quantity} onChange={(e) => updateRange(idx, { ...range, quantity: e.target.value as Quantity })}>
+                        {
+                            Object.entries(quantities).map(([quantity, { label, unit }]) => <MenuItem key={quantity} value={quantity}>{label}（{unit}）</MenuItem>)
+                        }
+                    </TextField>
+                    <TextField type="number" label="不小于" value={range.min ?? ""} onChange={(e) => updateRange(idx, { ...range, min: parseBound(e.target.value) })}></TextField>
+                    <TextField type="number" label="不大于" value={range.max ?? ""} onChange={(e) => updateRange(idx, { ...range, max: parseBound(e.target.value) })}></TextField>
+                    <Button color="error" onClick={() => setRanges(ranges.filter((_, i) => i !== idx))}>移除</Button>
+                </Grid2>)
+            }
         </Grid2>
         <Grid2>
             <Grid2 spacing={1} container justifyContent={"center"}>
@@ -106,8 +259,11 @@ export default function Home() {
         </Grid2>
         <Grid2 display={"flex"} gap={1} flexDirection={"column"} justifyContent={"space-around"}>
             {
-                structures.map((structure, idx) => <Grid2 container justifyContent={"center"} key={idx}>
-                    <Grid2 size={1}>{structure.id}</Grid2>
+                hits.map(({ structure, snippet }, idx) => <Grid2 container justifyContent={"center"} key={idx}>
+                    <Grid2 size={1}>
+                        <Checkbox size="small" checked={selected.includes(structure.id)} onChange={(e) => setSelected(e.target.checked ? [...selected, structure.id] : selected.filter(id => id !== structure.id))}></Checkbox>
+                        {structure.id}
+                    </Grid2>
                     <Grid2 size={2}>{structure.name}</Grid2>
                     <Grid2 size={3}>{structure.formula}</Grid2>
                     <Grid2 size={3}>{structure.smiles}</Grid2>
@@ -116,23 +272,26 @@ export default function Home() {
                         <ButtonGroup variant="contained">
                             <Button onClick={() => navigate(`/structure?id=${structure.id}`)}>详情</Button>
                             <Button color="error" onClick={() => {
-                                removeStructure(structure.id).then(refreshList).catch((e) => message(`删除失败，原因为：${e}`))
+                                removeStructure(structure.id).then(() => setSelected(selected.filter(id => id !== structure.id))).then(refreshList).catch((e) => message(`删除失败，原因为：${describeError(e)}`))
                             }}>删除</Button>
                         </ButtonGroup>
                     </Grid2>
+                    {
+                        snippet.length > 0 ? <Grid2 size={12}>
+                            <Typography variant="body2" color="textSecondary">
+                                {snippet.map((part, key) => part.matched ? <mark key={key}>{part.text}</mark> : <span key={key}>{part.text}</span>)}
+                            </Typography>
+                        </Grid2> : null
+                    }
                 </Grid2>)
             }
         </Grid2>
-        <Grid2>
+        <Grid2 container alignItems={"center"} spacing={2}>
             <ButtonGroup variant="contained">
-                {
-                    new Array(count).fill(0).map((_, k) => k).map(key => <Button color={key === page ? "info" : "primary"} key={key} onClick={() => {
-                        navigate(`/?page=${key}`)
-                    }}>
-                        {key + 1}
-                    </Button>)
-                }
+                <Button disabled={cursors.length === 1} onClick={() => setCursors(cursors.slice(0, -1))}>上一页</Button>
+                <Button disabled={next_cursor === null} onClick={() => setCursors([...cursors, next_cursor])}>下一页</Button>
             </ButtonGroup>
+            <Typography>共{total}个结构，第{cursors.length}页</Typography>
         </Grid2>
     </Grid2 >
 }
\ No newline at end of file
@@ -1,7 +1,108 @@
-import { Box, Typography } from "@mui/material";
+import { Box, Button, Checkbox, FormControlLabel, MenuItem, TextField, Typography } from "@mui/material";
+import { useEffect, useState } from "react";
+import { useNavigate, useSearchParams } from "react-router";
+import { checkArchive, checkImport, importArchive, ImportMode, ImportReport, importFromFolder, Manifest, MatchBy, Resolution } from "./bindings";
+import { describeError, describeIssue } from "./errors";
+import TransferProgress from "./TransferProgress";
+
+export const modes: [ImportMode, string][] = [
+    ["Fail", "遇到相同的结构时停止导入"],
+    ["Skip", "跳过已有结构"],
+    ["Overwrite", "用导入的结构覆盖已有结构"],
+    ["Merge", "补充已有结构中空白的信息"],
+    ["Renumber", "全部作为新结构导入"],
+]
+
+export const matchFields: [MatchBy, string][] = [
+    ["Id", "ID"],
+    ["Name", "名称"],
+    ["Smiles", "SMILES"],
+]
+
+const resolutions: Record<Resolution, string> = {
+    Error: "导致导入失败",
+    Skipped: "跳过",
+    Overwritten: "覆盖已有记录",
+    Merged: "补充已有记录",
+    Renumbered: "使用新的ID",
+}
 
 export default function ImportDataPage() {
+    const [searchParams] = useSearchParams();
+    // 从目录导入时给出folder，从归档导入时给出archive
+    const archive = searchParams.get("archive");
+    const folder = archive ?? searchParams.get("folder")!;
+    const navigate = useNavigate();
+    const [report, setReport] = useState<ImportReport | string | null>(null);
+    const [manifest, setManifest] = useState<Manifest | null>(null);
+    const [mode, setMode] = useState<ImportMode>("Fail");
+    const [matchBy, setMatchBy] = useState<MatchBy>("Id");
+    const [reset, setReset] = useState(false);
+    // 0为未开始，1为导入中，2为完成，字符串为出错信息
+    const [state, setState] = useState<number | string>(0);
+
+    // 清空数据库与导入在同一个事务中进行，导入失败时已有数据保持不变
+    const options = { mode, match_by: matchBy, replace: reset };
+
+    // 冲突的处理结果取决于选择的处理方式和是否清空数据库，改变后重新检查
+    useEffect(() => {
+        const checked = archive === null ? checkImport(folder, options) : checkArchive(archive, options).then((result) => {
+            setManifest(result.manifest)
+            return result.report
+        });
+        checked.then(setReport).catch((e) => setReport(describeError(e)))
+    }, [mode, matchBy, reset])
+
+    if (report === null) {
+        return <Typography>正在检查{folder}中的数据</Typography>
+    }
+    if (typeof report === "string") {
+        return <Box>
+            <Typography>无法检查导入数据：{report}</Typography>
+            <Button variant="contained" color="info" onClick={() => navigate("/")}>返回首页</Button>
+        </Box>
+    }
+    if (state === 1) {
+        return <TransferProgress title="数据导入中，请勿关闭程序" />
+    }
+    if (state === 2) {
+        return <Box>
+            <Typography>数据导入完成</Typography>
+            <Button variant="contained" color="success" onClick={() => navigate("/")}>返回首页</Button>
+        </Box>
+    }
+    const counts = new Map<Resolution, number>();
+    report.conflicts.forEach(conflict => counts.set(conflict.resolution, (counts.get(conflict.resolution) ?? 0) + 1));
+    const failing = counts.has("Error");
     return <Box>
-        <Typography>数据导入中，请勿关闭程序</Typography>
+        {manifest !== null ? <Typography>归档导出于{new Date(manifest.exported_at).toLocaleString()}，校验和检查通过</Typography> : null}
+        <Typography>将从{folder}导入{report.structures}个结构、{report.properties}条属性、{report.components}条组成关系和{report.images}张图片</Typography>
+        {report.errors.length > 0 ? <>
+            <Typography color="error">发现{report.errors.length}个错误，请修改后重新导入：</Typography>
+            {report.errors.map((issue, index) => <Typography key={index} variant="body2">{describeIssue(issue)}</Typography>)}
+        </> : null}
+        {report.conflicts.length > 0 ? <Typography>
+            有{report.conflicts.length}处与已有结构相同（{[...new Set(report.conflicts.map(conflict => conflict.existing_id))].slice(0, 10).join(", ")}{report.conflicts.length > 10 ? "等" : ""}），
+            按当前处理方式：{[...counts].map(([resolution, count]) => `${count}处${resolutions[resolution]}`).join("，")}
+        </Typography> : null}
+        {failing ? <Typography color="error">请选择其他处理方式，或导入前清空数据库</Typography> : null}
+        <FormControlLabel label="导入前清空数据库" control={<Checkbox checked={reset} onClick={() => setReset(!reset)}></Checkbox>}></FormControlLabel>
+        <TextField select sx={{ width: 280 }} label="相同结构的处理方式" value={mode} disabled={reset} onChange={(e) => setMode(e.target.value as ImportMode)}>
+            {modes.map(([value, label]) => <MenuItem key={value} value={value}>{label}</MenuItem>)}
+        </TextField>
+        <TextField select sx={{ width: 160 }} label="按此判断结构相同" value={matchBy} disabled={reset || mode === "Fail" || mode === "Renumber"} onChange={(e) => setMatchBy(e.target.value as MatchBy)}>
+            {matchFields.map(([value, label]) => <MenuItem key={value} value={value}>{label}</MenuItem>)}
+        </TextField>
+        {typeof state === "string" ? <Typography color="error">导入未完成，未做任何修改：{state}</Typography> : null}
+        <Button variant="contained" color="primary" disabled={report.errors.length > 0 || failing} onClick={async () => {
+            setState(1)
+            try {
+                await (archive === null ? importFromFolder(folder, options) : importArchive(archive, options))
+                setState(2)
+            } catch (e) {
+                setState(describeError(e))
+            }
+        }}>开始导入</Button>
+        <Button variant="outlined" onClick={() => navigate("/")}>取消</Button>
     </Box>
-}
\ No newline at end of file
+}
@@ -0,0 +1,91 @@
+import { Box, Button, IconButton, MenuItem, TextField, Typography } from "@mui/material";
+import { useState } from "react";
+import { useNavigate, useSearchParams } from "react-router";
+import { ImportMode, importSdf, importXlsx, MatchBy, Quantity, SdfField } from "./bindings";
+import { describeError } from "./errors";
+import { matchFields, modes } from "./Import";
+import { quantities } from "./quantities";
+import TransferProgress from "./TransferProgress";
+
+/**
+ * SD标签可以对应的字段，第一项为下拉框中的取值，第三项为显示的名称
+ */
+const sdfFields: [string, SdfField, string][] = [
+    ["id", "Id", "ID"],
+    ["name", "Name", "名称"],
+    ["formula", "Formula", "分子式"],
+    ["smiles", "Smiles", "SMILES"],
+    ["charge", "Charge", "电荷"],
+    ...(Object.keys(quantities) as Quantity[]).map((quantity): [string, SdfField, string] => [quantity, { Property: quantity }, quantities[quantity].label]),
+    ["references", "References", "参考文献"],
+    ["remarks", "Remarks", "备注"],
+]
+
+/**
+ * 从SD文件或Excel工作簿导入，`format`为`sdf`或`xlsx`
+ */
+export default function ImportFilePage() {
+    const [searchParams] = useSearchParams();
+    const file = searchParams.get("file")!;
+    const sdf = searchParams.get("format") === "sdf";
+    const navigate = useNavigate();
+    // SD标签和对应字段在下拉框中的取值
+    const [mapping, setMapping] = useState<[string, string][]>([]);
+    const [mode, setMode] = useState<ImportMode>("Fail");
+    const [matchBy, setMatchBy] = useState<MatchBy>("Id");
+    // 0为未开始，1为导入中，2为完成，字符串为出错信息
+    const [state, setState] = useState<number | string>(0);
+
+    if (state === 1) {
+        return <TransferProgress title="数据导入中，请勿关闭程序" />
+    }
+    if (state === 2) {
+        return <Box>
+            <Typography>数据导入完成</Typography>
+            <Button variant="contained" color="success" onClick={() => navigate("/")}>返回首页</Button>
+        </Box>
+    }
+    return <Box>
+        <Typography>从{file}导入{sdf ? "结构和属性" : "结构、属性和组成关系"}</Typography>
+        {sdf ? <>
+            <Typography variant="body2">与字段名相同的SD标签（如name、smiles、density，不区分大小写）会自动识别，其他标签可以在下面指定对应的字段；没有名称标签时使用记录的标题行作为名称</Typography>
+            {mapping.map(([tag, key], index) => <Box key={index}>
+                <TextField label="SD标签" value={tag} onChange={(e) => setMapping(mapping.map((item, i) => i === index ? [e.target.value, item[1]] : item))}></TextField>
+                <TextField select sx={{ width: 200 }} label="字段" value={key} onChange={(e) => setMapping(mapping.map((item, i) => i === index ? [item[0], e.target.value] : item))}>
+                    {sdfFields.map(([key, _, label]) => <MenuItem key={key} value={key}>{label}</MenuItem>)}
+                </TextField>
+                <IconButton onClick={() => setMapping(mapping.filter((_, i) => i !== index))}>×</IconButton>
+            </Box>)}
+            <Button variant="outlined" onClick={() => setMapping([...mapping, ["", "name"]])}>添加标签映射</Button>
+        </> : <Typography variant="body2">工作簿中的structures、properties和components三个工作表与导出目录中的三个CSV表格格式相同，结构图片不会导入</Typography>}
+        <Box>
+            <TextField select sx={{ width: 280 }} label="相同结构的处理方式" value={mode} onChange={(e) => setMode(e.target.value as ImportMode)}>
+                {modes.map(([value, label]) => <MenuItem key={value} value={value}>{label}</MenuItem>)}
+            </TextField>
+            <TextField select sx={{ width: 160 }} label="按此判断结构相同" value={matchBy} disabled={mode === "Fail" || mode === "Renumber"} onChange={(e) => setMatchBy(e.target.value as MatchBy)}>
+                {matchFields.map(([value, label]) => <MenuItem key={value} value={value}>{label}</MenuItem>)}
+            </TextField>
+        </Box>
+        {typeof state === "string" ? <Typography color="error">导入未完成，未做任何修改：{state}</Typography> : null}
+        <Button variant="contained" color="primary" onClick={async () => {
+            const fields: { [tag: string]: SdfField } = {}
+            for (const [tag, key] of mapping) {
+                if (tag !== "") {
+                    fields[tag] = sdfFields.find(([k]) => k === key)![1]
+                }
+            }
+            setState(1)
+            try {
+                if (sdf) {
+                    await importSdf(file, { fields }, { mode, match_by: matchBy })
+                } else {
+                    await importXlsx(file, { mode, match_by: matchBy })
+                }
+                setState(2)
+            } catch (e) {
+                setState(describeError(e))
+            }
+        }}>开始导入</Button>
+        <Button variant="outlined" onClick={() => navigate("/")}>取消</Button>
+    </Box>
+}
@@ -0,0 +1,45 @@
+import { Box, Button, ButtonGroup, Typography } from "@mui/material";
+import { message, open } from "@tauri-apps/api/dialog";
+import { useNavigate } from "react-router";
+import { DatabaseStatus, databaseStatus, openDatabase, recoverDatabase } from "./bindings";
+import { describeError } from "./errors";
+import useFetch from "./useFetch";
+
+const databaseFilters = [{ name: "数据库", extensions: ["db", "sqlite"] }];
+
+export default function RecoveryView() {
+    const navigate = useNavigate();
+    const [status] = useFetch<DatabaseStatus | null, []>(databaseStatus, null, []);
+
+    const finish = (action: Promise<null>) => action
+        .then(() => navigate("/"))
+        .catch((e) => message(`操作失败，原因为：${describeError(e)}`));
+
+    return <Box display={"flex"} flexDirection={"column"} gap={2}>
+        <Typography variant="h5">无法打开数据库</Typography>
+        {
+            status?.state === "Failed" ? <>
+                <Typography>{status.path ?? "未能确定数据库位置"}</Typography>
+                <Typography>{describeError(status.error)}</Typography>
+            </> : null
+        }
+        <ButtonGroup variant="contained">
+            <Button onClick={async () => {
+                const path = await open({ filters: databaseFilters });
+                if (typeof path === "string") {
+                    finish(openDatabase(path))
+                }
+            }}>打开其他数据库</Button>
+            <Button color="secondary" disabled={status?.state !== "Failed" || status.path === null} onClick={async () => {
+                const backup = await open({ filters: databaseFilters });
+                if (typeof backup === "string") {
+                    finish(recoverDatabase({ action: "RestoreBackup", backup }))
+                }
+            }}>从备份恢复</Button>
+            <Button color="error" disabled={status?.state !== "Failed" || status.path === null} onClick={() => {
+                finish(recoverDatabase({ action: "CreateNew" }))
+            }}>新建空数据库</Button>
+        </ButtonGroup>
+        <Typography variant="body2">从备份恢复或新建数据库时，原数据库文件会被重命名为“.broken”文件保留。</Typography>
+    </Box>
+}
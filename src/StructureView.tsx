@@ -20,6 +20,7 @@ import mime from "mime";
 import { readBinaryFile } from "@tauri-apps/api/fs";
 import { basename } from "@tauri-apps/api/path";
 import useFetch from "./useFetch";
+import { describeError } from "./errors";
 import { calculateNMQ, calculateDP, analyseMoleculeFormula, explosionSimulate } from "./utils"
 
 type ViewState = {
@@ -66,7 +67,30 @@ const emptyProperty = {
     o_content: null,
     no_content: null,
     references: "",
-    remarks: ""
+    remarks: "",
+    decomp_temp_value: null,
+    density_value: null,
+    diss_temp_value: null,
+    formation_enthalpy_value: null,
+    impact_sensitive_value: null,
+    friction_sensitivity_value: null,
+    det_velocity_value: null,
+    det_pressure_value: null,
+    n_content_value: null,
+    o_content_value: null,
+    no_content_value: null,
+    value_note: null,
+    decomp_temp_unit: null,
+    density_unit: null,
+    diss_temp_unit: null,
+    formation_enthalpy_unit: null,
+    impact_sensitive_unit: null,
+    friction_sensitivity_unit: null,
+    det_velocity_unit: null,
+    det_pressure_unit: null,
+    n_content_unit: null,
+    o_content_unit: null,
+    no_content_unit: null
 };
 export default function StructureView() {
     const navigate = useNavigate();
@@ -129,7 +153,7 @@ export default function StructureView() {
                 <Typography variant="h5">详细信息</Typography>
 
                 {componentOf === null ? <>
-                    <Button variant="contained" color="success" onClick={() => updateToDB(state).then(refresh).catch(e => message(e))}>保存</Button>
+                    <Button variant="contained" color="success" onClick={() => updateToDB(state).then(refresh).catch(e => message(describeError(e)))}>保存</Button>
                     <Button variant="contained" color="primary" onClick={() => updateToDB(state).then(() => navigate("/"))}>保存并返回首页</Button>
                     <Button variant="contained" color="error" onClick={() => removeStructure(Number(currentId)).then(() => navigate("/"))}>删除并返回首页</Button>
                 </> :
@@ -248,6 +272,11 @@ export default function StructureView() {
                     <Grid2 size={12}>
                         <TextField fullWidth multiline label="备注" placeholder="备注（可填写多行内容）" value={state.property.remarks ?? ""} onChange={(e) => setState({ ...state, property: { ...state.property, remarks: e.target.value } })}></TextField>
                     </Grid2>
+                    {
+                        state.property.value_note !== null ? <Grid2 size={12}>
+                            <Typography variant="body2" color="warning">以下内容无法识别为数值，不能参与按数值的检索和排序：{state.property.value_note}</Typography>
+                        </Grid2> : null
+                    }
                 </Grid2>
             </Box>
             <Box display={"flex"} flexDirection={"column"} gap={2}>
@@ -0,0 +1,36 @@
+import { Box, Button, LinearProgress, Typography } from "@mui/material";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect, useState } from "react";
+import { cancelTransfer, Phase, Progress } from "./bindings";
+
+const phaseNames: Record<Phase, string> = {
+    Structures: "结构",
+    Properties: "属性",
+    Components: "组成关系",
+    Images: "图片",
+};
+
+/** 显示正在运行的导入导出的进度，并提供取消按钮 */
+export default function TransferProgress(props: { title: string }) {
+    const [progress, setProgress] = useState<Progress | null>(null);
+    const [cancelling, setCancelling] = useState(false);
+
+    useEffect(() => {
+        const unlisten = listen<Progress>("transfer-progress", (event) => setProgress(event.payload));
+        return () => {
+            unlisten.then((f) => f())
+        }
+    }, [])
+
+    return <Box>
+        <Typography>{props.title}</Typography>
+        {progress === null ? <LinearProgress /> : <>
+            <Typography variant="body2">正在处理{phaseNames[progress.phase]}：{progress.processed}/{progress.total}</Typography>
+            <LinearProgress variant="determinate" value={progress.total === 0 ? 100 : progress.processed / progress.total * 100} />
+        </>}
+        <Button variant="outlined" color="warning" disabled={cancelling} onClick={() => {
+            setCancelling(true)
+            cancelTransfer()
+        }}>{cancelling ? "正在取消" : "取消"}</Button>
+    </Box>
+}
@@ -10,10 +10,42 @@ declare global {
 // Function avoids 'window not defined' in SSR
 const invoke = () => window.__TAURI_INVOKE__;
 
+export function databaseStatus() {
+    return invoke()<DatabaseStatus>("database_status")
+}
+
+export function recoverDatabase(recovery: Recovery) {
+    return invoke()<null>("recover_database", { recovery })
+}
+
+export function openDatabase(path: string) {
+    return invoke()<null>("open_database", { path })
+}
+
+export function createDatabase(path: string) {
+    return invoke()<null>("create_database", { path })
+}
+
+export function closeDatabase() {
+    return invoke()<null>("close_database")
+}
+
+export function currentDatabase() {
+    return invoke()<string | null>("current_database")
+}
+
+export function recentDatabases() {
+    return invoke()<string[]>("recent_databases")
+}
+
 export function resetDatabase() {
     return invoke()<null>("reset_database")
 }
 
+export function schemaReport() {
+    return invoke()<SchemaReport>("schema_report")
+}
+
 export function createStructure(name: string | null, formula: string, smiles: string | null, charge: number) {
     return invoke()<number>("create_structure", { name,formula,smiles,charge })
 }
@@ -42,23 +74,191 @@ export function setProperty(model: Property) {
     return invoke()<null>("set_property", { model })
 }
 
-export function searchStructure(pageSize: number, pageNumber: number, keyword: string | null, maxCharge: number, minCharge: number) {
-    return invoke()<[Structure[], number]>("search_structure", { pageSize,pageNumber,keyword,maxCharge,minCharge })
+export function searchStructure(keyword: string | null, maxCharge: number, minCharge: number, ranges: PropertyRange[], query: string | null, sort: SortKey[], cursor: string | null, limit: number) {
+    return invoke()<SearchPage>("search_structure", { keyword,maxCharge,minCharge,ranges,query,sort,cursor,limit })
 }
 
 export function getStructureDetail(id: number) {
     return invoke()<[Structure, Property | null, Image | null, ([Component, Structure | null])[], ([Component, Structure | null])[]]>("get_structure_detail", { id })
 }
 
-export function exportToFolder(folderPath: string) {
-    return invoke()<null>("export_to_folder", { folderPath })
+export function exportToFolder(folderPath: string, options: ExportOptions) {
+    return invoke()<number>("export_to_folder", { folderPath,options })
+}
+
+export function importFromFolder(folderPath: string, options: ImportOptions) {
+    return invoke()<null>("import_from_folder", { folderPath,options })
+}
+
+export function checkImport(folderPath: string, options: ImportOptions) {
+    return invoke()<ImportReport>("check_import", { folderPath,options })
+}
+
+export function cancelTransfer() {
+    return invoke()<null>("cancel_transfer")
+}
+
+export function importSdf(path: string, sdf: SdfOptions, options: ImportOptions) {
+    return invoke()<null>("import_sdf", { path,sdf,options })
+}
+
+export function exportSdf(path: string, sdf: SdfOptions) {
+    return invoke()<null>("export_sdf", { path,sdf })
+}
+
+export function importXlsx(path: string, options: ImportOptions) {
+    return invoke()<null>("import_xlsx", { path,options })
+}
+
+export function exportXlsx(path: string, xlsx: XlsxOptions) {
+    return invoke()<null>("export_xlsx", { path,xlsx })
+}
+
+export function importArchive(path: string, options: ImportOptions) {
+    return invoke()<null>("import_archive", { path,options })
+}
+
+export function checkArchive(path: string, options: ImportOptions) {
+    return invoke()<ArchiveReport>("check_archive", { path,options })
 }
 
-export function importFromFolder(folderPath: string) {
-    return invoke()<null>("import_from_folder", { folderPath })
+export function exportArchive(path: string) {
+    return invoke()<null>("export_archive", { path })
 }
 
 export type Component = { structure_id: number; component_id: number; count: number }
 export type Structure = { id: number; name: string | null; formula: string; smiles: string | null; charge: number }
-export type Property = { structure_id: number; decomp_temp: string | null; density: string | null; diss_temp: string | null; formation_enthalpy: string | null; impact_sensitive: string | null; friction_sensitivity: string | null; det_velocity: string | null; det_pressure: string | null; n_content: string | null; o_content: string | null; no_content: string | null; references: string | null; remarks: string | null }
+export type Property = { structure_id: number; decomp_temp: string | null; density: string | null; diss_temp: string | null; formation_enthalpy: string | null; impact_sensitive: string | null; friction_sensitivity: string | null; det_velocity: string | null; det_pressure: string | null; n_content: string | null; o_content: string | null; no_content: string | null; references: string | null; remarks: string | null; decomp_temp_value: number | null; density_value: number | null; diss_temp_value: number | null; formation_enthalpy_value: number | null; impact_sensitive_value: number | null; friction_sensitivity_value: number | null; det_velocity_value: number | null; det_pressure_value: number | null; n_content_value: number | null; o_content_value: number | null; no_content_value: number | null; value_note: string | null; decomp_temp_unit: string | null; density_unit: string | null; diss_temp_unit: string | null; formation_enthalpy_unit: string | null; impact_sensitive_unit: string | null; friction_sensitivity_unit: string | null; det_velocity_unit: string | null; det_pressure_unit: string | null; n_content_unit: string | null; o_content_unit: string | null; no_content_unit: string | null }
 export type Image = { structure_id: number; filename: string; image: number[] }
+/**
+ * 全部数据库操作的错误类型，前端可根据`kind`字段区分错误种类并自行生成提示信息
+ */
+export type ChembankError = { kind: "DbUnavailable"; message: string } | { kind: "NotFound"; table: Table; key: number[] } | { kind: "DuplicateName"; name: string } | { kind: "DuplicateSmiles"; smiles: string } | { kind: "StillReferencedAsComponent"; id: number; referenced_by: number[] } | { kind: "SchemaTooNew"; migrations: string[] } | { kind: "SchemaOutdated"; migrations: string[] } | { kind: "NotChembankDatabase" } | { kind: "Database"; message: string } | { kind: "Io"; path: string | null; message: string } | { kind: "CsvFormat"; file: string; line: number | null; column: string | null; message: string } | { kind: "SdfFormat"; file: string; line: number | null; message: string } | { kind: "XlsxFormat"; file: string; message: string } | { kind: "InvalidArchive"; path: string; message: string } | { kind: "InvalidImageFolder"; path: string; message: string } | { kind: "InvalidSearch"; input: string; message: string } | { kind: "InvalidRecord"; table: Table; message: string } | { kind: "PathNotAllowed"; path: string; message: string } | { kind: "ImportRecord"; file: string; line: number | null; cause: ChembankError } | { kind: "Cancelled" } | { kind: "QuerySyntax"; query: string; position: number; message: string }
+/**
+ * 错误所涉及的数据表
+ */
+export type Table = "Structure" | "Property" | "Image" | "Component"
+/**
+ * 数据库结构检查结果
+ */
+export type SchemaReport = { unexpected_tables: string[]; drifts: SchemaDrift[]; foreign: boolean; pending_migrations: string[] }
+/**
+ * 实体定义与数据库文件之间的一处差异
+ */
+export type SchemaDrift = { kind: "MissingTable"; table: string } | { kind: "MissingColumn"; table: string; column: string } | { kind: "ExtraColumn"; table: string; column: string } | { kind: "ColumnType"; table: string; column: string; expected: TypeAffinity; found: TypeAffinity } | { kind: "Nullability"; table: string; column: string; expected_not_null: boolean } | { kind: "PrimaryKey"; table: string; expected: string[]; found: string[] } | { kind: "MissingUnique"; table: string; column: string }
+/**
+ * SQLite的列类型亲和性，用于比较实体定义与数据库中实际的列类型
+ */
+export type TypeAffinity = "Integer" | "Text" | "Blob" | "Real" | "Numeric"
+/**
+ * 当前数据库的状态，启动时无法打开默认数据库不会导致程序退出，而是记录失败原因，
+ * 由前端引导用户打开其他文件、从备份恢复或新建数据库
+ */
+export type DatabaseStatus = { state: "Open"; path: string | null } | { state: "Closed" } | { state: "Failed"; path: string | null; error: ChembankError }
+/**
+ * 从无法打开的数据库中恢复的方式
+ */
+export type Recovery = { action: "RestoreBackup"; backup: string } | { action: "CreateNew" }
+/**
+ * 属性数值的检索范围，上下限均包含在内，省略表示不限。
+ * 比较的是换算为`Quantity::unit`后的数值，没有数值的结构不会被检索到
+ */
+export type PropertyRange = { quantity: Quantity; min: number | null; max: number | null }
+/**
+ * 可以按数值检索的属性。文本列保留用户输入的原始内容，数值列保存换算为`unit`后的数值
+ */
+export type Quantity = "DecompTemp" | "Density" | "DissTemp" | "FormationEnthalpy" | "ImpactSensitive" | "FrictionSensitivity" | "DetVelocity" | "DetPressure" | "NContent" | "OContent" | "NoContent"
+/**
+ * 检索到的结构，使用全文索引检索时附带匹配内容的摘要
+ */
+export type SearchHit = { structure: Structure; snippet: SnippetPart[] }
+/**
+ * 检索结果摘要中的一段文本，`matched`表示该段与关键词匹配，需要突出显示
+ */
+export type SnippetPart = { text: string; matched: boolean }
+/**
+ * 一页检索结果。`total`为满足条件的结构总数，`next_cursor`用于获取下一页，为空表示没有更多结果
+ */
+export type SearchPage = { hits: SearchHit[]; total: number; next_cursor: string | null }
+/**
+ * 排序键，`descending`为真时倒序。没有值的结构无论正序倒序都排在最后
+ */
+export type SortKey = { field: SortField; descending: boolean }
+/**
+ * 排序依据。`Relevance`为全文检索的相关度，只在关键词使用全文索引时有效，否则忽略
+ */
+export type SortField = "Relevance" | "Id" | "Name" | "Formula" | "Smiles" | "Charge" | { Property: Quantity }
+/**
+ * 导入前的检查结果，各表的数量为文件中可以解析的记录数。
+ * 没有错误且冲突都不会导致导入失败时，导入会按选择的方式写入这些记录
+ */
+export type ImportReport = { structures: number; properties: number; components: number; images: number; conflicts: ImportConflict[]; errors: ImportIssue[] }
+/**
+ * 待导入的记录与数据库中已有记录的冲突，`field`为冲突的列，`existing_id`为已有记录所属结构的ID
+ */
+export type ImportConflict = { file: string; line: number | null; table: Table; field: string; value: string; existing_id: number; resolution: Resolution }
+/**
+ * 导入前检查发现的一处错误，行号从1开始并包含表头行；图片和整个文件的错误没有行号
+ */
+export type ImportIssue = { file: string; line: number | null; column: string | null; message: string }
+/**
+ * 导入选项。除`Fail`以外的方式中，没有对应已有结构的导入结构尽量沿用原ID，
+ * ID已被占用时重新编号，属性、组成和图片中引用的ID随之改变
+ */
+export type ImportOptions = { mode?: ImportMode; match_by?: MatchBy; replace?: boolean }
+/**
+ * 导入的结构与已有结构相同时的处理方式
+ */
+export type ImportMode = "Fail" | "Skip" | "Overwrite" | "Merge" | "Renumber"
+/**
+ * 判断导入的结构与已有结构相同的依据
+ */
+export type MatchBy = "Id" | "Name" | "Smiles"
+/**
+ * 导出选项
+ */
+export type ExportOptions = { merged?: boolean; subset?: ExportSubset | null }
+/**
+ * 导入导出的进度，`processed`为当前阶段已处理的记录数
+ */
+export type Progress = { phase: Phase; processed: number; total: number }
+/**
+ * 导入导出的阶段
+ */
+export type Phase = "Structures" | "Properties" | "Components" | "Images"
+/**
+ * SD文件的导入导出选项
+ */
+export type SdfOptions = { fields?: { [key: string]: SdfField } }
+/**
+ * SD标签对应的字段
+ */
+export type SdfField = "Id" | "Name" | "Formula" | "Smiles" | "Charge" | { Property: Quantity } | "References" | "Remarks"
+/**
+ * Excel导出选项
+ */
+export type XlsxOptions = { images?: boolean }
+/**
+ * 归档的检查结果
+ */
+export type ArchiveReport = { manifest: Manifest; report: ImportReport }
+/**
+ * 归档中的清单文件`manifest.json`
+ */
+export type Manifest = { version: number; schema: string; exported_at: string; counts: ArchiveCounts; checksums: { [key: string]: string } }
+/**
+ * 归档中各表的记录数和图片数
+ */
+export type ArchiveCounts = { structures: number; properties: number; components: number; images: number }
+/**
+ * 导出的部分结构。这些结构直接或间接含有的组成部分总是一并导出，使导出的数据可以单独导入
+ */
+export type ExportSubset = { Ids: number[] } | { Filter: SearchFilter }
+/**
+ * 检索条件，含义与`Chembank::search_structure`的同名参数相同，省略的条件不限
+ */
+export type SearchFilter = { keyword: string | null; max_charge: number; min_charge: number; ranges: PropertyRange[]; query: string | null }
+/**
+ * 按选择的导入方式，冲突的记录在导入时的处理结果
+ */
+export type Resolution = "Error" | "Skipped" | "Overwritten" | "Merged" | "Renumbered"
@@ -0,0 +1,63 @@
+import { ChembankError, ImportIssue, Table } from "./bindings";
+
+const tableNames: Record<Table, string> = {
+    Structure: "结构",
+    Property: "属性",
+    Image: "图片",
+    Component: "组成",
+};
+
+export function describeError(e: unknown): string {
+    if (typeof e !== "object" || e === null || !("kind" in e)) {
+        return String(e);
+    }
+    const error = e as ChembankError;
+    switch (error.kind) {
+        case "DbUnavailable":
+            return `无法连接到数据库，请重启程序，如果该问题仍然发生，请联系管理员：${error.message}`;
+        case "NotFound":
+            return `没有找到对应的${tableNames[error.table]}记录（${error.key.join(", ")}），可能已经删除或未添加`;
+        case "DuplicateName":
+            return `名称“${error.name}”与已有结构重复`;
+        case "DuplicateSmiles":
+            return `SMILES“${error.smiles}”与已有结构重复`;
+        case "StillReferencedAsComponent":
+            return `该结构仍被作为结构${error.referenced_by.join(", ")}的组成部分存在，请检查删除相应结构后再删除此结构`;
+        case "SchemaTooNew":
+            return `该数据库由更新版本的程序创建，请升级程序后再打开，未知的迁移：${error.migrations.join(", ")}`;
+        case "SchemaOutdated":
+            return `该数据库的结构版本较旧，需要先升级才能读取，尚未执行的迁移：${error.migrations.join(", ")}`;
+        case "NotChembankDatabase":
+            return "该文件不是化合物数据库，为避免损坏其中的数据，禁止写入";
+        case "Database":
+            return `数据库故障，详细信息：${error.message}`;
+        case "Io":
+            return `无法读写文件${error.path ?? ""}：${error.message}`;
+        case "CsvFormat":
+            return `表格${error.file}格式不正确${error.line === null ? "" : `，第${error.line}行`}${error.column === null ? "" : `，${error.column}列`}：${error.message}`;
+        case "SdfFormat":
+            return `SD文件${error.file}格式不正确${error.line === null ? "" : `，第${error.line}行`}：${error.message}`;
+        case "XlsxFormat":
+            return `Excel工作簿${error.file}格式不正确：${error.message}`;
+        case "InvalidArchive":
+            return `归档${error.path}无法导入：${error.message}`;
+        case "InvalidImageFolder":
+            return `图片目录${error.path}不正确：${error.message}`;
+        case "InvalidSearch":
+            return `检索条件“${error.input}”不正确：${error.message}`;
+        case "InvalidRecord":
+            return `${error.table}表的记录格式不正确：${error.message}`;
+        case "PathNotAllowed":
+            return `不能访问服务器上的路径${error.path}：${error.message}`;
+        case "ImportRecord":
+            return `导入${error.file}${error.line === null ? "" : `第${error.line}行`}时出错，已撤销本次导入：${describeError(error.cause)}`;
+        case "Cancelled":
+            return "操作已取消";
+        case "QuerySyntax":
+            return `检索表达式第${error.position + 1}个字符处有误：${error.message}`;
+    }
+}
+
+export function describeIssue(issue: ImportIssue): string {
+    return `${issue.file}${issue.line === null ? "" : `第${issue.line}行`}${issue.column === null ? "" : `${issue.column}列`}：${issue.message}`;
+}
@@ -8,6 +8,8 @@ import Home from "./Home";
 import "./main.css";
 import StructureView from "./StructureView";
 import ImportDataPage from "./Import";
+import ImportFilePage from "./ImportFile";
+import RecoveryView from "./Recovery";
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
@@ -21,6 +23,8 @@ ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
           <Route path="/component" element={<ComponentView />}></Route>
           <Route path="/export" element={<ExportView />}></Route>
           <Route path="/import" element={<ImportDataPage />}></Route>
+          <Route path="/import-file" element={<ImportFilePage />}></Route>
+          <Route path="/recovery" element={<RecoveryView />}></Route>
         </Routes>
       </Container>
     </BrowserRouter>
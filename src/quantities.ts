@@ -0,0 +1,18 @@
+import { Quantity } from "./bindings";
+
+/**
+ * 可以按数值检索的属性的名称和单位，与后端`Quantity::label`和`Quantity::unit`一致
+ */
+export const quantities: Record<Quantity, { label: string, unit: string }> = {
+    DecompTemp: { label: "分解温度", unit: "℃" },
+    Density: { label: "密度", unit: "g·cm-3" },
+    DissTemp: { label: "热熔解温度", unit: "℃" },
+    FormationEnthalpy: { label: "生成焓", unit: "kJ·mol-1" },
+    ImpactSensitive: { label: "撞击感度", unit: "J" },
+    FrictionSensitivity: { label: "摩擦感度", unit: "N" },
+    DetVelocity: { label: "爆速", unit: "m·s-1" },
+    DetPressure: { label: "爆压", unit: "GPa" },
+    NContent: { label: "氮含量", unit: "%" },
+    OContent: { label: "氧含量", unit: "%" },
+    NoContent: { label: "氮氧含量", unit: "%" },
+};